@@ -3,6 +3,7 @@ use annotate_snippets::{
     snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation},
 };
 
+use crate::error::DiagnosticSpan;
 use crate::syntax::{ParsedSpan, Span};
 
 #[derive(Debug, Clone, Default)]
@@ -114,7 +115,25 @@ impl ErrorBuilder {
         self.footer_annot(message, AnnotationType::Note)
     }
 
-    // TODO: handle multiple files
+    /// The annotated locations, for machine-readable diagnostics. Error annotations are primary.
+    pub fn diagnostic_spans(&self) -> Vec<DiagnosticSpan> {
+        self.annotations
+            .iter()
+            .map(|annot| DiagnosticSpan {
+                range: annot.span.range(),
+                is_primary: annot.annotation_type == AnnotationType::Error,
+                label: Some(annot.message.clone()),
+            })
+            .collect()
+    }
+    /// The messages of the footer annotations.
+    pub fn notes(&self) -> Vec<String> {
+        self.footer
+            .iter()
+            .map(|annot| annot.message.clone())
+            .collect()
+    }
+
     #[allow(clippy::drop_ref)]
     pub fn format(&mut self) -> String {
         if self.consumed {
@@ -124,23 +143,32 @@ impl ErrorBuilder {
         self.consumed = true;
         drop(self); // Get rid of the self reference so we don't use it by mistake.
 
-        let slices = if this.annotations.is_empty() {
-            Vec::new()
-        } else {
-            let input = this.annotations[0].span.to_input();
-            let annotations = this
-                .annotations
-                .into_iter()
-                .map(|annot| annot.into_annotation())
-                .collect();
-            vec![Slice {
-                source: input,
-                line_start: 1, // TODO
-                origin: Some("<current file>".to_string()),
+        // Annotations are grouped by source file, in order of first appearance.
+        let mut files: Vec<(ParsedSpan, Vec<SourceAnnotation>)> = Vec::new();
+        for annot in this.annotations {
+            match files
+                .iter_mut()
+                .find(|(span, _)| span.same_source(&annot.span))
+            {
+                Some((_, annotations)) => {
+                    annotations.push(annot.into_annotation())
+                }
+                None => files
+                    .push((annot.span.clone(), vec![annot.into_annotation()])),
+            }
+        }
+        let slices = files
+            .into_iter()
+            .map(|(span, annotations)| Slice {
+                source: span.to_input(),
+                line_start: 1,
+                origin: Some(
+                    span.origin().unwrap_or("<current file>").to_string(),
+                ),
                 fold: true,
                 annotations,
-            }]
-        };
+            })
+            .collect();
         let footer = this
             .footer
             .into_iter()
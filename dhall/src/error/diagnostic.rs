@@ -0,0 +1,149 @@
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::error::{Error, ErrorKind, ImportError};
+use crate::syntax::SourceRange;
+
+/// A machine-readable description of an error, modeled after rustc's `--error-format=json`.
+/// Serialize it with serde to report errors to other tools.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Identifies the kind of error, e.g. `type/MissingRecordField` or `import/Missing`.
+    pub code: String,
+    pub message: String,
+    pub spans: Vec<DiagnosticSpan>,
+    pub notes: Vec<String>,
+    /// The errors that caused this one, e.g. the error of each side of an import alternative.
+    pub children: Vec<Diagnostic>,
+    /// The error as displayed to humans.
+    pub rendered: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+/// A location pointed to by a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticSpan {
+    #[serde(flatten)]
+    pub range: SourceRange,
+    /// Whether this is where the error happened, as opposed to related locations.
+    pub is_primary: bool,
+    pub label: Option<String>,
+}
+
+impl Error {
+    /// Describe this error for consumption by other tools.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let mut spans = Vec::new();
+        let mut notes = Vec::new();
+        let mut children = Vec::new();
+        let (code, message) = match self.kind() {
+            ErrorKind::IO(err) => ("io".to_string(), err.to_string()),
+            ErrorKind::Parse(err) => {
+                // pest only exposes the message as the last line of the rendered error.
+                let rendered = err.to_string();
+                let message = rendered
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .trim_start()
+                    .trim_start_matches("= ");
+                ("parse".to_string(), message.to_string())
+            }
+            ErrorKind::Decode(err) => {
+                let code = format!("decode/{}", err.code());
+                (code, err.to_string())
+            }
+            ErrorKind::Encode(err) => ("encode".to_string(), err.to_string()),
+            ErrorKind::Resolve(err) => {
+                if let ImportError::AlternativesFailed(errs) = err {
+                    children = errs.iter().map(Error::to_diagnostic).collect();
+                }
+                let code = format!("import/{}", err.code());
+                (code, err.to_string())
+            }
+            ErrorKind::Typecheck(err) => {
+                spans = err.spans().to_vec();
+                notes = err.notes().to_vec();
+                let message = err.message();
+                let code = format!("type/{}", message.code());
+                (code, message.to_string())
+            }
+            ErrorKind::Cache(err) => {
+                let code = format!("cache/{}", err.code());
+                (code, err.to_string())
+            }
+            ErrorKind::Convert(err) => {
+                let code = format!("convert/{}", err.code());
+                (code, err.to_string())
+            }
+        };
+        if spans.is_empty() {
+            spans.extend(self.range().map(|range| DiagnosticSpan {
+                range,
+                is_primary: true,
+                label: None,
+            }));
+        }
+        if !self.import_stack().is_empty() {
+            notes.push(format!(
+                "while importing {}",
+                self.import_stack().iter().format(" → ")
+            ));
+        }
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            spans,
+            notes,
+            children,
+            rendered: self.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::Error;
+    use crate::semantics::parse::parse_str;
+
+    #[test]
+    fn type_errors_should_convert_to_diagnostics() -> Result<(), Error> {
+        let err = parse_str("let r = { y = 1 }\nin  r.x")?
+            .skip_resolve()?
+            .typecheck()
+            .unwrap_err();
+        let diagnostic = Error::from(err).to_diagnostic();
+        assert_eq!(diagnostic.code, "type/MissingRecordField");
+        assert_eq!(diagnostic.message, "missing record field `x`");
+        let primary = diagnostic.spans.iter().find(|s| s.is_primary).unwrap();
+        assert_eq!(primary.range.file, None);
+        assert_eq!(
+            (primary.range.start.line, primary.range.start.column),
+            (2, 5)
+        );
+
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["spans"][0]["start"]["line"], 2);
+        assert_eq!(json["spans"][0]["is_primary"], true);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors_should_convert_to_diagnostics() {
+        let diagnostic = parse_str("{ x = }").unwrap_err().to_diagnostic();
+        assert_eq!(diagnostic.code, "parse");
+        assert!(diagnostic.message.starts_with("expected"));
+        assert_eq!(diagnostic.spans[0].range.start.column, 7);
+    }
+}
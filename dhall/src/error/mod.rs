@@ -1,16 +1,33 @@
+use itertools::Itertools;
 use std::io::Error as IOError;
+use std::sync::Arc;
 
 use crate::semantics::resolve::{ImportLocation, ImportStack};
-use crate::syntax::{Import, ParseError};
+use crate::syntax::{
+    Expr, Hash, Import, Label, ParseError, SourcePosition, SourceRange, Span, V,
+};
 
 mod builder;
 pub use builder::*;
+mod diagnostic;
+pub use diagnostic::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
+    /// The chain of imports being resolved when the error happened, outermost first.
+    import_stack: ImportStack,
+    /// The expression that failed, for errors that don't carry a location.
+    location: Option<Box<ErrorLocation>>,
+}
+
+#[derive(Debug)]
+struct ErrorLocation {
+    range: SourceRange,
+    /// The error rendered with the source code it points to.
+    formatted: String,
 }
 
 #[derive(Debug)]
@@ -23,6 +40,7 @@ pub enum ErrorKind {
     Resolve(ImportError),
     Typecheck(TypeError),
     Cache(CacheError),
+    Convert(ConvertError),
 }
 
 #[derive(Debug)]
@@ -30,9 +48,25 @@ pub enum ImportError {
     Missing,
     MissingEnvVar,
     SanityCheck,
+    CorsCheck,
+    RemoteImportsDisabled,
+    EnvImportsDisabled,
     UnexpectedImport(Import<()>),
     ImportCycle(ImportStack, ImportLocation),
     Url(url::ParseError),
+    /// Could not connect to the remote server, or the connection failed; carries the underlying
+    /// error, shared so that the error can be copied
+    Connection(Arc<dyn std::error::Error + Send + Sync>),
+    /// The remote server responded with a non-2xx status code
+    HttpStatus(u16),
+    /// The request for a remote import timed out
+    Timeout,
+    /// The contents of a remote import are not valid UTF-8
+    InvalidUtf8,
+    /// The contents of a remote import are larger than the configured maximum
+    BodyTooLarge,
+    /// Every side of an import alternative failed; carries the error of each side, in order
+    AlternativesFailed(Vec<Error>),
 }
 
 #[derive(Debug)]
@@ -50,12 +84,118 @@ pub enum EncodeError {
 #[derive(Debug)]
 pub struct TypeError {
     message: TypeMessage,
+    /// The error rendered with the source code it points to.
+    formatted: String,
+    /// The locations the error points to.
+    spans: Vec<DiagnosticSpan>,
+    notes: Vec<String>,
 }
 
-/// The specific type error
-#[derive(Debug)]
+/// The specific type error. Types are given as expressions in normal form.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum TypeMessage {
-    Custom(String),
+    /// A variable that is not in scope
+    UnboundVariable(V),
+    /// `Sort` has no type
+    Untyped,
+    /// An expression in type position that is not a type; carries its value
+    NotAType(Expr),
+    /// The body of a function is neither a term, a type nor a kind
+    InvalidOutputType,
+    /// The type of an expression differs from its annotation
+    AnnotMismatch { expected: Expr, found: Expr },
+    /// A function argument doesn't have the type the function expects
+    TypeMismatch { expected: Expr, found: Expr },
+    /// Application of something that is not a function; carries its type
+    NotAFunction(Expr),
+    /// An interpolated expression that is not `Text`
+    InvalidTextInterpolation,
+    /// `Some` applied to something that is not a term
+    InvalidOptionalType,
+    /// A list whose elements are not terms, or an empty list annotated with something that is
+    /// not `List T`
+    InvalidListType,
+    /// A list element whose type differs from the type of the first element
+    InvalidListElement { expected: Expr, found: Expr },
+    /// A record field that is neither a term, a type nor a kind
+    InvalidFieldType(Label),
+    /// A union alternative that is neither a type nor a kind
+    InvalidVariantType(Label),
+    /// An assertion whose sides are not equivalent; carries the normalized sides
+    AssertMismatch { expected: Expr, found: Expr },
+    /// An assertion whose type is not an equivalence
+    AssertMustTakeEquivalence,
+    /// `⫽` applied to something that is not a record
+    MustCombineRecord,
+    /// `∧` or `⩓` applied to something that is not a record, possibly in a nested field
+    RecordTypeMergeRequiresRecordType,
+    /// An operand whose type differs from the type the operator expects
+    BinOpTypeMismatch { expected: Expr, found: Expr },
+    /// `#` applied to something that is not a list; carries its type
+    MustAppendList(Expr),
+    /// The sides of `===` have different types
+    EquivalenceTypeMismatch { expected: Expr, found: Expr },
+    /// The sides of `===` are not terms
+    EquivalenceArgumentsMustBeTerms,
+    /// The condition of an `if` is not a `Bool`
+    InvalidPredicate,
+    /// The branches of an `if` are not terms
+    IfBranchMustBeTerm,
+    /// The branches of an `if` have different types
+    IfBranchMismatch { expected: Expr, found: Expr },
+    /// The first argument of `merge` is not a record
+    Merge1ArgMustBeRecord,
+    /// The second argument of `merge` is neither a union nor an `Optional`
+    Merge2ArgMustBeUnionOrOptional,
+    /// The handler for `variant` expects an input of the wrong type
+    MergeHandlerWrongInputType {
+        variant: Label,
+        expected: Expr,
+        found: Expr,
+    },
+    /// The handler for `variant` is not a function; carries its type
+    MergeHandlerNotAFunction { variant: Label, found: Expr },
+    /// The output type of a handler depends on its input
+    MergeReturnTypeIsDependent,
+    /// A handler for an alternative that the union doesn't have
+    MergeHandlerMissingVariant(Label),
+    /// A handler whose output type differs from the output type of the handlers before it
+    MergeHandlerTypeMismatch { expected: Expr, found: Expr },
+    /// An alternative of the union that has no handler
+    MergeVariantMissingHandler(Label),
+    /// The type of a `merge` differs from its annotation
+    MergeAnnotMismatch { expected: Expr, found: Expr },
+    /// A `merge` of an empty union without an annotation
+    MergeEmptyNeedsAnnotation,
+    /// `toMap` applied to a record of types or kinds
+    InvalidToMapRecordKind,
+    /// `toMap` applied to something that is not a record
+    MustMapARecord,
+    /// `toMap` applied to an empty record without an annotation
+    MissingToMapType,
+    /// A `toMap` annotation that is not of the form `List { mapKey : Text, mapValue : T }`
+    InvalidToMapType,
+    /// `toMap` applied to a record whose fields have different types
+    HeterogenousRecordToMap,
+    /// The type of a `toMap` differs from its annotation
+    ToMapAnnotMismatch { expected: Expr, found: Expr },
+    /// Access to a field that the record doesn't have
+    MissingRecordField(Label),
+    /// Access to an alternative that the union doesn't have
+    MissingUnionField(Label),
+    /// Field access on something that is neither a record nor a union type
+    NotARecord,
+    /// Projection of something that is not a record
+    ProjectionMustBeRecord,
+    /// Projection of a field that the record doesn't have
+    ProjectionMissingEntry(Label),
+    /// Projection by an expression that is not a record type
+    ProjectionByExprTakesRecordType,
+    /// Projection by a record type whose field differs in type from the field of the record
+    ProjectionWrongType(Label),
+    /// An import whose semantic hash differs from the hash it is annotated with
+    HashMismatch { expected: Hash, found: Hash },
 }
 
 #[derive(Debug)]
@@ -65,28 +205,449 @@ pub enum CacheError {
     CacheHashInvalid,
 }
 
+/// A value that can't be converted from or to another format, like JSON. Values and types are
+/// given as text, and locations in JSON values as paths like `.servers[0].port`.
+///
+/// Some variants only exist with the `json` or `yaml` features, so matching on this type needs a
+/// wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// A function, a type, or another value that has no counterpart in the format
+    Unsupported(String),
+    /// A `Double` that is not a finite number
+    SpecialDouble(String),
+    /// The input is not valid JSON
+    #[cfg(feature = "json")]
+    InvalidJson(serde_json::Error),
+    /// The input is not valid YAML
+    #[cfg(feature = "yaml")]
+    InvalidYaml(serde_yaml::Error),
+    /// A JSON value that doesn't match the expected Dhall type
+    JsonMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    /// A field required by the expected record type is missing from a JSON object
+    MissingField(String),
+    /// A JSON object has a field that the expected record type doesn't have
+    UnexpectedField(String),
+    /// An array element whose type differs from the elements before it
+    HeterogeneousList(String),
+    /// Only `null` or empty arrays were found, so the type can't be inferred
+    CannotInfer(String),
+}
+
 impl Error {
     pub fn new(kind: ErrorKind) -> Self {
-        Error { kind }
+        Error {
+            kind,
+            import_stack: ImportStack::new(),
+            location: None,
+        }
     }
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
     }
+    /// The chain of imports being resolved when the error happened, outermost first. Empty if
+    /// the error did not happen inside an import.
+    pub fn import_stack(&self) -> &[ImportLocation] {
+        &self.import_stack
+    }
+    /// Record the imports being resolved, unless an inner import already did.
+    pub(crate) fn in_imports(mut self, stack: &[ImportLocation]) -> Self {
+        if self.import_stack.is_empty() {
+            self.import_stack = stack.to_vec();
+        }
+        self
+    }
+    /// A parse error in the text read from `origin`, i.e. a path, url or environment variable.
+    pub(crate) fn parse_error_at(
+        err: ParseError,
+        origin: Option<&str>,
+    ) -> Self {
+        let mut range = parse_error_range(&err);
+        range.file = origin.map(str::to_owned);
+        let formatted = err.to_string();
+        let mut error = Error::from(err);
+        error.location = Some(Box::new(ErrorLocation { range, formatted }));
+        error
+    }
+    /// The location of the error in the source code, if known.
+    pub fn range(&self) -> Option<SourceRange> {
+        if let Some(location) = &self.location {
+            return Some(location.range.clone());
+        }
+        match &self.kind {
+            ErrorKind::Parse(err) => Some(parse_error_range(err)),
+            ErrorKind::Typecheck(err) => err.range().cloned(),
+            _ => None,
+        }
+    }
+    /// The errors of the sides of a failed import alternative, with the span of the side. Nested
+    /// alternatives are flattened, so that `a ? b ? c` reports three errors.
+    pub(crate) fn into_alternatives(self, span: &Span) -> Vec<Error> {
+        match self.kind {
+            ErrorKind::Resolve(ImportError::AlternativesFailed(errs)) => errs,
+            _ => vec![self.at_span(span)],
+        }
+    }
+    /// Record the expression that failed, unless the error already has a location. Failed
+    /// import alternatives point to each of their sides instead.
+    pub(crate) fn at_span(mut self, span: &Span) -> Self {
+        let alternatives = matches!(
+            self.kind,
+            ErrorKind::Resolve(ImportError::AlternativesFailed(_))
+        );
+        if self.range().is_some() || alternatives {
+            return self;
+        }
+        if let Some(range) = span.range() {
+            let message = self.kind.to_string();
+            let formatted = ErrorBuilder::new(&message)
+                .span_err(span.clone(), &message)
+                .format();
+            self.location = Some(Box::new(ErrorLocation { range, formatted }));
+        }
+        self
+    }
+}
+
+/// The location of a parse error, without the file.
+fn parse_error_range(err: &ParseError) -> SourceRange {
+    use pest::error::LineColLocation;
+    let ((line, column), end) = match err.line_col {
+        LineColLocation::Pos(start) => (start, start),
+        LineColLocation::Span(start, end) => (start, end),
+    };
+    SourceRange {
+        file: None,
+        start: SourcePosition { line, column },
+        end: SourcePosition {
+            line: end.0,
+            column: end.1,
+        },
+    }
 }
 
 impl TypeError {
     pub fn new(message: TypeMessage) -> Self {
-        TypeError { message }
+        let formatted = message.to_string();
+        TypeError::with_formatted(message, formatted)
+    }
+    /// An error that displays as `formatted`.
+    pub fn with_formatted(message: TypeMessage, formatted: String) -> Self {
+        TypeError {
+            message,
+            formatted,
+            spans: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+    /// An error that displays as the output of `builder`, and points to its annotations.
+    pub fn from_builder(
+        message: TypeMessage,
+        builder: &mut ErrorBuilder,
+    ) -> Self {
+        TypeError {
+            message,
+            spans: builder.diagnostic_spans(),
+            notes: builder.notes(),
+            formatted: builder.format(),
+        }
+    }
+    pub fn message(&self) -> &TypeMessage {
+        &self.message
+    }
+    /// The location of the error in the source code, if known.
+    pub fn range(&self) -> Option<&SourceRange> {
+        self.spans
+            .iter()
+            .find(|span| span.is_primary)
+            .map(|span| &span.range)
+    }
+    pub fn spans(&self) -> &[DiagnosticSpan] {
+        &self.spans
+    }
+    pub fn notes(&self) -> &[String] {
+        &self.notes
     }
 }
 
-impl std::fmt::Display for TypeError {
+impl TypeMessage {
+    /// A stable name for this kind of type error, e.g. `MissingRecordField`. Used as the `type/…`
+    /// code of diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeMessage::UnboundVariable(..) => "UnboundVariable",
+            TypeMessage::Untyped => "Untyped",
+            TypeMessage::NotAType(..) => "NotAType",
+            TypeMessage::InvalidOutputType => "InvalidOutputType",
+            TypeMessage::AnnotMismatch { .. } => "AnnotMismatch",
+            TypeMessage::TypeMismatch { .. } => "TypeMismatch",
+            TypeMessage::NotAFunction(..) => "NotAFunction",
+            TypeMessage::InvalidTextInterpolation => "InvalidTextInterpolation",
+            TypeMessage::InvalidOptionalType => "InvalidOptionalType",
+            TypeMessage::InvalidListType => "InvalidListType",
+            TypeMessage::InvalidListElement { .. } => "InvalidListElement",
+            TypeMessage::InvalidFieldType(..) => "InvalidFieldType",
+            TypeMessage::InvalidVariantType(..) => "InvalidVariantType",
+            TypeMessage::AssertMismatch { .. } => "AssertMismatch",
+            TypeMessage::AssertMustTakeEquivalence => {
+                "AssertMustTakeEquivalence"
+            }
+            TypeMessage::MustCombineRecord => "MustCombineRecord",
+            TypeMessage::RecordTypeMergeRequiresRecordType => {
+                "RecordTypeMergeRequiresRecordType"
+            }
+            TypeMessage::BinOpTypeMismatch { .. } => "BinOpTypeMismatch",
+            TypeMessage::MustAppendList(..) => "MustAppendList",
+            TypeMessage::EquivalenceTypeMismatch { .. } => {
+                "EquivalenceTypeMismatch"
+            }
+            TypeMessage::EquivalenceArgumentsMustBeTerms => {
+                "EquivalenceArgumentsMustBeTerms"
+            }
+            TypeMessage::InvalidPredicate => "InvalidPredicate",
+            TypeMessage::IfBranchMustBeTerm => "IfBranchMustBeTerm",
+            TypeMessage::IfBranchMismatch { .. } => "IfBranchMismatch",
+            TypeMessage::Merge1ArgMustBeRecord => "Merge1ArgMustBeRecord",
+            TypeMessage::Merge2ArgMustBeUnionOrOptional => {
+                "Merge2ArgMustBeUnionOrOptional"
+            }
+            TypeMessage::MergeHandlerWrongInputType { .. } => {
+                "MergeHandlerWrongInputType"
+            }
+            TypeMessage::MergeHandlerNotAFunction { .. } => {
+                "MergeHandlerNotAFunction"
+            }
+            TypeMessage::MergeReturnTypeIsDependent => {
+                "MergeReturnTypeIsDependent"
+            }
+            TypeMessage::MergeHandlerMissingVariant(..) => {
+                "MergeHandlerMissingVariant"
+            }
+            TypeMessage::MergeHandlerTypeMismatch { .. } => {
+                "MergeHandlerTypeMismatch"
+            }
+            TypeMessage::MergeVariantMissingHandler(..) => {
+                "MergeVariantMissingHandler"
+            }
+            TypeMessage::MergeAnnotMismatch { .. } => "MergeAnnotMismatch",
+            TypeMessage::MergeEmptyNeedsAnnotation => {
+                "MergeEmptyNeedsAnnotation"
+            }
+            TypeMessage::InvalidToMapRecordKind => "InvalidToMapRecordKind",
+            TypeMessage::MustMapARecord => "MustMapARecord",
+            TypeMessage::MissingToMapType => "MissingToMapType",
+            TypeMessage::InvalidToMapType => "InvalidToMapType",
+            TypeMessage::HeterogenousRecordToMap => "HeterogenousRecordToMap",
+            TypeMessage::ToMapAnnotMismatch { .. } => "ToMapAnnotMismatch",
+            TypeMessage::MissingRecordField(..) => "MissingRecordField",
+            TypeMessage::MissingUnionField(..) => "MissingUnionField",
+            TypeMessage::NotARecord => "NotARecord",
+            TypeMessage::ProjectionMustBeRecord => "ProjectionMustBeRecord",
+            TypeMessage::ProjectionMissingEntry(..) => "ProjectionMissingEntry",
+            TypeMessage::ProjectionByExprTakesRecordType => {
+                "ProjectionByExprTakesRecordType"
+            }
+            TypeMessage::ProjectionWrongType(..) => "ProjectionWrongType",
+            TypeMessage::HashMismatch { .. } => "HashMismatch",
+        }
+    }
+}
+
+impl std::fmt::Display for TypeMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         use TypeMessage::*;
-        let msg = match &self.message {
-            Custom(s) => format!("Type error: {}", s),
-        };
-        write!(f, "{}", msg)
+        match self {
+            UnboundVariable(var) => write!(f, "unbound variable `{}`", var),
+            Untyped => write!(f, "Sort does not have a type"),
+            NotAType(expr) => write!(f, "Expected a type, found: `{}`", expr),
+            InvalidOutputType => write!(f, "Invalid output type"),
+            AnnotMismatch { expected, found } => {
+                write!(f, "annot mismatch: {} != {}", found, expected)
+            }
+            TypeMismatch { expected, found } => write!(
+                f,
+                "wrong type of function argument: expected `{}`, found `{}`",
+                expected, found
+            ),
+            NotAFunction(ty) => write!(f, "expected function, found `{}`", ty),
+            MergeHandlerWrongInputType {
+                variant,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the handler for `{}` expects a value of type `{}`, found `{}`",
+                variant, expected, found
+            ),
+            MergeHandlerNotAFunction { variant, found } => write!(
+                f,
+                "the handler for `{}` must be a function, found a value of \
+                 type `{}`",
+                variant, found
+            ),
+            InvalidToMapRecordKind => {
+                write!(f, "`toMap` only accepts records of type `Type`")
+            }
+            MustMapARecord => {
+                write!(f, "The argument to `toMap` must be a record")
+            }
+            MissingToMapType => write!(
+                f,
+                "`toMap` applied to an empty record requires a type annotation"
+            ),
+            InvalidToMapType => write!(
+                f,
+                "The type of `toMap x` must be of the form \
+                 `List {{ mapKey : Text, mapValue : T }}`"
+            ),
+            HeterogenousRecordToMap => {
+                write!(f, "Every field of the record must have the same type")
+            }
+            ToMapAnnotMismatch { expected, found } => write!(
+                f,
+                "`toMap` annotation mismatch: expected `{}`, found `{}`",
+                expected, found
+            ),
+            InvalidTextInterpolation => {
+                write!(f, "interpolated expressions must have type `Text`")
+            }
+            InvalidOptionalType => {
+                write!(f, "the argument of `Some` must be a term")
+            }
+            InvalidListType => write!(
+                f,
+                "the type of a list must be of the form `List T`, where `T` \
+                 is a type"
+            ),
+            InvalidListElement { expected, found } => write!(
+                f,
+                "the elements of a list must have the same type: expected \
+                 `{}`, found `{}`",
+                expected, found
+            ),
+            InvalidFieldType(x) => {
+                write!(f, "the field `{}` must be a term, a type or a kind", x)
+            }
+            InvalidVariantType(x) => {
+                write!(f, "the alternative `{}` must be a type or a kind", x)
+            }
+            AssertMismatch { expected, found } => write!(
+                f,
+                "assertion failed: `{}` is not equivalent to `{}`",
+                expected, found
+            ),
+            AssertMustTakeEquivalence => {
+                write!(f, "`assert` expects an equivalence `x === y`")
+            }
+            MustCombineRecord => write!(f, "`⫽` can only combine records"),
+            RecordTypeMergeRequiresRecordType => {
+                write!(f, "only records can be merged recursively")
+            }
+            BinOpTypeMismatch { expected, found } => write!(
+                f,
+                "wrong type of operand: expected `{}`, found `{}`",
+                expected, found
+            ),
+            MustAppendList(ty) => write!(
+                f,
+                "`#` can only append lists, found a value of type `{}`",
+                ty
+            ),
+            EquivalenceTypeMismatch { expected, found } => write!(
+                f,
+                "the sides of `===` must have the same type: `{}` and `{}`",
+                expected, found
+            ),
+            EquivalenceArgumentsMustBeTerms => {
+                write!(f, "the sides of `===` must be terms")
+            }
+            InvalidPredicate => {
+                write!(f, "the condition of an `if` must be a `Bool`")
+            }
+            IfBranchMustBeTerm => {
+                write!(f, "the branches of an `if` must be terms")
+            }
+            IfBranchMismatch { expected, found } => write!(
+                f,
+                "the branches of an `if` must have the same type: `{}` and \
+                 `{}`",
+                expected, found
+            ),
+            Merge1ArgMustBeRecord => {
+                write!(f, "the first argument of `merge` must be a record")
+            }
+            Merge2ArgMustBeUnionOrOptional => write!(
+                f,
+                "the second argument of `merge` must be a union or an \
+                 `Optional`"
+            ),
+            MergeReturnTypeIsDependent => write!(
+                f,
+                "the output type of a `merge` handler must not depend on its \
+                 input"
+            ),
+            MergeHandlerMissingVariant(x) => {
+                write!(
+                    f,
+                    "there is a handler for the missing alternative `{}`",
+                    x
+                )
+            }
+            MergeHandlerTypeMismatch { expected, found } => write!(
+                f,
+                "the handlers of a `merge` must have the same output type: \
+                 expected `{}`, found `{}`",
+                expected, found
+            ),
+            MergeVariantMissingHandler(x) => {
+                write!(f, "missing handler for the alternative `{}`", x)
+            }
+            MergeAnnotMismatch { expected, found } => write!(
+                f,
+                "`merge` annotation mismatch: expected `{}`, found `{}`",
+                expected, found
+            ),
+            MergeEmptyNeedsAnnotation => {
+                write!(f, "an empty `merge` requires a type annotation")
+            }
+            MissingRecordField(x) => write!(f, "missing record field `{}`", x),
+            MissingUnionField(x) => {
+                write!(f, "missing union alternative `{}`", x)
+            }
+            NotARecord => {
+                write!(f, "only records and union types have fields")
+            }
+            ProjectionMustBeRecord => {
+                write!(f, "only records can be projected")
+            }
+            ProjectionMissingEntry(x) => {
+                write!(f, "cannot project the missing field `{}`", x)
+            }
+            ProjectionByExprTakesRecordType => {
+                write!(f, "a projection by type expects a record type")
+            }
+            ProjectionWrongType(x) => write!(
+                f,
+                "the field `{}` does not have the type of the projection",
+                x
+            ),
+            HashMismatch { expected, found } => write!(
+                f,
+                "hash mismatch: expected `{}`, found `{}`",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Type error: {}", self.formatted)
     }
 }
 
@@ -101,23 +662,320 @@ impl std::fmt::Display for EncodeError {
     }
 }
 
-impl std::error::Error for EncodeError {}
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::CBORError(e) => Some(e),
+        }
+    }
+}
 
-impl std::fmt::Display for Error {
+impl DecodeError {
+    /// A stable name for this kind of decoding error, used as the `decode/…` code of diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DecodeError::CBORError(..) => "CBORError",
+            DecodeError::WrongFormatError(..) => "WrongFormatError",
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &self.kind {
+        match self {
+            DecodeError::CBORError(e) => write!(f, "Decode error: {}", e),
+            DecodeError::WrongFormatError(what) => {
+                write!(f, "Decode error: invalid encoding of {}", what)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::CBORError(e) => Some(e),
+            DecodeError::WrongFormatError(_) => None,
+        }
+    }
+}
+
+impl ImportError {
+    /// A stable name for this kind of import error, used as the `import/…` code of diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ImportError::Missing => "Missing",
+            ImportError::MissingEnvVar => "MissingEnvVar",
+            ImportError::SanityCheck => "SanityCheck",
+            ImportError::CorsCheck => "CorsCheck",
+            ImportError::RemoteImportsDisabled => "RemoteImportsDisabled",
+            ImportError::EnvImportsDisabled => "EnvImportsDisabled",
+            ImportError::UnexpectedImport(..) => "UnexpectedImport",
+            ImportError::ImportCycle(..) => "ImportCycle",
+            ImportError::Url(..) => "Url",
+            ImportError::Connection(..) => "Connection",
+            ImportError::HttpStatus(..) => "HttpStatus",
+            ImportError::Timeout => "Timeout",
+            ImportError::InvalidUtf8 => "InvalidUtf8",
+            ImportError::BodyTooLarge => "BodyTooLarge",
+            ImportError::AlternativesFailed(..) => "AlternativesFailed",
+        }
+    }
+
+    /// A copy of an error from a download, so that it can be reported again for each import of
+    /// the same url. The other errors can't be copied.
+    pub fn copy_download_error(&self) -> Option<ImportError> {
+        Some(match self {
+            ImportError::Url(err) => ImportError::Url(*err),
+            ImportError::Connection(err) => {
+                ImportError::Connection(err.clone())
+            }
+            ImportError::HttpStatus(status) => ImportError::HttpStatus(*status),
+            ImportError::Timeout => ImportError::Timeout,
+            ImportError::InvalidUtf8 => ImportError::InvalidUtf8,
+            ImportError::BodyTooLarge => ImportError::BodyTooLarge,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ImportError::*;
+        write!(f, "Import error: ")?;
+        match self {
+            Missing => write!(f, "missing import"),
+            MissingEnvVar => write!(f, "environment variable is not set"),
+            SanityCheck => write!(
+                f,
+                "a remote import cannot import an environment variable"
+            ),
+            CorsCheck => write!(
+                f,
+                "the remote server does not allow this cross-origin import"
+            ),
+            RemoteImportsDisabled => write!(f, "remote imports are disabled"),
+            EnvImportsDisabled => {
+                write!(f, "environment variable imports are disabled")
+            }
+            UnexpectedImport(_) => {
+                write!(f, "unexpected import in an expression without imports")
+            }
+            ImportCycle(stack, location) => write!(
+                f,
+                "import cycle: {} → {}",
+                stack.iter().format(" → "),
+                location
+            ),
+            Url(e) => write!(f, "invalid url: {}", e),
+            Connection(e) => write!(f, "connection failed: {}", e),
+            HttpStatus(status) => {
+                write!(f, "the server responded with HTTP status {}", status)
+            }
+            Timeout => write!(f, "the request timed out"),
+            InvalidUtf8 => write!(f, "the imported text is not valid UTF-8"),
+            BodyTooLarge => write!(
+                f,
+                "the response is larger than the configured maximum size"
+            ),
+            AlternativesFailed(errs) => {
+                write!(f, "every import alternative failed")?;
+                for err in errs {
+                    write!(f, "\n- {}", err.to_string().replace('\n', "\n  "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Url(e) => Some(e),
+            ImportError::Connection(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+impl CacheError {
+    /// A stable name for this kind of cache error, used as the `cache/…` code of diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CacheError::MissingConfiguration => "MissingConfiguration",
+            CacheError::InitialisationError { .. } => "InitialisationError",
+            CacheError::CacheHashInvalid => "CacheHashInvalid",
+        }
+    }
+}
+
+impl std::fmt::Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CacheError::MissingConfiguration => {
+                write!(f, "Cache error: could not find a cache directory")
+            }
+            CacheError::InitialisationError { cause } => write!(
+                f,
+                "Cache error: could not create the cache directory: {}",
+                cause
+            ),
+            CacheError::CacheHashInvalid => write!(
+                f,
+                "Cache error: the cached expression does not match its hash"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CacheError::InitialisationError { cause } => Some(cause),
+            _ => None,
+        }
+    }
+}
+
+impl ConvertError {
+    /// A stable name for this kind of conversion error, used as the `convert/…` code of
+    /// diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConvertError::Unsupported(..) => "Unsupported",
+            ConvertError::SpecialDouble(..) => "SpecialDouble",
+            #[cfg(feature = "json")]
+            ConvertError::InvalidJson(..) => "InvalidJson",
+            #[cfg(feature = "yaml")]
+            ConvertError::InvalidYaml(..) => "InvalidYaml",
+            ConvertError::JsonMismatch { .. } => "JsonMismatch",
+            ConvertError::MissingField(..) => "MissingField",
+            ConvertError::UnexpectedField(..) => "UnexpectedField",
+            ConvertError::HeterogeneousList(..) => "HeterogeneousList",
+            ConvertError::CannotInfer(..) => "CannotInfer",
+        }
+    }
+}
+
+impl std::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConvertError::Unsupported(value) => {
+                write!(
+                    f,
+                    "Conversion error: `{}` has no JSON equivalent",
+                    value
+                )
+            }
+            ConvertError::SpecialDouble(value) => write!(
+                f,
+                "Conversion error: `{}` is not a finite number, which JSON \
+                 does not support",
+                value
+            ),
+            #[cfg(feature = "json")]
+            ConvertError::InvalidJson(e) => {
+                write!(f, "Conversion error: invalid JSON: {}", e)
+            }
+            #[cfg(feature = "yaml")]
+            ConvertError::InvalidYaml(e) => {
+                write!(f, "Conversion error: invalid YAML: {}", e)
+            }
+            ConvertError::JsonMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Conversion error: expected a value of type `{}` at `{}`, \
+                 found `{}`",
+                expected, path, found
+            ),
+            ConvertError::MissingField(path) => {
+                write!(f, "Conversion error: missing field `{}`", path)
+            }
+            ConvertError::UnexpectedField(path) => write!(
+                f,
+                "Conversion error: the field `{}` is not in the expected type",
+                path
+            ),
+            ConvertError::HeterogeneousList(path) => write!(
+                f,
+                "Conversion error: the array element at `{}` does not have \
+                 the same type as the elements before it",
+                path
+            ),
+            ConvertError::CannotInfer(path) => write!(
+                f,
+                "Conversion error: cannot infer the type of `{}`, which is \
+                 only ever `null` or `[]`; provide a type",
+                path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "json")]
+            ConvertError::InvalidJson(e) => Some(e),
+            #[cfg(feature = "yaml")]
+            ConvertError::InvalidYaml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
             ErrorKind::IO(err) => write!(f, "{}", err),
             ErrorKind::Parse(err) => write!(f, "{}", err),
-            ErrorKind::Decode(err) => write!(f, "{:?}", err),
-            ErrorKind::Encode(err) => write!(f, "{:?}", err),
-            ErrorKind::Resolve(err) => write!(f, "{:?}", err),
+            ErrorKind::Decode(err) => write!(f, "{}", err),
+            ErrorKind::Encode(err) => write!(f, "{}", err),
+            ErrorKind::Resolve(err) => write!(f, "{}", err),
             ErrorKind::Typecheck(err) => write!(f, "{}", err),
-            ErrorKind::Cache(err) => write!(f, "{:?}", err),
+            ErrorKind::Cache(err) => write!(f, "{}", err),
+            ErrorKind::Convert(err) => write!(f, "{}", err),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}", location.formatted),
+            None => write!(f, "{}", self.kind),
+        }?;
+        if !self.import_stack.is_empty() {
+            write!(
+                f,
+                "\nwhile importing {}",
+                self.import_stack.iter().format(" → ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The message of the wrapped error is already part of this error, so the source is the cause of
+/// the wrapped error.
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::IO(err) => err.source(),
+            ErrorKind::Parse(err) => err.source(),
+            ErrorKind::Decode(err) => err.source(),
+            ErrorKind::Encode(err) => err.source(),
+            ErrorKind::Resolve(err) => err.source(),
+            ErrorKind::Typecheck(err) => err.source(),
+            ErrorKind::Cache(err) => err.source(),
+            ErrorKind::Convert(err) => err.source(),
+        }
+    }
+}
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Error {
         Error::new(kind)
@@ -163,3 +1021,35 @@ impl From<CacheError> for Error {
         ErrorKind::Cache(err).into()
     }
 }
+impl From<ConvertError> for Error {
+    fn from(err: ConvertError) -> Error {
+        ErrorKind::Convert(err).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error as _;
+
+    use crate::semantics::parse::{parse_binary, parse_str};
+
+    #[test]
+    fn import_errors_should_point_to_the_import() {
+        let err = parse_str("env:DHALL_TEST_MISSING_VARIABLE")
+            .unwrap()
+            .resolve()
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.starts_with(
+            "error: Import error: environment variable is not set"
+        ));
+        assert!(message.contains("--> <current file>:1:1"));
+    }
+
+    #[test]
+    fn errors_should_chain_their_causes() {
+        let err = parse_binary(&[0xff]).unwrap_err();
+        assert!(err.to_string().starts_with("Decode error: "));
+        assert!(err.source().is_some());
+    }
+}
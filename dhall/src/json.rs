@@ -0,0 +1,689 @@
+//! Conversion between Dhall values and JSON or YAML, following the conventions of
+//! `dhall-to-json` and `json-to-dhall`.
+use serde_json::{Map, Number, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::builtins::Builtin;
+use crate::error::{ConvertError, Error};
+use crate::operations::OpKind;
+use crate::semantics::{Nir, NirKind};
+use crate::syntax::{Const, Expr, ExprKind, Label, NumKind, Span, V};
+use crate::{JsonOptions, ToExprOptions};
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn unsupported(nir: &Nir) -> Error {
+    ConvertError::Unsupported(nir.to_expr(ToExprOptions::default()).to_string())
+        .into()
+}
+
+fn number(num: &NumKind) -> Result<Value> {
+    Ok(match num {
+        NumKind::Bool(b) => Value::Bool(*b),
+        NumKind::Natural(n) => Value::Number((*n as u64).into()),
+        NumKind::Integer(n) => Value::Number((*n as i64).into()),
+        NumKind::Double(d) => match Number::from_f64((*d).into()) {
+            Some(d) => Value::Number(d),
+            None => {
+                let d: f64 = (*d).into();
+                return Err(ConvertError::SpecialDouble(d.to_string()).into());
+            }
+        },
+    })
+}
+
+/// Whether the record has exactly the fields of an association list entry.
+fn is_map_entry<T>(kvs: &HashMap<Label, T>) -> bool {
+    kvs.len() == 2 && kvs.contains_key("mapKey") && kvs.contains_key("mapValue")
+}
+
+/// Convert a value in normal form.
+pub(crate) fn to_json(nir: &Nir, options: &JsonOptions) -> Result<Value> {
+    let mut json = nir_to_json(nir)?;
+    if !options.preserve_null {
+        omit_null_fields(&mut json);
+    }
+    Ok(json)
+}
+
+fn nir_to_json(nir: &Nir) -> Result<Value> {
+    Ok(match nir.kind() {
+        NirKind::Num(num) => number(num)?,
+        NirKind::TextLit(t) => match t.as_text() {
+            Some(t) => Value::String(t),
+            None => return Err(unsupported(nir)),
+        },
+        NirKind::EmptyOptionalLit(_) => Value::Null,
+        NirKind::NEOptionalLit(x) => nir_to_json(x)?,
+        NirKind::EmptyListLit(t) => match t.kind() {
+            NirKind::RecordType(kts) if is_map_entry(kts) => {
+                Value::Object(Map::new())
+            }
+            _ => Value::Array(Vec::new()),
+        },
+        NirKind::NEListLit(xs) => match xs[0].kind() {
+            NirKind::RecordLit(kvs) if is_map_entry(kvs) => {
+                let mut map = Map::new();
+                for x in xs {
+                    let (k, v) = match x.kind() {
+                        NirKind::RecordLit(kvs) => {
+                            (&kvs["mapKey"], &kvs["mapValue"])
+                        }
+                        _ => unreachable!("Internal type error"),
+                    };
+                    let k = match k.kind() {
+                        NirKind::TextLit(t) => t.as_text(),
+                        _ => None,
+                    };
+                    match k {
+                        Some(k) => map.insert(k, nir_to_json(v)?),
+                        None => return Err(unsupported(nir)),
+                    };
+                }
+                Value::Object(map)
+            }
+            _ => {
+                Value::Array(xs.iter().map(nir_to_json).collect::<Result<_>>()?)
+            }
+        },
+        NirKind::RecordLit(kvs) => Value::Object(
+            kvs.iter()
+                .map(|(k, v)| Ok((k.into(), nir_to_json(v)?)))
+                .collect::<Result<_>>()?,
+        ),
+        // Unions are converted to their payload, and alternatives without one to their name.
+        NirKind::UnionLit(_, x, _) => nir_to_json(x)?,
+        NirKind::UnionConstructor(field, kts)
+            if kts.get(field).map(Option::is_some) == Some(false) =>
+        {
+            Value::String(field.into())
+        }
+        NirKind::LamClosure { .. } => {
+            let expr = nir.to_expr(ToExprOptions { alpha: true });
+            match json_type_to_json(&expr) {
+                Some(json) => json?,
+                None => return Err(unsupported(nir)),
+            }
+        }
+        _ => return Err(unsupported(nir)),
+    })
+}
+
+/// Convert a value of the Prelude's `JSON.Type`, i.e. `λ(JSON : Type) → λ(json : { .. }) → ..`.
+/// Returns `None` if the value doesn't have this shape.
+fn json_type_to_json(expr: &Expr) -> Option<Result<Value>> {
+    let body = match expr.kind() {
+        ExprKind::Lam(_, ty, body)
+            if matches!(ty.kind(), ExprKind::Const(Const::Type)) =>
+        {
+            body
+        }
+        _ => return None,
+    };
+    match body.kind() {
+        ExprKind::Lam(_, ty, body)
+            if matches!(ty.kind(), ExprKind::RecordType(_)) =>
+        {
+            // After alpha-normalization, the record of constructors is `_@0`.
+            let json = V("_".into(), 0);
+            encoded_to_json(body, &json)
+        }
+        _ => None,
+    }
+}
+
+/// Convert an expression built with the constructors of the `json` record.
+fn encoded_to_json(expr: &Expr, json: &V) -> Option<Result<Value>> {
+    let constructor = |e: &Expr| match e.kind() {
+        ExprKind::Op(OpKind::Field(record, field)) => match record.kind() {
+            ExprKind::Var(v) if v == json => Some(field.clone()),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(field) = constructor(expr) {
+        return match field.as_ref() {
+            "null" => Some(Ok(Value::Null)),
+            _ => None,
+        };
+    }
+    let (field, arg) = match expr.kind() {
+        ExprKind::Op(OpKind::App(f, arg)) => (constructor(f)?, arg),
+        _ => return None,
+    };
+    let items = |arg: &Expr| match arg.kind() {
+        ExprKind::NEListLit(xs) => Some(xs.clone()),
+        ExprKind::EmptyListLit(_) => Some(Vec::new()),
+        _ => None,
+    };
+    Some(match (field.as_ref(), arg.kind()) {
+        ("bool", ExprKind::Num(num @ NumKind::Bool(_)))
+        | ("integer", ExprKind::Num(num @ NumKind::Integer(_)))
+        | ("double", ExprKind::Num(num @ NumKind::Double(_)))
+        // The `number` constructor of older versions of the Prelude
+        | ("number", ExprKind::Num(num @ NumKind::Double(_))) => number(num),
+        ("string", ExprKind::TextLit(t)) if t.tail().is_empty() => {
+            Ok(Value::String(t.head().to_string()))
+        }
+        ("array", _) => items(arg)?
+            .iter()
+            .map(|x| encoded_to_json(x, json))
+            .collect::<Option<Result<_>>>()?
+            .map(Value::Array),
+        ("object", _) => {
+            let mut map = Map::new();
+            for entry in items(arg)? {
+                let (k, v) = match entry.kind() {
+                    ExprKind::RecordLit(kvs) => {
+                        (kvs.get("mapKey")?, kvs.get("mapValue")?)
+                    }
+                    _ => return None,
+                };
+                let k = match k.kind() {
+                    ExprKind::TextLit(t) if t.tail().is_empty() => t.head(),
+                    _ => return None,
+                };
+                match encoded_to_json(v, json)? {
+                    Ok(v) => map.insert(k.to_string(), v),
+                    Err(err) => return Some(Err(err)),
+                };
+            }
+            Ok(Value::Object(map))
+        }
+        _ => return None,
+    })
+}
+
+/// The type of a JSON value, as far as it can be inferred.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// The type of `null` or of the elements of `[]`
+    Unknown,
+    Bool,
+    Natural,
+    Integer,
+    Double,
+    Text,
+    Optional(Box<Shape>),
+    List(Box<Shape>),
+    Record(BTreeMap<String, Shape>),
+}
+
+impl Shape {
+    fn infer(json: &Value, path: &str) -> Result<Shape> {
+        Ok(match json {
+            Value::Null => Shape::Optional(Box::new(Shape::Unknown)),
+            Value::Bool(_) => Shape::Bool,
+            Value::Number(n) if n.is_u64() => Shape::Natural,
+            Value::Number(n) if n.is_i64() => Shape::Integer,
+            Value::Number(_) => Shape::Double,
+            Value::String(_) => Shape::Text,
+            Value::Array(xs) => {
+                let mut shape = Shape::Unknown;
+                for (i, x) in xs.iter().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    shape = shape
+                        .unify(Shape::infer(x, &path)?)
+                        .ok_or(ConvertError::HeterogeneousList(path))?;
+                }
+                Shape::List(Box::new(shape))
+            }
+            Value::Object(kvs) => Shape::Record(
+                kvs.iter()
+                    .map(|(k, v)| {
+                        Ok((k.clone(), Shape::infer(v, &field_path(path, k))?))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    /// The most precise type of both values, if any.
+    fn unify(self, other: Shape) -> Option<Shape> {
+        use Shape::*;
+        Some(match (self, other) {
+            (Unknown, x) | (x, Unknown) => x,
+            (x, y) if x == y => x,
+            (Natural, Integer) | (Integer, Natural) => Integer,
+            (Natural, Double)
+            | (Integer, Double)
+            | (Double, Natural)
+            | (Double, Integer) => Double,
+            (Optional(x), Optional(y)) => Optional(Box::new(x.unify(*y)?)),
+            (Optional(x), y) | (y, Optional(x)) => {
+                Optional(Box::new(x.unify(y)?))
+            }
+            (List(x), List(y)) => List(Box::new(x.unify(*y)?)),
+            (Record(mut xs), Record(ys)) => {
+                // Fields missing from some of the objects are optional.
+                for (k, x) in xs.iter_mut() {
+                    if !ys.contains_key(k) {
+                        *x = x.clone().unify(Optional(Box::new(Unknown)))?;
+                    }
+                }
+                for (k, y) in ys {
+                    let shape = match xs.remove(&k) {
+                        Some(x) => x.unify(y)?,
+                        None => y.unify(Optional(Box::new(Unknown)))?,
+                    };
+                    xs.insert(k, shape);
+                }
+                Record(xs)
+            }
+            _ => return None,
+        })
+    }
+
+    fn to_type(&self, path: &str) -> Result<Nir> {
+        let builtin = |b| Ok(Nir::from_kind(NirKind::BuiltinType(b)));
+        Ok(Nir::from_kind(match self {
+            Shape::Unknown => {
+                return Err(ConvertError::CannotInfer(path.to_string()).into())
+            }
+            Shape::Bool => return builtin(Builtin::Bool),
+            Shape::Natural => return builtin(Builtin::Natural),
+            Shape::Integer => return builtin(Builtin::Integer),
+            Shape::Double => return builtin(Builtin::Double),
+            Shape::Text => return builtin(Builtin::Text),
+            Shape::Optional(x) => NirKind::OptionalType(x.to_type(path)?),
+            Shape::List(x) => {
+                NirKind::ListType(x.to_type(&format!("{}[]", path))?)
+            }
+            Shape::Record(kts) => NirKind::RecordType(
+                kts.iter()
+                    .map(|(k, t)| {
+                        Ok((
+                            k.as_str().into(),
+                            t.to_type(&field_path(path, k))?,
+                        ))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        }))
+    }
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    match path {
+        "." => format!(".{}", field),
+        _ => format!("{}.{}", path, field),
+    }
+}
+
+fn mk_expr(kind: ExprKind<Expr>) -> Expr {
+    Expr::new(kind, Span::Artificial)
+}
+
+fn type_to_expr(ty: &Nir) -> Expr {
+    ty.to_expr(ToExprOptions::default())
+}
+
+/// Convert a JSON value to an expression of type `ty`. `path` locates the value for errors.
+fn json_to_expr(json: &Value, ty: &Nir, path: &str) -> Result<Expr> {
+    use NirKind::{BuiltinType, ListType, OptionalType, RecordType, UnionType};
+    let mismatch = || -> Error {
+        ConvertError::JsonMismatch {
+            path: path.to_string(),
+            expected: type_to_expr(ty).to_string(),
+            found: json.to_string(),
+        }
+        .into()
+    };
+    let num = |n| Ok(mk_expr(ExprKind::Num(n)));
+    match (ty.kind(), json) {
+        (BuiltinType(Builtin::Bool), Value::Bool(b)) => num(NumKind::Bool(*b)),
+        (BuiltinType(Builtin::Natural), Value::Number(n)) => match n.as_u64() {
+            Some(n) => num(NumKind::Natural(n as usize)),
+            None => Err(mismatch()),
+        },
+        (BuiltinType(Builtin::Integer), Value::Number(n)) => match n.as_i64() {
+            Some(n) => num(NumKind::Integer(n as isize)),
+            None => Err(mismatch()),
+        },
+        (BuiltinType(Builtin::Double), Value::Number(n)) => match n.as_f64() {
+            Some(n) => num(NumKind::Double(n.into())),
+            None => Err(mismatch()),
+        },
+        (BuiltinType(Builtin::Text), Value::String(s)) => {
+            Ok(mk_expr(ExprKind::TextLit(s.clone().into())))
+        }
+        (OptionalType(t), Value::Null) => {
+            Ok(mk_expr(ExprKind::Op(OpKind::App(
+                mk_expr(ExprKind::Builtin(Builtin::OptionalNone)),
+                type_to_expr(t),
+            ))))
+        }
+        (OptionalType(t), _) => {
+            Ok(mk_expr(ExprKind::SomeLit(json_to_expr(json, t, path)?)))
+        }
+        (ListType(_), Value::Array(xs)) if xs.is_empty() => {
+            Ok(mk_expr(ExprKind::EmptyListLit(type_to_expr(ty))))
+        }
+        (ListType(t), Value::Array(xs)) => Ok(mk_expr(ExprKind::NEListLit(
+            xs.iter()
+                .enumerate()
+                .map(|(i, x)| json_to_expr(x, t, &format!("{}[{}]", path, i)))
+                .collect::<Result<_>>()?,
+        ))),
+        // Objects become association lists when a map is expected.
+        (ListType(t), Value::Object(kvs)) => {
+            let value_ty = match t.kind() {
+                RecordType(kts)
+                    if is_map_entry(kts)
+                        && matches!(
+                            kts["mapKey"].kind(),
+                            BuiltinType(Builtin::Text)
+                        ) =>
+                {
+                    &kts["mapValue"]
+                }
+                _ => return Err(mismatch()),
+            };
+            if kvs.is_empty() {
+                return Ok(mk_expr(ExprKind::EmptyListLit(type_to_expr(ty))));
+            }
+            Ok(mk_expr(ExprKind::NEListLit(
+                kvs.iter()
+                    .map(|(k, v)| {
+                        let mut entry = BTreeMap::new();
+                        entry.insert(
+                            "mapKey".into(),
+                            mk_expr(ExprKind::TextLit(k.clone().into())),
+                        );
+                        entry.insert(
+                            "mapValue".into(),
+                            json_to_expr(v, value_ty, &field_path(path, k))?,
+                        );
+                        Ok(mk_expr(ExprKind::RecordLit(entry)))
+                    })
+                    .collect::<Result<_>>()?,
+            )))
+        }
+        (RecordType(kts), Value::Object(kvs)) => {
+            if let Some(k) = kvs.keys().find(|k| !kts.contains_key(k.as_str()))
+            {
+                let path = field_path(path, k);
+                return Err(ConvertError::UnexpectedField(path).into());
+            }
+            Ok(mk_expr(ExprKind::RecordLit(
+                kts.iter()
+                    .map(|(k, t)| {
+                        let path = field_path(path, k.as_ref());
+                        let v = match (kvs.get(k.as_ref()), t.kind()) {
+                            (Some(v), _) => json_to_expr(v, t, &path)?,
+                            // Missing optional fields are `None`.
+                            (None, OptionalType(_)) => {
+                                json_to_expr(&Value::Null, t, &path)?
+                            }
+                            (None, _) => {
+                                return Err(
+                                    ConvertError::MissingField(path).into()
+                                )
+                            }
+                        };
+                        Ok((k.clone(), v))
+                    })
+                    .collect::<Result<_>>()?,
+            )))
+        }
+        // The first alternative that matches is used. Alternatives without a payload match
+        // their name.
+        (UnionType(kts), _) => {
+            let constructor = |k: &Label| {
+                mk_expr(ExprKind::Op(OpKind::Field(
+                    type_to_expr(ty),
+                    k.clone(),
+                )))
+            };
+            let mut alternatives: Vec<_> = kts.iter().collect();
+            alternatives.sort_by_key(|(k, _)| *k);
+            for (k, t) in alternatives {
+                match (t, json) {
+                    (None, Value::String(s)) if s.as_str() == k.as_ref() => {
+                        return Ok(constructor(k))
+                    }
+                    (Some(t), _) => {
+                        if let Ok(x) = json_to_expr(json, t, path) {
+                            return Ok(mk_expr(ExprKind::Op(OpKind::App(
+                                constructor(k),
+                                x,
+                            ))));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Err(mismatch())
+        }
+        (BuiltinType(_), _) | (ListType(_), _) | (RecordType(_), _) => {
+            Err(mismatch())
+        }
+        _ => {
+            Err(ConvertError::Unsupported(type_to_expr(ty).to_string()).into())
+        }
+    }
+}
+
+/// Converts a JSON value to a Dhall expression of type `ty`. Objects become records, or
+/// association lists when the type is a `List { mapKey : Text, mapValue : T }`; `null` and
+/// missing fields become `None`, and strings and other values become the first alternative of a
+/// union that matches them.
+///
+/// Without a type, one is inferred from the value: arrays must have elements of a common type,
+/// and fields missing from some of the objects of an array are optional.
+pub fn from_json(json: &Value, ty: Option<&Nir>) -> Result<Expr> {
+    let inferred;
+    let ty = match ty {
+        Some(ty) => ty,
+        None => {
+            inferred = Shape::infer(json, ".")?.to_type(".")?;
+            &inferred
+        }
+    };
+    json_to_expr(json, ty, ".")
+}
+
+/// Converts a YAML document to a Dhall expression, like `from_json`.
+#[cfg(feature = "yaml")]
+pub fn from_yaml(yaml: &str, ty: Option<&Nir>) -> Result<Expr> {
+    let json: Value =
+        serde_yaml::from_str(yaml).map_err(ConvertError::InvalidYaml)?;
+    from_json(&json, ty)
+}
+
+/// Remove the record fields that are `null`, like `dhall-to-json` does by default.
+fn omit_null_fields(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            *map = std::mem::take(map)
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .collect();
+            map.values_mut().for_each(omit_null_fields);
+        }
+        Value::Array(xs) => xs.iter_mut().for_each(omit_null_fields),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::error::{ConvertError, Error, ErrorKind};
+    use crate::json::from_json;
+    use crate::semantics::parse::parse_str;
+    use crate::{JsonOptions, Normalized};
+
+    fn normalize(s: &str) -> Result<Normalized, Error> {
+        Ok(parse_str(s)?.skip_resolve()?.typecheck()?.normalize())
+    }
+
+    /// Convert to Dhall text, checking that the result has the expected type.
+    fn from_json_str(
+        json: serde_json::Value,
+        ty: Option<&str>,
+    ) -> Result<String, Error> {
+        let ty = ty.map(normalize).transpose()?;
+        let expr = from_json(&json, ty.as_ref().map(Normalized::as_nir))?;
+        let typed =
+            parse_str(&expr.to_string())?.skip_resolve()?.typecheck()?;
+        if let Some(ty) = ty {
+            assert_eq!(typed.get_type()?, ty);
+        }
+        Ok(expr.to_string())
+    }
+
+    fn to_json(
+        s: &str,
+        options: &JsonOptions,
+    ) -> Result<serde_json::Value, Error> {
+        parse_str(s)?
+            .skip_resolve()?
+            .typecheck()?
+            .normalize()
+            .to_json(options)
+    }
+
+    #[test]
+    fn values_should_convert_to_json() -> Result<(), Error> {
+        let options = JsonOptions::default();
+        let value = to_json(
+            r#"{ n = 1, i = -2, d = 1.5, t = "x", b = True, s = Some 3,
+                 l = [ None Natural, Some 1 ], u = < A | B : Text >.B "y",
+                 e = < A | B : Text >.A, m = toMap { k = 1 },
+                 empty = [] : List { mapKey : Text, mapValue : Bool } }"#,
+            &options,
+        )?;
+        assert_eq!(
+            value,
+            json!({ "n": 1, "i": -2, "d": 1.5, "t": "x", "b": true, "s": 3,
+                    "l": [null, 1], "u": "y", "e": "A", "m": { "k": 1 },
+                    "empty": {} })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn null_fields_should_be_omitted_unless_preserved() -> Result<(), Error> {
+        let input = "{ x = None Natural, y = 1 }";
+        let value = to_json(input, &JsonOptions::default())?;
+        assert_eq!(value, json!({ "y": 1 }));
+        let options = JsonOptions {
+            preserve_null: true,
+        };
+        assert_eq!(to_json(input, &options)?, json!({ "x": null, "y": 1 }));
+        Ok(())
+    }
+
+    #[test]
+    fn prelude_json_values_should_convert_to_json() -> Result<(), Error> {
+        let value = to_json(
+            r#"λ(JSON : Type) →
+               λ(json : { array : List JSON → JSON, bool : Bool → JSON,
+                          double : Double → JSON, integer : Integer → JSON,
+                          null : JSON, string : Text → JSON,
+                          object : List { mapKey : Text, mapValue : JSON } → JSON }) →
+                 json.object
+                   [ { mapKey = "a", mapValue = json.array [ json.bool True, json.double 2.5 ] }
+                   , { mapKey = "b", mapValue = json.string "x" }
+                   , { mapKey = "c", mapValue = json.integer -1 }
+                   , { mapKey = "d", mapValue = json.null }
+                   ]"#,
+            &JsonOptions {
+                preserve_null: true,
+            },
+        )?;
+        assert_eq!(
+            value,
+            json!({ "a": [true, 2.5], "b": "x", "c": -1, "d": null })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn functions_and_special_doubles_should_not_convert() {
+        let err =
+            to_json("λ(x : Bool) → x", &JsonOptions::default()).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Convert(_)));
+        let err = to_json("Infinity", &JsonOptions::default()).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Convert(ConvertError::SpecialDouble(_))
+        ));
+    }
+
+    #[test]
+    fn json_should_convert_to_the_expected_type() -> Result<(), Error> {
+        let ty = "{ name : Text, port : Optional Natural, tags : List Text,
+                    env : List { mapKey : Text, mapValue : Text },
+                    mode : < Fast | Slow | Custom : Natural >,
+                    ratio : Double, offset : Integer }";
+        let converted = from_json_str(
+            json!({ "name": "x", "tags": [], "env": { "A": "1" },
+                    "mode": "Slow", "ratio": 1, "offset": 2 }),
+            Some(ty),
+        )?;
+        assert!(converted.contains("port = None Natural"));
+        assert!(converted.contains("tags = [] : List Text"));
+        assert!(
+            converted.contains(r#"env = [{ mapKey = "A", mapValue = "1" }]"#)
+        );
+        assert!(converted.contains("offset = +2"));
+
+        let converted = from_json_str(
+            json!({ "mode": 3 }),
+            Some("{ mode : < Fast | Custom : Natural > }"),
+        )?;
+        assert!(converted.contains(".Custom 3"));
+        Ok(())
+    }
+
+    #[test]
+    fn json_should_not_convert_to_the_wrong_type() {
+        let err = |json, ty| match from_json_str(json, Some(ty)) {
+            Err(err) => match err.kind() {
+                ErrorKind::Convert(err) => err.to_string(),
+                _ => panic!("{}", err),
+            },
+            Ok(expr) => panic!("{}", expr),
+        };
+        assert_eq!(
+            err(json!({ "a": [1, -1] }), "{ a : List Natural }"),
+            "Conversion error: expected a value of type `Natural` at \
+             `.a[1]`, found `-1`"
+        );
+        assert_eq!(
+            err(json!({}), "{ a : Natural }"),
+            "Conversion error: missing field `.a`"
+        );
+        assert_eq!(
+            err(json!({ "b": 1 }), "{}"),
+            "Conversion error: the field `.b` is not in the expected type"
+        );
+    }
+
+    #[test]
+    fn json_types_should_be_inferred() -> Result<(), Error> {
+        assert_eq!(
+            from_json_str(
+                json!([{ "a": 1, "b": null }, { "a": -1.5, "b": "x" }, {}]),
+                None
+            )?,
+            r#"[{ a = Some 1.0, b = None Text }, { a = Some -1.5, b = Some "x" }, { a = None Double, b = None Text }]"#
+        );
+        assert_eq!(from_json_str(json!([1, -2]), None)?, "[+1, -2]");
+
+        let err = from_json_str(json!({ "a": [1, "x"] }), None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Convert(ConvertError::HeterogeneousList(path)) if path == ".a[1]"
+        ));
+        let err = from_json_str(json!({ "a": [] }), None).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Convert(ConvertError::CannotInfer(path)) if path == ".a[]"
+        ));
+        Ok(())
+    }
+}
@@ -10,20 +10,28 @@
 
 pub mod builtins;
 pub mod error;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod operations;
 pub mod semantics;
 pub mod syntax;
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use url::Url;
 
 use crate::error::{Error, TypeError};
+use crate::semantics::locate;
 use crate::semantics::parse;
 use crate::semantics::resolve;
-use crate::semantics::resolve::ImportLocation;
-use crate::semantics::{typecheck, typecheck_with, Hir, Nir, Tir, Type};
-use crate::syntax::Expr;
+use crate::semantics::resolve::{ImportLocation, ImportResolver};
+use crate::semantics::{
+    typecheck, typecheck_with, DefaultResolver, Hir, Nir, NirKind, Tir, TyEnv,
+    Type,
+};
+use crate::syntax::{Expr, Label, SourcePosition, SourceRange};
 
 #[derive(Debug, Clone)]
 pub struct Parsed(Expr, ImportLocation);
@@ -47,6 +55,11 @@ pub struct Typed {
 #[derive(Debug, Clone)]
 pub struct Normalized(Nir);
 
+/// Names bound to values, that expressions can refer to. This is the top-level environment of a
+/// REPL, where each input can use the values defined before it.
+#[derive(Debug, Clone)]
+pub struct Env(TyEnv);
+
 /// Controls conversion from `Nir` to `Expr`
 #[derive(Copy, Clone, Default)]
 pub struct ToExprOptions {
@@ -54,20 +67,144 @@ pub struct ToExprOptions {
     pub alpha: bool,
 }
 
+/// Controls conversion of values to JSON and YAML.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Whether to keep record fields that are `None` or `null`, instead of omitting them
+    pub preserve_null: bool,
+}
+
+/// What a variable or an import refers to, as found by `Parsed::definition_at`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Definition {
+    /// The variable is bound by the `let`, `λ` or `∀` whose label is at this location
+    Binding(SourceRange),
+    /// The import points to this local file
+    File(PathBuf),
+}
+
+/// Controls which imports are allowed during import resolution, and how they are cached.
+#[derive(Debug, Clone)]
+pub struct ResolveOptions {
+    /// Whether to allow remote imports
+    pub remote_imports: bool,
+    /// Whether to allow environment variable imports
+    pub env_imports: bool,
+    /// Where to cache imports protected by a hash
+    pub cache_dir: CacheDir,
+    /// How to fetch remote imports
+    pub http: HttpOptions,
+}
+
+/// Controls which imports `Parsed::freeze` adds an integrity hash to. By default, only remote
+/// imports without a hash are frozen.
+#[derive(Debug, Clone, Default)]
+pub struct FreezeOptions {
+    /// Whether to also freeze local and environment variable imports
+    pub all: bool,
+    /// Whether to recompute the hash of imports that already have one
+    pub refreeze: bool,
+    /// Whether to leave the fallbacks of import alternatives, i.e. the right side of `?`,
+    /// unchanged
+    pub keep_fallbacks: bool,
+}
+
+/// Controls how remote imports are fetched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HttpOptions {
+    /// Timeout for each request, from connecting to reading the whole body. Defaults to 30
+    /// seconds.
+    pub timeout: Option<Duration>,
+    /// Maximum size of a response body, in bytes
+    pub max_body_size: Option<u64>,
+    /// Proxy to send all requests through. By default, the system proxy is used.
+    pub proxy: Option<Url>,
+    /// Additional root certificates to trust, PEM-encoded
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Value of the `User-Agent` header
+    pub user_agent: Option<String>,
+}
+
+/// Where to cache imports protected by a hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheDir {
+    /// Use `$XDG_CACHE_HOME/dhall`, or the platform equivalent. This is only used when remote
+    /// imports are allowed, so that local-only resolution never reads the environment.
+    Default,
+    /// Use the given directory, creating it if needed
+    Path(PathBuf),
+    /// Don't cache imports
+    Disabled,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        ResolveOptions {
+            remote_imports: true,
+            env_imports: true,
+            cache_dir: CacheDir::Default,
+            http: HttpOptions::default(),
+        }
+    }
+}
+
 impl Parsed {
     pub fn parse_file(f: &Path) -> Result<Parsed, Error> {
-        parse::parse_file(f)
+        parse::parse_file(f, &DefaultResolver)
+    }
+    /// Like `parse_file`, reading the file with the provided resolver.
+    pub fn parse_file_with(
+        f: &Path,
+        resolver: &dyn ImportResolver,
+    ) -> Result<Parsed, Error> {
+        parse::parse_file(f, resolver)
     }
     pub fn parse_remote(url: Url) -> Result<Parsed, Error> {
-        parse::parse_remote(url)
+        parse::parse_remote(url, &DefaultResolver, &HttpOptions::default())
+    }
+    pub fn parse_remote_with_options(
+        url: Url,
+        options: &HttpOptions,
+    ) -> Result<Parsed, Error> {
+        parse::parse_remote(url, &DefaultResolver, options)
+    }
+    /// Like `parse_remote_with_options`, downloading the file with the provided resolver.
+    pub fn parse_remote_with(
+        url: Url,
+        resolver: &dyn ImportResolver,
+        options: &HttpOptions,
+    ) -> Result<Parsed, Error> {
+        parse::parse_remote(url, resolver, options)
+    }
+    /// Like `parse_remote_with_options`, but without blocking.
+    #[cfg(feature = "async")]
+    pub async fn parse_remote_async(
+        url: Url,
+        options: &HttpOptions,
+    ) -> Result<Parsed, Error> {
+        parse::parse_remote_async(url, &DefaultResolver, options).await
+    }
+    /// Like `parse_remote_with`, but without blocking.
+    #[cfg(feature = "async")]
+    pub async fn parse_remote_async_with(
+        url: Url,
+        resolver: &dyn ImportResolver,
+        options: &HttpOptions,
+    ) -> Result<Parsed, Error> {
+        parse::parse_remote_async(url, resolver, options).await
     }
     pub fn parse_str(s: &str) -> Result<Parsed, Error> {
         parse::parse_str(s)
     }
+    /// Parse text read from the file `path`, e.g. the unsaved contents of a file in an editor.
+    /// Relative imports are resolved from the directory of `path`.
+    pub fn parse_str_at(s: &str, path: &Path) -> Result<Parsed, Error> {
+        parse::parse_str_at(s, path)
+    }
     pub fn parse_binary_file(f: &Path) -> Result<Parsed, Error> {
         parse::parse_binary_file(f)
     }
-    #[allow(dead_code)]
     pub fn parse_binary(data: &[u8]) -> Result<Parsed, Error> {
         parse::parse_binary(data)
     }
@@ -75,14 +212,102 @@ impl Parsed {
     pub fn resolve(self) -> Result<Resolved, Error> {
         resolve::resolve(self)
     }
+    pub fn resolve_with_options(
+        self,
+        options: &ResolveOptions,
+    ) -> Result<Resolved, Error> {
+        resolve::resolve_with_options(self, options)
+    }
+    /// Resolve imports, using the provided resolver to read their contents.
+    pub fn resolve_with(
+        self,
+        resolver: &dyn ImportResolver,
+        options: &ResolveOptions,
+    ) -> Result<Resolved, Error> {
+        resolve::resolve_with(self, resolver, options)
+    }
+    /// Resolve imports without blocking. All the remote imports found in a file are downloaded
+    /// concurrently.
+    ///
+    /// The returned future is not `Send`: await it directly instead of spawning it on a
+    /// multi-threaded executor.
+    #[cfg(feature = "async")]
+    pub async fn resolve_async(self) -> Result<Resolved, Error> {
+        resolve::resolve_async(self).await
+    }
+    /// Like `resolve_async`, using the provided resolver. Remote imports are downloaded with its
+    /// `fetch_remote_async` method.
+    #[cfg(feature = "async")]
+    pub async fn resolve_async_with(
+        self,
+        resolver: &dyn ImportResolver,
+        options: &ResolveOptions,
+    ) -> Result<Resolved, Error> {
+        resolve::resolve_async_with(self, resolver, options).await
+    }
     pub fn skip_resolve(self) -> Result<Resolved, Error> {
         resolve::skip_resolve(self)
     }
+    /// Add a `sha256:` integrity hash to the imports selected by `options`. Only imports of Dhall
+    /// code can be frozen, not `as Text` or `as Location` imports.
+    pub fn freeze(&self, options: &FreezeOptions) -> Result<Parsed, Error> {
+        let resolve_options = ResolveOptions::default();
+        resolve::freeze_with(self, &DefaultResolver, &resolve_options, options)
+    }
+    /// Like `freeze`, using the provided resolver and options to resolve the imports.
+    pub fn freeze_with(
+        &self,
+        resolver: &dyn ImportResolver,
+        resolve_options: &ResolveOptions,
+        options: &FreezeOptions,
+    ) -> Result<Parsed, Error> {
+        resolve::freeze_with(self, resolver, resolve_options, options)
+    }
+    /// Like `freeze`, but write the hashes into the source text the expression was parsed from,
+    /// keeping the rest of the text, including comments, as is.
+    pub fn freeze_source(
+        &self,
+        options: &FreezeOptions,
+    ) -> Result<String, Error> {
+        let resolve_options = ResolveOptions::default();
+        resolve::freeze_source_with(
+            self,
+            &DefaultResolver,
+            &resolve_options,
+            options,
+        )
+    }
+    /// Like `freeze_source`, using the provided resolver and options to resolve the imports.
+    pub fn freeze_source_with(
+        &self,
+        resolver: &dyn ImportResolver,
+        resolve_options: &ResolveOptions,
+        options: &FreezeOptions,
+    ) -> Result<String, Error> {
+        resolve::freeze_source_with(self, resolver, resolve_options, options)
+    }
+    /// Resolve imports in an expression that can refer to the names bound in `env`.
+    pub fn resolve_in(self, env: &Env) -> Result<Resolved, Error> {
+        let options = ResolveOptions::default();
+        resolve::resolve_in(
+            self,
+            env.0.as_nameenv(),
+            &DefaultResolver,
+            &options,
+        )
+    }
 
     /// Converts a value back to the corresponding AST expression.
     pub fn to_expr(&self) -> Expr {
         self.0.clone()
     }
+    /// What the variable or import at `position` refers to, if anything.
+    pub fn definition_at(
+        &self,
+        position: SourcePosition,
+    ) -> Option<Definition> {
+        locate::definition_at(&self.0, &self.1, position)
+    }
 }
 
 impl Resolved {
@@ -96,6 +321,14 @@ impl Resolved {
     pub fn to_expr(&self) -> Expr {
         self.0.to_expr_noopts()
     }
+    /// The innermost subexpression at `position` that typechecks, with its type. This works
+    /// even if the whole expression doesn't typecheck.
+    pub fn type_at(
+        &self,
+        position: SourcePosition,
+    ) -> Option<(SourceRange, Expr)> {
+        locate::type_at(&self.0, position)
+    }
 }
 
 impl Typed {
@@ -121,6 +354,43 @@ impl Typed {
     pub fn get_type(&self) -> Result<Normalized, TypeError> {
         Ok(Normalized(self.ty.clone().into_nir()))
     }
+    /// The fields of the expression, if it normalizes to a record literal.
+    pub fn fields(&self) -> Option<BTreeMap<Label, Typed>> {
+        let value = self.hir.eval_closed_expr();
+        match (value.kind(), self.ty.kind()) {
+            (NirKind::RecordLit(kvs), NirKind::RecordType(kts)) => kvs
+                .iter()
+                .map(|(label, value)| {
+                    let ty = kts.get(label)?.clone();
+                    let ty =
+                        Type::new_infer_universe(&TyEnv::new(), ty).ok()?;
+                    let hir = value.to_hir_noenv();
+                    Some((label.clone(), Typed { hir, ty }))
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Env(TyEnv::new())
+    }
+    /// Bind `name` to the value of `typed`, shadowing any previous binding of the same name.
+    pub fn insert(&self, name: &Label, typed: &Typed) -> Self {
+        let value = typed.hir.eval_closed_expr();
+        Env(self.0.insert_value(name, value, typed.ty.clone()))
+    }
+    /// Typecheck an expression that can refer to the names bound in the environment. The result
+    /// doesn't depend on the environment anymore: the names are replaced by their values.
+    pub fn typecheck(&self, resolved: &Resolved) -> Result<Typed, TypeError> {
+        let tir = resolved.0.typecheck(&self.0)?;
+        Ok(Typed {
+            hir: tir.eval(&self.0).to_hir_noenv(),
+            ty: tir.ty().clone(),
+        })
+    }
 }
 
 impl Normalized {
@@ -139,6 +409,23 @@ impl Normalized {
     pub fn to_expr_alpha(&self) -> Expr {
         self.0.to_expr(ToExprOptions { alpha: true })
     }
+    /// Converts a value to JSON, following the conventions of `dhall-to-json`: association lists
+    /// become objects, unions become their payload, and values of the Prelude's `JSON.Type` are
+    /// decoded. Functions and types can't be converted.
+    #[cfg(feature = "json")]
+    pub fn to_json(
+        &self,
+        options: &JsonOptions,
+    ) -> Result<serde_json::Value, Error> {
+        json::to_json(&self.0, options)
+    }
+    /// Converts a value to a YAML document, with the same conventions as `to_json`.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self, options: &JsonOptions) -> Result<String, Error> {
+        let json = self.to_json(options)?;
+        Ok(serde_yaml::to_string(&json)
+            .expect("JSON values can always be written as YAML"))
+    }
 }
 
 macro_rules! derive_traits_for_wrapper_struct {
@@ -3,11 +3,11 @@ use std::cmp::max;
 use std::collections::HashMap;
 
 use crate::builtins::Builtin;
-use crate::error::{ErrorBuilder, TypeError};
+use crate::error::{ErrorBuilder, TypeError, TypeMessage};
 use crate::operations::{BinOp, OpKind};
 use crate::semantics::{
-    merge_maps, mk_span_err, mkerr, Binder, Closure, Hir, HirKind, Nir,
-    NirKind, Tir, TyEnv, Type,
+    merge_maps, mk_builder_err, mk_span_err, Binder, Closure, Hir, HirKind,
+    Nir, NirKind, Tir, TyEnv, Type,
 };
 use crate::syntax::{Const, ExprKind, Span};
 
@@ -22,7 +22,7 @@ fn check_rectymerge(
         _ => {
             return mk_span_err(
                 span.clone(),
-                "RecordTypeMergeRequiresRecordType",
+                TypeMessage::RecordTypeMergeRequiresRecordType,
             )
         }
     };
@@ -31,7 +31,7 @@ fn check_rectymerge(
         _ => {
             return mk_span_err(
                 span.clone(),
-                "RecordTypeMergeRequiresRecordType",
+                TypeMessage::RecordTypeMergeRequiresRecordType,
             )
         }
     };
@@ -51,7 +51,7 @@ fn typecheck_binop(
     l: &Tir<'_>,
     r: &Tir<'_>,
 ) -> Result<Type, TypeError> {
-    let span_err = |msg: &str| mk_span_err(span.clone(), msg);
+    let span_err = |msg: TypeMessage| mk_span_err(span.clone(), msg);
     use BinOp::*;
     use NirKind::{ListType, RecordType};
 
@@ -63,12 +63,12 @@ fn typecheck_binop(
             // Extract the LHS record type
             let kts_x = match x_type.kind() {
                 RecordType(kts) => kts,
-                _ => return span_err("MustCombineRecord"),
+                _ => return span_err(TypeMessage::MustCombineRecord),
             };
             // Extract the RHS record type
             let kts_y = match y_type.kind() {
                 RecordType(kts) => kts,
-                _ => return span_err("MustCombineRecord"),
+                _ => return span_err(TypeMessage::MustCombineRecord),
             };
 
             // Union the two records, prefering
@@ -104,21 +104,31 @@ fn typecheck_binop(
         ListAppend => {
             match l.ty().kind() {
                 ListType(..) => {}
-                _ => return span_err("BinOpTypeMismatch"),
+                _ => {
+                    return span_err(TypeMessage::MustAppendList(
+                        l.ty().to_expr_tyenv(env),
+                    ))
+                }
             }
 
             if l.ty() != r.ty() {
-                return span_err("BinOpTypeMismatch");
+                return span_err(TypeMessage::BinOpTypeMismatch {
+                    expected: l.ty().to_expr_tyenv(env),
+                    found: r.ty().to_expr_tyenv(env),
+                });
             }
 
             l.ty().clone()
         }
         Equivalence => {
             if l.ty() != r.ty() {
-                return span_err("EquivalenceTypeMismatch");
+                return span_err(TypeMessage::EquivalenceTypeMismatch {
+                    expected: l.ty().to_expr_tyenv(env),
+                    found: r.ty().to_expr_tyenv(env),
+                });
             }
             if l.ty().ty().as_const() != Some(Const::Type) {
-                return span_err("EquivalenceArgumentsMustBeTerms");
+                return span_err(TypeMessage::EquivalenceArgumentsMustBeTerms);
             }
 
             Type::from_const(Const::Type)
@@ -136,12 +146,13 @@ fn typecheck_binop(
                 ImportAlt => unreachable!("ImportAlt leftover in tck"),
             });
 
-            if *l.ty() != t {
-                return span_err("BinOpTypeMismatch");
-            }
-
-            if *r.ty() != t {
-                return span_err("BinOpTypeMismatch");
+            for operand in &[l, r] {
+                if *operand.ty() != t {
+                    return span_err(TypeMessage::BinOpTypeMismatch {
+                        expected: t.to_expr_tyenv(env),
+                        found: operand.ty().to_expr_tyenv(env),
+                    });
+                }
             }
 
             t
@@ -156,13 +167,13 @@ fn typecheck_merge(
     scrut: &Tir<'_>,
     type_annot: Option<&Tir<'_>>,
 ) -> Result<Type, TypeError> {
-    let span_err = |msg: &str| mk_span_err(span.clone(), msg);
+    let span_err = |msg: TypeMessage| mk_span_err(span.clone(), msg);
     use NirKind::{OptionalType, PiClosure, RecordType, UnionType};
 
     let record_type = record.ty();
     let handlers = match record_type.kind() {
         RecordType(kts) => kts,
-        _ => return span_err("Merge1ArgMustBeRecord"),
+        _ => return span_err(TypeMessage::Merge1ArgMustBeRecord),
     };
 
     let scrut_type = scrut.ty();
@@ -174,7 +185,7 @@ fn typecheck_merge(
             kts.insert("Some".into(), Some(ty.clone()));
             Cow::Owned(kts)
         }
-        _ => return span_err("Merge2ArgMustBeUnionOrOptional"),
+        _ => return span_err(TypeMessage::Merge2ArgMustBeUnionOrOptional),
     };
 
     let mut inferred_type = None;
@@ -184,32 +195,35 @@ fn typecheck_merge(
             Some(Some(variant_type)) => match handler_type.kind() {
                 PiClosure { closure, annot, .. } => {
                     if variant_type != annot {
-                        return mkerr(
-                            ErrorBuilder::new(format!(
-                                "Wrong handler input type"
-                            ))
-                            .span_err(
-                                span,
-                                format!("in this merge expression",),
-                            )
-                            .span_err(
-                                record.span(),
-                                format!(
+                        let message = TypeMessage::MergeHandlerWrongInputType {
+                            variant: x.clone(),
+                            expected: annot.to_expr_tyenv(env),
+                            found: variant_type.to_expr_tyenv(env),
+                        };
+                        return mk_builder_err(
+                            ErrorBuilder::new(&message)
+                                .span_err(
+                                    span,
+                                    format!("in this merge expression",),
+                                )
+                                .span_err(
+                                    record.span(),
+                                    format!(
                                     "the handler for `{}` expects a value of \
                                      type: `{}`",
                                     x,
                                     annot.to_expr_tyenv(env)
                                 ),
-                            )
-                            .span_err(
-                                scrut.span(),
-                                format!(
+                                )
+                                .span_err(
+                                    scrut.span(),
+                                    format!(
                                     "but the corresponding variant has type: \
                                      `{}`",
                                     variant_type.to_expr_tyenv(env)
                                 ),
-                            )
-                            .format(),
+                                ),
+                            message,
                         );
                     }
 
@@ -217,56 +231,69 @@ fn typecheck_merge(
                     match closure.remove_binder() {
                         Ok(v) => Type::new_infer_universe(env, v.clone())?,
                         Err(()) => {
-                            return span_err("MergeReturnTypeIsDependent")
+                            return span_err(
+                                TypeMessage::MergeReturnTypeIsDependent,
+                            )
                         }
                     }
                 }
                 _ => {
-                    return mkerr(
-                        ErrorBuilder::new(format!(
-                            "merge handler is not a function"
-                        ))
-                        .span_err(span, format!("in this merge expression"))
-                        .span_err(
-                            record.span(),
-                            format!(
-                                "the handler for `{}` has type: `{}`",
-                                x,
-                                handler_type.to_expr_tyenv(env)
-                            ),
-                        )
-                        .span_help(
-                            scrut.span(),
-                            format!(
-                                "the corresponding variant has type: `{}`",
-                                variant_type.to_expr_tyenv(env)
-                            ),
-                        )
-                        .help(format!(
+                    let message = TypeMessage::MergeHandlerNotAFunction {
+                        variant: x.clone(),
+                        found: handler_type.to_expr_tyenv(env),
+                    };
+                    return mk_builder_err(
+                        ErrorBuilder::new(&message)
+                            .span_err(span, format!("in this merge expression"))
+                            .span_err(
+                                record.span(),
+                                format!(
+                                    "the handler for `{}` has type: `{}`",
+                                    x,
+                                    handler_type.to_expr_tyenv(env)
+                                ),
+                            )
+                            .span_help(
+                                scrut.span(),
+                                format!(
+                                    "the corresponding variant has type: `{}`",
+                                    variant_type.to_expr_tyenv(env)
+                                ),
+                            )
+                            .help(format!(
                             "a handler for this variant must be a function \
                              that takes an input of type: `{}`",
                             variant_type.to_expr_tyenv(env)
-                        ))
-                        .format(),
-                    )
+                        )),
+                        message,
+                    );
                 }
             },
             // Union alternative without type
             Some(None) => Type::new_infer_universe(env, handler_type.clone())?,
-            None => return span_err("MergeHandlerMissingVariant"),
+            None => {
+                return span_err(TypeMessage::MergeHandlerMissingVariant(
+                    x.clone(),
+                ))
+            }
         };
         match &inferred_type {
             None => inferred_type = Some(handler_return_type),
             Some(t) => {
                 if t != &handler_return_type {
-                    return span_err("MergeHandlerTypeMismatch");
+                    return span_err(TypeMessage::MergeHandlerTypeMismatch {
+                        expected: t.to_expr_tyenv(env),
+                        found: handler_return_type.to_expr_tyenv(env),
+                    });
                 }
             }
         }
     }
     for x in variants.keys() {
         if !handlers.contains_key(x) {
-            return span_err("MergeVariantMissingHandler");
+            return span_err(TypeMessage::MergeVariantMissingHandler(
+                x.clone(),
+            ));
         }
     }
 
@@ -277,13 +304,18 @@ fn typecheck_merge(
     Ok(match (inferred_type, type_annot) {
         (Some(t1), Some(t2)) => {
             if t1 != t2 {
-                return span_err("MergeAnnotMismatch");
+                return span_err(TypeMessage::MergeAnnotMismatch {
+                    expected: t2.to_expr_tyenv(env),
+                    found: t1.to_expr_tyenv(env),
+                });
             }
             t1
         }
         (Some(t), None) => t,
         (None, Some(t)) => t,
-        (None, None) => return span_err("MergeEmptyNeedsAnnotation"),
+        (None, None) => {
+            return span_err(TypeMessage::MergeEmptyNeedsAnnotation)
+        }
     })
 }
 
@@ -292,7 +324,7 @@ pub fn typecheck_operation(
     span: Span,
     opkind: &OpKind<Tir<'_>>,
 ) -> Result<Type, TypeError> {
-    let span_err = |msg: &str| mk_span_err(span.clone(), msg);
+    let span_err = |msg: TypeMessage| mk_span_err(span.clone(), msg);
     use NirKind::{ListType, PiClosure, RecordType, UnionType};
     use OpKind::*;
 
@@ -302,59 +334,64 @@ pub fn typecheck_operation(
                 // TODO: store Type in closure
                 PiClosure { annot, closure, .. } => {
                     if arg.ty().as_nir() != annot {
-                        return mkerr(
-                            ErrorBuilder::new(format!(
-                                "wrong type of function argument"
-                            ))
-                            .span_err(
-                                f.span(),
-                                format!(
-                                    "this expects an argument of type: {}",
+                        let message = TypeMessage::TypeMismatch {
+                            expected: annot.to_expr_tyenv(env),
+                            found: arg.ty().to_expr_tyenv(env),
+                        };
+                        return mk_builder_err(
+                            ErrorBuilder::new(&message)
+                                .span_err(
+                                    f.span(),
+                                    format!(
+                                        "this expects an argument of type: {}",
+                                        annot.to_expr_tyenv(env),
+                                    ),
+                                )
+                                .span_err(
+                                    arg.span(),
+                                    format!(
+                                        "but this has type: {}",
+                                        arg.ty().to_expr_tyenv(env),
+                                    ),
+                                )
+                                .note(format!(
+                                    "expected type `{}`\n   found type `{}`",
                                     annot.to_expr_tyenv(env),
-                                ),
-                            )
-                            .span_err(
-                                arg.span(),
-                                format!(
-                                    "but this has type: {}",
                                     arg.ty().to_expr_tyenv(env),
-                                ),
-                            )
-                            .note(format!(
-                                "expected type `{}`\n   found type `{}`",
-                                annot.to_expr_tyenv(env),
-                                arg.ty().to_expr_tyenv(env),
-                            ))
-                            .format(),
+                                )),
+                            message,
                         );
                     }
 
                     let arg_nf = arg.eval(env);
                     Type::new_infer_universe(env, closure.apply(arg_nf))?
                 }
-                _ => return mkerr(
-                    ErrorBuilder::new(format!(
-                        "expected function, found `{}`",
-                        f.ty().to_expr_tyenv(env)
-                    ))
-                    .span_err(
-                        f.span(),
-                        format!("function application requires a function",),
-                    )
-                    .format(),
-                ),
+                _ => {
+                    let message =
+                        TypeMessage::NotAFunction(f.ty().to_expr_tyenv(env));
+                    return mk_builder_err(
+                        ErrorBuilder::new(&message).span_err(
+                            f.span(),
+                            format!("function application requires a function",),
+                        ),
+                        message,
+                    );
+                }
             }
         }
         BinOp(o, l, r) => typecheck_binop(env, span, *o, l, r)?,
         BoolIf(x, y, z) => {
             if *x.ty().kind() != NirKind::from_builtin(Builtin::Bool) {
-                return span_err("InvalidPredicate");
+                return span_err(TypeMessage::InvalidPredicate);
             }
             if y.ty().ty().as_const() != Some(Const::Type) {
-                return span_err("IfBranchMustBeTerm");
+                return span_err(TypeMessage::IfBranchMustBeTerm);
             }
             if y.ty() != z.ty() {
-                return span_err("IfBranchMismatch");
+                return span_err(TypeMessage::IfBranchMismatch {
+                    expected: y.ty().to_expr_tyenv(env),
+                    found: z.ty().to_expr_tyenv(env),
+                });
             }
 
             y.ty().clone()
@@ -364,56 +401,47 @@ pub fn typecheck_operation(
         }
         ToMap(record, annot) => {
             if record.ty().ty().as_const() != Some(Const::Type) {
-                return span_err("`toMap` only accepts records of type `Type`");
+                return span_err(TypeMessage::InvalidToMapRecordKind);
             }
             let record_t = record.ty();
             let kts = match record_t.kind() {
                 RecordType(kts) => kts,
-                _ => {
-                    return span_err("The argument to `toMap` must be a record")
-                }
+                _ => return span_err(TypeMessage::MustMapARecord),
             };
 
             if kts.is_empty() {
                 let annot = if let Some(annot) = annot {
                     annot
                 } else {
-                    return span_err(
-                        "`toMap` applied to an empty record requires a type \
-                         annotation",
-                    );
+                    return span_err(TypeMessage::MissingToMapType);
                 };
                 let annot_val = annot.eval_to_type(env)?;
 
-                let err_msg = "The type of `toMap x` must be of the form \
-                               `List { mapKey : Text, mapValue : T }`";
                 let arg = match annot_val.kind() {
                     ListType(t) => t,
-                    _ => return span_err(err_msg),
+                    _ => return span_err(TypeMessage::InvalidToMapType),
                 };
                 let kts = match arg.kind() {
                     RecordType(kts) => kts,
-                    _ => return span_err(err_msg),
+                    _ => return span_err(TypeMessage::InvalidToMapType),
                 };
                 if kts.len() != 2 {
-                    return span_err(err_msg);
+                    return span_err(TypeMessage::InvalidToMapType);
                 }
                 match kts.get("mapKey") {
                     Some(t) if *t == Nir::from_builtin(Builtin::Text) => {}
-                    _ => return span_err(err_msg),
+                    _ => return span_err(TypeMessage::InvalidToMapType),
                 }
                 match kts.get("mapValue") {
                     Some(_) => {}
-                    None => return span_err(err_msg),
+                    None => return span_err(TypeMessage::InvalidToMapType),
                 }
                 annot_val
             } else {
                 let entry_type = kts.iter().next().unwrap().1.clone();
                 for (_, t) in kts.iter() {
                     if *t != entry_type {
-                        return span_err(
-                            "Every field of the record must have the same type",
-                        );
+                        return span_err(TypeMessage::HeterogenousRecordToMap);
                     }
                 }
 
@@ -426,7 +454,10 @@ pub fn typecheck_operation(
                 if let Some(annot) = annot {
                     let annot_val = annot.eval_to_type(env)?;
                     if output_type != annot_val {
-                        return span_err("Annotation mismatch");
+                        return span_err(TypeMessage::ToMapAnnotMismatch {
+                            expected: annot_val.to_expr_tyenv(env),
+                            found: output_type.to_expr_tyenv(env),
+                        });
                     }
                 }
                 output_type
@@ -436,7 +467,11 @@ pub fn typecheck_operation(
             match scrut.ty().kind() {
                 RecordType(kts) => match kts.get(x) {
                     Some(val) => Type::new_infer_universe(env, val.clone())?,
-                    None => return span_err("MissingRecordField"),
+                    None => {
+                        return span_err(TypeMessage::MissingRecordField(
+                            x.clone(),
+                        ))
+                    }
                 },
                 NirKind::Const(_) => {
                     let scrut = scrut.eval_to_type(env)?;
@@ -450,25 +485,33 @@ pub fn typecheck_operation(
                             })
                             .to_type(scrut.ty()),
                             Some(None) => scrut,
-                            None => return span_err("MissingUnionField"),
+                            None => {
+                                return span_err(
+                                    TypeMessage::MissingUnionField(x.clone()),
+                                )
+                            }
                         },
-                        _ => return span_err("NotARecord"),
+                        _ => return span_err(TypeMessage::NotARecord),
                     }
                 }
-                _ => return span_err("NotARecord"),
+                _ => return span_err(TypeMessage::NotARecord),
             }
         }
         Projection(record, labels) => {
             let record_type = record.ty();
             let kts = match record_type.kind() {
                 RecordType(kts) => kts,
-                _ => return span_err("ProjectionMustBeRecord"),
+                _ => return span_err(TypeMessage::ProjectionMustBeRecord),
             };
 
             let mut new_kts = HashMap::new();
             for l in labels {
                 match kts.get(l) {
-                    None => return span_err("ProjectionMissingEntry"),
+                    None => {
+                        return span_err(TypeMessage::ProjectionMissingEntry(
+                            l.clone(),
+                        ))
+                    }
                     Some(t) => {
                         new_kts.insert(l.clone(), t.clone());
                     }
@@ -481,23 +524,33 @@ pub fn typecheck_operation(
             let record_type = record.ty();
             let rec_kts = match record_type.kind() {
                 RecordType(kts) => kts,
-                _ => return span_err("ProjectionMustBeRecord"),
+                _ => return span_err(TypeMessage::ProjectionMustBeRecord),
             };
 
             let selection_val = selection.eval_to_type(env)?;
             let sel_kts = match selection_val.kind() {
                 RecordType(kts) => kts,
-                _ => return span_err("ProjectionByExprTakesRecordType"),
+                _ => {
+                    return span_err(
+                        TypeMessage::ProjectionByExprTakesRecordType,
+                    )
+                }
             };
 
             for (l, sel_ty) in sel_kts {
                 match rec_kts.get(l) {
                     Some(rec_ty) => {
                         if rec_ty != sel_ty {
-                            return span_err("ProjectionWrongType");
+                            return span_err(TypeMessage::ProjectionWrongType(
+                                l.clone(),
+                            ));
                         }
                     }
-                    None => return span_err("ProjectionMissingEntry"),
+                    None => {
+                        return span_err(TypeMessage::ProjectionMissingEntry(
+                            l.clone(),
+                        ))
+                    }
                 }
             }
 
@@ -0,0 +1,244 @@
+//! Find what is at a position in the source text, for use by editors.
+use crate::semantics::{Hir, HirKind, ImportLocation, TyEnv};
+use crate::syntax::{
+    Expr, ExprKind, Label, ParsedSpan, SourcePosition, SourceRange, Span, V,
+};
+use crate::Definition;
+
+/// The source text of `span` and the byte offset of `position` in it.
+fn locate(span: Span, position: SourcePosition) -> Option<(ParsedSpan, usize)> {
+    match span {
+        Span::Parsed(span) => {
+            let offset = span.source().offset(position)?;
+            Some((span, offset))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `span` points to `offset` in the same source text as `root`.
+fn covers(root: &ParsedSpan, span: &Span, offset: usize) -> bool {
+    match span {
+        Span::Parsed(span) => {
+            span.same_source(root)
+                && span.start() <= offset
+                && offset < span.end()
+        }
+        _ => false,
+    }
+}
+
+/// The innermost subexpression of `hir` at `position` that typechecks, with its type.
+pub(crate) fn type_at(
+    hir: &Hir,
+    position: SourcePosition,
+) -> Option<(SourceRange, Expr)> {
+    let (root, offset) = locate(hir.span(), position)?;
+    if !covers(&root, &hir.span(), offset) {
+        return None;
+    }
+    type_at_offset(&root, offset, &TyEnv::new(), hir)
+}
+
+fn type_at_offset(
+    root: &ParsedSpan,
+    offset: usize,
+    env: &TyEnv,
+    hir: &Hir,
+) -> Option<(SourceRange, Expr)> {
+    let inner = match hir.kind() {
+        HirKind::Expr(kind) => {
+            let mut child = None;
+            kind.map_ref_maybe_binder(|binder, e| {
+                if child.is_none() && covers(root, &e.span(), offset) {
+                    child = Some((binder, e));
+                }
+            });
+            match child {
+                None => label_type(root, env, hir.span(), kind),
+                Some((binder, child)) => {
+                    // Extend the environment like typechecking does.
+                    let env = match (binder, kind) {
+                        (None, _) => env.clone(),
+                        (Some(l), ExprKind::Let(_, _, val, _)) => {
+                            let val = val.typecheck(env).ok()?;
+                            env.insert_value(l, val.eval(env), val.ty().clone())
+                        }
+                        (Some(l), ExprKind::Lam(_, annot, _))
+                        | (Some(l), ExprKind::Pi(_, annot, _)) => {
+                            let annot = annot.typecheck(env).ok()?;
+                            env.insert_type(l, annot.eval_to_type(env).ok()?)
+                        }
+                        _ => return None,
+                    };
+                    type_at_offset(root, offset, &env, child)
+                }
+            }
+        }
+        // Imports are typechecked as a whole.
+        HirKind::Var(_) | HirKind::Import(..) => None,
+    };
+    inner.or_else(|| {
+        let tir = hir.typecheck(env).ok()?;
+        Some((hir.span().range()?, tir.ty().to_expr_tyenv(env)))
+    })
+}
+
+/// The type of the variable bound by `kind`, with the location of its label. Used when the
+/// position is on a binder but not in any of its subexpressions.
+fn label_type(
+    root: &ParsedSpan,
+    env: &TyEnv,
+    span: Span,
+    kind: &ExprKind<Hir>,
+) -> Option<(SourceRange, Expr)> {
+    let (label, ty) = match kind {
+        ExprKind::Let(l, _, val, _) => {
+            (l, val.typecheck(env).ok()?.ty().clone())
+        }
+        ExprKind::Lam(l, annot, _) | ExprKind::Pi(l, annot, _) => {
+            (l, annot.typecheck(env).ok()?.eval_to_type(env).ok()?)
+        }
+        _ => return None,
+    };
+    match span {
+        Span::Parsed(span) if span.same_source(root) => {
+            Some((binder_label(&span, label), ty.to_expr_tyenv(env)))
+        }
+        _ => None,
+    }
+}
+
+/// What the variable or import at `position` refers to. `location` is where `expr` was read
+/// from.
+pub(crate) fn definition_at(
+    expr: &Expr,
+    location: &ImportLocation,
+    position: SourcePosition,
+) -> Option<Definition> {
+    let (root, offset) = locate(expr.span(), position)?;
+    if !covers(&root, &expr.span(), offset) {
+        return None;
+    }
+    // The enclosing binders, innermost last.
+    let mut binders: Vec<(&Label, Span)> = Vec::new();
+    let mut expr = expr;
+    loop {
+        match expr.kind() {
+            ExprKind::Var(V(label, idx)) => {
+                let (_, span) = binders
+                    .iter()
+                    .rev()
+                    .filter(|(l, _)| *l == label)
+                    .nth(*idx)?;
+                return match span {
+                    Span::Parsed(span) => {
+                        Some(Definition::Binding(binder_label(span, label)))
+                    }
+                    _ => None,
+                };
+            }
+            ExprKind::Import(import) => {
+                let import = import.traverse_ref(|_| Ok::<_, ()>(())).ok()?;
+                return match location.chain(&import.location, false).ok()? {
+                    ImportLocation::Local(path) => Some(Definition::File(path)),
+                    _ => None,
+                };
+            }
+            kind => {
+                let mut child = None;
+                kind.map_ref_maybe_binder(|binder, e| {
+                    if child.is_none() && covers(&root, &e.span(), offset) {
+                        child = Some((binder, e));
+                    }
+                });
+                let (binder, child) = child?;
+                if let Some(label) = binder {
+                    binders.push((label, expr.span()));
+                }
+                expr = child;
+            }
+        }
+    }
+}
+
+/// Where `label` is written in the binder that starts at `span`, e.g. `let x` or `λ(x : T)`.
+fn binder_label(span: &ParsedSpan, label: &Label) -> SourceRange {
+    let text = &span.source().text()[span.start()..span.end()];
+    let label = String::from(label);
+    // Skip the keyword, which could contain the label.
+    let skip = text
+        .find(|c: char| c == '(' || c.is_whitespace())
+        .unwrap_or(0);
+    match text[skip..].find(&label) {
+        Some(i) => {
+            let start = span.start() + skip + i;
+            span.with_offsets(start, start + label.len()).range()
+        }
+        None => span.range(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::syntax::{SourcePosition, SourceRange};
+    use crate::{Definition, Parsed};
+
+    fn pos(line: usize, column: usize) -> SourcePosition {
+        SourcePosition { line, column }
+    }
+
+    #[test]
+    fn type_at_should_find_the_innermost_subexpression() {
+        let text =
+            "let f = λ(x : Natural) → x + 1\nin  { a = f 2, b = True && 0 }";
+        let resolved = Parsed::parse_str(text).unwrap().skip_resolve().unwrap();
+        let type_at = |line, column| {
+            let (range, ty) = resolved.type_at(pos(line, column))?;
+            Some((range.start, range.end, ty.to_string()))
+        };
+        // The `x` in the body of the function
+        assert_eq!(
+            type_at(1, 26),
+            Some((pos(1, 26), pos(1, 27), "Natural".to_string()))
+        );
+        assert_eq!(
+            type_at(1, 5).map(|t| t.2),
+            Some("∀(x : Natural) → Natural".to_string())
+        );
+        assert_eq!(
+            type_at(2, 11).map(|t| t.2),
+            Some("∀(x : Natural) → Natural".to_string())
+        );
+        // The record doesn't typecheck, but its fields can be inspected.
+        assert_eq!(type_at(2, 20).map(|t| t.2), Some("Bool".to_string()));
+        assert_eq!(type_at(2, 22).map(|t| t.2), Some("Bool".to_string()));
+        assert_eq!(type_at(3, 1), None);
+    }
+
+    #[test]
+    fn definition_at_should_find_binders_and_imports() {
+        let path = PathBuf::from("./dir/main.dhall");
+        let text = "let x = 1\nlet x = x + 1\nin  λ(y : Natural) → x + y + x@1 + ../other.dhall";
+        let parsed = Parsed::parse_str_at(text, &path).unwrap();
+        let binding = |start: SourcePosition| {
+            Some(Definition::Binding(SourceRange {
+                file: Some("./dir/main.dhall".to_string()),
+                start,
+                end: pos(start.line, start.column + 1),
+            }))
+        };
+        // `x` in the second binding refers to the first one.
+        assert_eq!(parsed.definition_at(pos(2, 9)), binding(pos(1, 5)));
+        assert_eq!(parsed.definition_at(pos(3, 22)), binding(pos(2, 5)));
+        assert_eq!(parsed.definition_at(pos(3, 26)), binding(pos(3, 7)));
+        assert_eq!(parsed.definition_at(pos(3, 30)), binding(pos(1, 5)));
+        assert_eq!(
+            parsed.definition_at(pos(3, 37)),
+            Some(Definition::File(PathBuf::from("./other.dhall")))
+        );
+        assert_eq!(parsed.definition_at(pos(1, 9)), None);
+    }
+}
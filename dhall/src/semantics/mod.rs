@@ -1,3 +1,4 @@
+pub mod locate;
 pub mod nze;
 pub mod parse;
 pub mod resolve;
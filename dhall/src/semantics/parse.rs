@@ -4,22 +4,41 @@ use std::path::Path;
 use url::Url;
 
 use crate::error::Error;
-use crate::semantics::resolve::{download_http_text, ImportLocation};
+use crate::semantics::resolve::{ImportLocation, ImportResolver};
 use crate::syntax::binary;
 use crate::syntax::parse_expr;
-use crate::Parsed;
+use crate::{HttpOptions, Parsed};
 
-pub fn parse_file(f: &Path) -> Result<Parsed, Error> {
-    let text = std::fs::read_to_string(f)?;
-    let expr = parse_expr(&text)?;
+pub fn parse_file(
+    f: &Path,
+    resolver: &dyn ImportResolver,
+) -> Result<Parsed, Error> {
+    let text = resolver.read_local(f)?;
     let root = ImportLocation::Local(f.to_owned());
+    let expr = root.parse(&text)?;
     Ok(Parsed(expr, root))
 }
 
-pub fn parse_remote(url: Url) -> Result<Parsed, Error> {
-    let body = download_http_text(url.clone())?;
-    let expr = parse_expr(&body)?;
+pub fn parse_remote(
+    url: Url,
+    resolver: &dyn ImportResolver,
+    options: &HttpOptions,
+) -> Result<Parsed, Error> {
+    let body = resolver.fetch_remote(&url, &[], options)?.text;
     let root = ImportLocation::Remote(url);
+    let expr = root.parse(&body)?;
+    Ok(Parsed(expr, root))
+}
+
+#[cfg(feature = "async")]
+pub async fn parse_remote_async(
+    url: Url,
+    resolver: &dyn ImportResolver,
+    options: &HttpOptions,
+) -> Result<Parsed, Error> {
+    let body = resolver.fetch_remote_async(&url, &[], options).await?.text;
+    let root = ImportLocation::Remote(url);
+    let expr = root.parse(&body)?;
     Ok(Parsed(expr, root))
 }
 
@@ -29,6 +48,12 @@ pub fn parse_str(s: &str) -> Result<Parsed, Error> {
     Ok(Parsed(expr, root))
 }
 
+pub fn parse_str_at(s: &str, path: &Path) -> Result<Parsed, Error> {
+    let root = ImportLocation::Local(path.to_owned());
+    let expr = root.parse(s)?;
+    Ok(Parsed(expr, root))
+}
+
 pub fn parse_binary(data: &[u8]) -> Result<Parsed, Error> {
     let expr = binary::decode(data)?;
     let root = ImportLocation::Missing;
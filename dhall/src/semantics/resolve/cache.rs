@@ -1,3 +1,4 @@
+use futures_util::future::Future;
 use std::env;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -7,7 +8,7 @@ use crate::parse::parse_binary_file;
 use crate::semantics::{Import, TypedHir};
 use crate::syntax::Hash;
 use crate::syntax::{binary, Expr};
-use crate::Parsed;
+use crate::{CacheDir, Parsed, ResolveOptions};
 use std::env::VarError;
 use std::ffi::OsStr;
 use std::fs::File;
@@ -45,20 +46,22 @@ pub struct Cache {
     cache_dir: Option<PathBuf>,
 }
 
+fn init_cache_dir(path: PathBuf) -> Result<PathBuf, CacheError> {
+    if !path.exists() {
+        std::fs::create_dir_all(path.as_path())
+            .map(|_| path)
+            .map_err(|e| CacheError::InitialisationError { cause: e })
+    } else {
+        Ok(path)
+    }
+}
+
 impl Cache {
     fn new_with_provider(
         provider: impl Fn(&str) -> Result<String, VarError> + Copy,
     ) -> Cache {
         // Should warn that we can't initialize cache on error
-        let cache_dir = load_cache_dir(provider).and_then(|path| {
-            if !path.exists() {
-                std::fs::create_dir_all(path.as_path())
-                    .map(|_| path)
-                    .map_err(|e| CacheError::InitialisationError { cause: e })
-            } else {
-                Ok(path)
-            }
-        });
+        let cache_dir = load_cache_dir(provider).and_then(init_cache_dir);
         Cache {
             cache_dir: cache_dir.ok(),
         }
@@ -67,6 +70,27 @@ impl Cache {
     pub fn new() -> Cache {
         Cache::new_with_provider(|name| env::var(OsStr::new(name)))
     }
+
+    /// Use the given directory as a cache, creating it if needed.
+    pub fn with_dir(path: &Path) -> Cache {
+        Cache {
+            cache_dir: init_cache_dir(path.to_owned()).ok(),
+        }
+    }
+
+    /// A cache that never stores nor finds anything.
+    pub fn disabled() -> Cache {
+        Cache { cache_dir: None }
+    }
+
+    pub fn from_options(options: &ResolveOptions) -> Cache {
+        match &options.cache_dir {
+            CacheDir::Default if options.remote_imports => Cache::new(),
+            CacheDir::Default => Cache::disabled(),
+            CacheDir::Path(path) => Cache::with_dir(path),
+            CacheDir::Disabled => Cache::disabled(),
+        }
+    }
 }
 
 impl Cache {
@@ -84,6 +108,11 @@ impl Cache {
             .filter(|cache_file| cache_file.exists())
     }
 
+    /// Whether the cache has an entry for this import.
+    pub fn contains(&self, import: &Import) -> bool {
+        self.search_cache_file(import).is_some()
+    }
+
     fn search_cache(&self, import: &Import) -> Option<Result<Parsed, Error>> {
         self.search_cache_file(import)
             .map(|cache_file| parse_binary_file(cache_file.as_path()))
@@ -101,46 +130,48 @@ impl Cache {
             .map(|cache_file| save_expr(cache_file.as_path(), expr));
     }
 
-    pub fn caching_import<F, R>(
+    pub async fn caching_import<F, FF, R, RF>(
         &self,
         import: &Import,
         fetcher: F,
         mut resolver: R,
     ) -> Result<TypedHir, Error>
     where
-        F: FnOnce() -> Result<Parsed, Error>,
-        R: FnMut(Parsed) -> Result<TypedHir, Error>,
+        F: FnOnce() -> FF,
+        FF: Future<Output = Result<Parsed, Error>>,
+        R: FnMut(Parsed) -> RF,
+        RF: Future<Output = Result<TypedHir, Error>>,
     {
         // Lookup the cache
-        self.search_cache(import)
-            // On cache found
-            .and_then(|cache_result| {
-                // Try to resolve the cache imported content
-                match cache_result.and_then(|parsed| resolver(parsed)).and_then(
-                    |typed_hir| {
-                        check_hash(import.hash.as_ref().unwrap(), typed_hir)
-                    },
-                ) {
-                    // Cache content is invalid (can't be parsed / can't be resolved / content sha invalid )
-                    Err(_) => {
-                        // Delete cache file since it's invalid
-                        self.delete_cache(import);
-                        // Result as there were no cache
-                        None
-                    }
-                    // Cache valid
-                    r => Some(r),
+        if let Some(cache_result) = self.search_cache(import) {
+            // Try to resolve the cache imported content
+            let typed_hir = match cache_result {
+                Ok(parsed) => resolver(parsed).await,
+                Err(err) => Err(err),
+            };
+            match typed_hir.and_then(|typed_hir| {
+                check_hash(import.hash.as_ref().unwrap(), typed_hir)
+            }) {
+                // Cache content is invalid (can't be parsed / can't be resolved / content sha invalid )
+                Err(_) => {
+                    // Delete cache file since it's invalid
+                    self.delete_cache(import);
+                    // Continue as if there were no cache
                 }
-            })
-            .unwrap_or_else(|| {
-                // Fetch and resolve as provided
-                let imported = fetcher().and_then(resolver);
-                // Save in cache the result if ok
-                let _ = imported.as_ref().map(|(hir, _)| {
-                    self.save_expr(import, &hir.to_expr_noopts())
-                });
-                imported
-            })
+                // Cache valid
+                r => return r,
+            }
+        }
+        // Fetch and resolve as provided
+        let imported = match fetcher().await {
+            Ok(parsed) => resolver(parsed).await,
+            Err(err) => Err(err),
+        };
+        // Save in cache the result if ok
+        let _ = imported
+            .as_ref()
+            .map(|(hir, _)| self.save_expr(import, &hir.to_expr_noopts()));
+        imported
     }
 }
 
@@ -176,10 +207,16 @@ mod test {
     use crate::syntax::{
         parse_expr, ExprKind, ImportMode, ImportTarget, NumKind, Span,
     };
+    use futures_util::future::{ready, FutureExt};
     use rand::distributions::Alphanumeric;
     use rand::Rng;
     use std::env::temp_dir;
 
+    fn resolve_and_typecheck(parsed: Parsed) -> Result<TypedHir, Error> {
+        let result = parsed.resolve()?.typecheck()?;
+        Ok((result.normalize().to_hir(), result.ty))
+    }
+
     #[cfg(any(unix, windows))]
     #[test]
     fn load_cache_dir_should_result_xdg_cache_first() {
@@ -257,6 +294,39 @@ mod test {
         std::fs::remove_dir_all(dir.as_path()).unwrap();
     }
 
+    #[test]
+    fn from_options_should_not_use_default_cache_offline() {
+        let options = ResolveOptions {
+            remote_imports: false,
+            ..ResolveOptions::default()
+        };
+        assert_eq!(Cache::from_options(&options), Cache::disabled());
+    }
+
+    #[test]
+    fn from_options_should_create_provided_cache_folder() {
+        let test_id = rand::thread_rng()
+            .sample_iter(Alphanumeric)
+            .take(36)
+            .collect::<String>();
+        let dir = temp_dir().join(test_id);
+
+        let options = ResolveOptions {
+            remote_imports: false,
+            cache_dir: CacheDir::Path(dir.join("dhall")),
+            ..ResolveOptions::default()
+        };
+        let actual = Cache::from_options(&options);
+        assert_eq!(
+            actual,
+            Cache {
+                cache_dir: Some(dir.join("dhall"))
+            }
+        );
+        assert!(dir.join("dhall").exists());
+        std::fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
     #[test]
     fn caching_import_should_load_cache() -> Result<(), Error> {
         let test_id = rand::thread_rng()
@@ -285,15 +355,17 @@ mod test {
 
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || panic!("Should not fetch import"),
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || async { panic!("Should not fetch import") },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok());
         assert_eq!(resolve_counter, 1);
@@ -316,18 +388,20 @@ mod test {
         let mut resolve_counter = 0;
         let mut fetcher_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(resolve_counter, 1);
@@ -349,18 +423,20 @@ mod test {
         let mut resolve_counter = 0;
         let mut fetcher_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(resolve_counter, 1);
@@ -391,18 +467,20 @@ mod test {
         let mut fetcher_counter = 0;
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(resolve_counter, 1);
@@ -439,18 +517,20 @@ mod test {
         let mut fetcher_counter = 0;
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(fetcher_counter, 1, "Should fetch since cache is invalid");
@@ -492,25 +572,25 @@ mod test {
         let mut fetcher_counter = 0;
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                match resolve_counter {
-                    1 => Err(Error::new(ErrorKind::Cache(
-                        CacheError::CacheHashInvalid,
-                    ))),
-                    _ => {
-                        let result = parsed.resolve()?.typecheck()?;
-                        Ok((result.normalize().to_hir(), result.ty))
-                    }
-                }
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(match resolve_counter {
+                        1 => Err(Error::new(ErrorKind::Cache(
+                            CacheError::CacheHashInvalid,
+                        ))),
+                        _ => resolve_and_typecheck(parsed),
+                    })
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(fetcher_counter, 1, "Should fetch since cache is invalid");
@@ -552,18 +632,20 @@ mod test {
         let mut fetcher_counter = 0;
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(fetcher_counter, 1, "Should fetch since cache is invalid");
@@ -598,18 +680,20 @@ mod test {
         let mut fetcher_counter = 0;
         let mut resolve_counter = 0;
 
-        let result = cache.caching_import(
-            &import,
-            || {
-                fetcher_counter += 1;
-                parse_str("1")
-            },
-            |parsed| {
-                resolve_counter += 1;
-                let result = parsed.resolve()?.typecheck()?;
-                Ok((result.normalize().to_hir(), result.ty))
-            },
-        );
+        let result = cache
+            .caching_import(
+                &import,
+                || {
+                    fetcher_counter += 1;
+                    ready(parse_str("1"))
+                },
+                |parsed| {
+                    resolve_counter += 1;
+                    ready(resolve_and_typecheck(parsed))
+                },
+            )
+            .now_or_never()
+            .unwrap();
 
         assert!(result.is_ok(), "caching_import Should be valid");
         assert_eq!(fetcher_counter, 1, "Should fetch since cache is mising");
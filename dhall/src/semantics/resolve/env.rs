@@ -69,35 +69,43 @@ impl ImportEnv {
         ImportEnv::default()
     }
 
-    pub fn handle_import(
+    /// Whether the import at this location has been resolved already.
+    pub fn is_resolved(&self, location: &ImportLocation) -> bool {
+        self.cache.contains_key(location)
+    }
+
+    /// Start resolving the import at `location`. Returns its value if it was resolved already.
+    /// Otherwise the import must be finished with `finish_import`.
+    pub fn start_import(
         &mut self,
-        mut location: ImportLocation,
-        do_resolve: impl FnOnce(&mut Self) -> Result<TypedHir, Error>,
-    ) -> Result<TypedHir, Error> {
-        if self.stack.contains(&location) {
-            return Err(
-                ImportError::ImportCycle(self.stack.clone(), location).into()
-            );
+        location: &ImportLocation,
+    ) -> Result<Option<TypedHir>, Error> {
+        if self.stack.contains(location) {
+            return Err(ImportError::ImportCycle(
+                self.stack.clone(),
+                location.clone(),
+            )
+            .into());
         }
-        Ok(match self.cache.get(&location) {
-            Some(expr) => expr.clone(),
-            None => {
-                let expr = {
-                    // Push the current location on the stack
-                    self.stack.push(location);
-                    // Resolve the import recursively
-                    // WARNING: do not propagate errors here or the stack will get messed up.
-                    let result = do_resolve(self);
-                    // Remove location from the stack.
-                    location = self.stack.pop().unwrap();
-                    result
-                }?;
-
-                // Add the resolved import to the cache
-                self.cache.insert(location, expr.clone());
+        if let Some(expr) = self.cache.get(location) {
+            return Ok(Some(expr.clone()));
+        }
+        // Push the current location on the stack
+        self.stack.push(location.clone());
+        Ok(None)
+    }
 
-                expr
-            }
-        })
+    /// Finish resolving the last started import with the result of resolving it recursively.
+    pub fn finish_import(
+        &mut self,
+        result: Result<TypedHir, Error>,
+    ) -> Result<TypedHir, Error> {
+        let result = result.map_err(|err| err.in_imports(&self.stack));
+        // Remove location from the stack.
+        let location = self.stack.pop().unwrap();
+        let expr = result?;
+        // Add the resolved import to the cache
+        self.cache.insert(location, expr.clone());
+        Ok(expr)
     }
 }
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use url::Url;
+
+use crate::builtins::Builtin;
+use crate::error::Error;
+use crate::operations::OpKind;
+use crate::semantics::{
+    skip_resolve_expr, Hir, ImportLocation, ImportResolver, Nir, NirKind,
+};
+use crate::syntax::{Expr, ExprKind, Span};
+use crate::{CacheDir, HttpOptions, Parsed, ResolveOptions, Resolved};
+
+/// HTTP headers, as a list of name-value pairs.
+pub type Headers = Vec<(String, String)>;
+
+/// The headers to send to each origin, as configured in `headers.dhall`.
+pub type OriginHeaders = HashMap<String, Headers>;
+
+const HEADERS_ENV_VAR: &str = "DHALL_HEADERS";
+
+fn mkexpr(kind: ExprKind<Expr>) -> Expr {
+    Expr::new(kind, Span::Artificial)
+}
+
+/// `List { mapKey : Text, mapValue : value_ty }`
+fn make_map_type(value_ty: Expr) -> Expr {
+    let mut record = BTreeMap::default();
+    record.insert("mapKey".into(), mkexpr(ExprKind::Builtin(Builtin::Text)));
+    record.insert("mapValue".into(), value_ty);
+    mkexpr(ExprKind::Op(OpKind::App(
+        mkexpr(ExprKind::Builtin(Builtin::List)),
+        mkexpr(ExprKind::RecordType(record)),
+    )))
+}
+
+/// Extract the entries of a normalized `List { mapKey : Text, mapValue : T }`.
+fn map_entries(nir: &Nir) -> Vec<(String, Nir)> {
+    let entries = match nir.kind() {
+        NirKind::NEListLit(entries) => entries,
+        _ => return Vec::new(),
+    };
+    entries
+        .iter()
+        .filter_map(|entry| match entry.kind() {
+            NirKind::RecordLit(kvs) => {
+                let key = match kvs.get("mapKey")?.kind() {
+                    NirKind::TextLit(key) => key.as_text()?,
+                    _ => return None,
+                };
+                Some((key, kvs.get("mapValue")?.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn nir_to_headers(nir: &Nir) -> Headers {
+    map_entries(nir)
+        .into_iter()
+        .filter_map(|(name, value)| match value.kind() {
+            NirKind::TextLit(value) => Some((name, value.as_text()?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Typecheck and evaluate the expression of a `using` clause.
+pub fn eval_headers(hir: Hir) -> Result<Headers, Error> {
+    let text_type = mkexpr(ExprKind::Builtin(Builtin::Text));
+    let ty = skip_resolve_expr(&make_map_type(text_type))?;
+    let headers = Resolved(hir).typecheck_with(&ty)?.normalize();
+    Ok(nir_to_headers(headers.as_nir()))
+}
+
+/// The key of `url` in the origin headers configuration, e.g. `example.com:443`.
+fn origin(url: &Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// Add the headers configured for the origin of `url` to the headers from a `using` clause.
+/// Headers from the `using` clause take precedence.
+pub fn merge_headers(
+    origin_headers: &OriginHeaders,
+    url: &Url,
+    headers: &[(String, String)],
+) -> Headers {
+    let configured = origin_headers
+        .get(&origin(url))
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| {
+            !headers.iter().any(|(n, _)| n.eq_ignore_ascii_case(name))
+        });
+    configured.chain(headers).cloned().collect()
+}
+
+/// Find the configuration file for origin headers: `$XDG_CONFIG_HOME/dhall/headers.dhall`, or
+/// `~/.config/dhall/headers.dhall`.
+fn headers_file(resolver: &dyn ImportResolver) -> Option<PathBuf> {
+    let config_dir = resolver
+        .read_env("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| {
+            resolver
+                .read_env("HOME")
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .ok()?;
+    Some(config_dir.join("dhall").join("headers.dhall"))
+}
+
+/// Load the origin headers configuration, from the `DHALL_HEADERS` environment variable or from
+/// the `headers.dhall` configuration file. A missing configuration means no origin headers.
+pub fn load_origin_headers(
+    resolver: &dyn ImportResolver,
+) -> Result<OriginHeaders, Error> {
+    let parsed = match resolver.read_env(HEADERS_ENV_VAR) {
+        Ok(text) => {
+            let location = ImportLocation::Env(HEADERS_ENV_VAR.to_owned());
+            Parsed(location.parse(&text)?, location)
+        }
+        Err(_) => {
+            let path = match headers_file(resolver) {
+                Some(path) => path,
+                None => return Ok(OriginHeaders::new()),
+            };
+            match resolver.read_local(&path) {
+                Ok(text) => {
+                    let location = ImportLocation::Local(path);
+                    Parsed(location.parse(&text)?, location)
+                }
+                Err(_) => return Ok(OriginHeaders::new()),
+            }
+        }
+    };
+    // Remote imports would need the origin headers themselves.
+    let options = ResolveOptions {
+        remote_imports: false,
+        env_imports: true,
+        cache_dir: CacheDir::Disabled,
+        http: HttpOptions::default(),
+    };
+    let text_type = mkexpr(ExprKind::Builtin(Builtin::Text));
+    let ty = skip_resolve_expr(&make_map_type(make_map_type(text_type)))?;
+    let config = parsed
+        .resolve_with(resolver, &options)?
+        .typecheck_with(&ty)?
+        .normalize();
+    Ok(map_entries(config.as_nir())
+        .into_iter()
+        .map(|(origin, headers)| (origin, nir_to_headers(&headers)))
+        .collect())
+}
@@ -1,8 +1,12 @@
 pub mod cache;
 pub mod env;
+pub mod headers;
 pub mod hir;
 pub mod resolve;
+pub mod resolver;
 pub use cache::*;
 pub use env::*;
+pub use headers::*;
 pub use hir::*;
 pub use resolve::*;
+pub use resolver::*;
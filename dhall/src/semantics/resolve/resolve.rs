@@ -1,24 +1,31 @@
+use futures_util::future::{
+    join_all, ready, Future, FutureExt, LocalBoxFuture,
+};
 use itertools::Itertools;
+use once_cell::unsync::OnceCell;
 use std::borrow::Cow;
-use std::collections::BTreeMap;
-use std::env;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::iter::once;
 use std::path::PathBuf;
 use url::Url;
 
 use crate::builtins::Builtin;
-use crate::error::ErrorBuilder;
 use crate::error::{Error, ImportError};
+use crate::error::{ErrorBuilder, TypeMessage};
 use crate::operations::{BinOp, OpKind};
-use crate::semantics::{mkerr, Cache, Hir, HirKind, ImportEnv, NameEnv, Type};
+use crate::semantics::{
+    eval_headers, load_origin_headers, merge_headers, mk_builder_err, Cache,
+    DefaultResolver, Headers, Hir, HirKind, ImportEnv, ImportResolver, NameEnv,
+    OriginHeaders, RemoteResponse, Type,
+};
 use crate::syntax;
 use crate::syntax::{
-    Expr, ExprKind, FilePath, FilePrefix, Hash, ImportMode, ImportTarget,
-    Label, Span, UnspannedExpr, URL,
+    parse_expr_with_origin, Expr, ExprKind, FilePath, FilePrefix, Hash,
+    ImportMode, ImportTarget, Label, Span, UnspannedExpr, URL,
 };
-use crate::{Parsed, Resolved};
+use crate::{FreezeOptions, Parsed, ResolveOptions, Resolved};
 
-// TODO: evaluate import headers
 pub type Import = syntax::Import<()>;
 
 /// Owned Hir with a type. Different from Tir because the Hir is owned.
@@ -42,7 +49,7 @@ impl ImportLocation {
     /// location, or error if not allowed.
     /// `sanity_check` indicates whether to check if that location is allowed to be referenced,
     /// for example to prevent a remote file from reading an environment variable.
-    fn chain(
+    pub(crate) fn chain(
         &self,
         target: &ImportTarget<()>,
         sanity_check: bool,
@@ -52,12 +59,6 @@ impl ImportLocation {
                 self.chain_local(*prefix, path)?
             }
             ImportTarget::Remote(remote) => {
-                if sanity_check {
-                    if let ImportLocation::Remote(..) = self {
-                        // TODO: allow if CORS check passes
-                        return Err(ImportError::SanityCheck.into());
-                    }
-                }
                 let mut url = Url::parse(&format!(
                     "{}://{}",
                     remote.scheme, remote.authority
@@ -142,29 +143,68 @@ impl ImportLocation {
         })
     }
 
-    fn fetch_dhall(self) -> Result<Parsed, Error> {
-        Ok(match self {
-            ImportLocation::Local(path) => Parsed::parse_file(&path)?,
-            ImportLocation::Remote(url) => Parsed::parse_remote(url)?,
-            ImportLocation::Env(var_name) => {
-                let val = match env::var(var_name) {
-                    Ok(val) => val,
-                    Err(_) => return Err(ImportError::MissingEnvVar.into()),
-                };
-                Parsed::parse_str(&val)?
+    /// A description of this location for error messages: the path, the url, or `env:NAME`.
+    pub fn to_origin(&self) -> Option<String> {
+        match self {
+            ImportLocation::Missing => None,
+            _ => Some(self.to_string()),
+        }
+    }
+
+    /// Parse text read from this location.
+    pub(crate) fn parse(&self, text: &str) -> Result<Expr, Error> {
+        let origin = self.to_origin();
+        parse_expr_with_origin(text, origin.as_deref())
+            .map_err(|err| Error::parse_error_at(err, origin.as_deref()))
+    }
+
+    /// Check that fetching from this location is allowed by the provided options.
+    pub(crate) fn check_allowed(
+        &self,
+        options: &ResolveOptions,
+    ) -> Result<(), Error> {
+        match self {
+            ImportLocation::Remote(..) if !options.remote_imports => {
+                Err(ImportError::RemoteImportsDisabled.into())
             }
-            ImportLocation::Missing => return Err(ImportError::Missing.into()),
-        })
+            ImportLocation::Env(..) if !options.env_imports => {
+                Err(ImportError::EnvImportsDisabled.into())
+            }
+            _ => Ok(()),
+        }
     }
 
-    fn fetch_text(self) -> Result<String, Error> {
+    async fn fetch_dhall(
+        self,
+        ctx: &ResolveContext<'_>,
+        parent: &ImportLocation,
+        headers: &[(String, String)],
+    ) -> Result<Parsed, Error> {
+        let text = self.fetch_text(ctx, parent, headers).await?;
+        let expr = self.parse(&text)?;
+        Ok(Parsed(expr, self))
+    }
+
+    /// `headers` are the headers to send if this is a remote location. `parent` is the location
+    /// this import was found in.
+    async fn fetch_text(
+        &self,
+        ctx: &ResolveContext<'_>,
+        parent: &ImportLocation,
+        headers: &[(String, String)],
+    ) -> Result<String, Error> {
+        let resolver = ctx.resolver;
         Ok(match self {
-            ImportLocation::Local(path) => std::fs::read_to_string(&path)?,
-            ImportLocation::Remote(url) => download_http_text(url)?,
-            ImportLocation::Env(var_name) => match env::var(var_name) {
-                Ok(val) => val,
-                Err(_) => return Err(ImportError::MissingEnvVar.into()),
-            },
+            ImportLocation::Local(path) => resolver.read_local(path)?,
+            ImportLocation::Remote(url) => {
+                let response =
+                    ctx.fetch_remote(ctx.request(url, headers)?).await?;
+                if let ImportLocation::Remote(parent) = parent {
+                    cors_check(parent, url, &response)?;
+                }
+                response.text
+            }
+            ImportLocation::Env(var_name) => resolver.read_env(var_name)?,
             ImportLocation::Missing => return Err(ImportError::Missing.into()),
         })
     }
@@ -192,22 +232,39 @@ impl ImportLocation {
     }
 }
 
-fn mkexpr(kind: UnspannedExpr) -> Expr {
-    Expr::new(kind, Span::Artificial)
+impl std::fmt::Display for ImportLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImportLocation::Local(path) => write!(f, "{}", path.display()),
+            ImportLocation::Remote(url) => write!(f, "{}", url),
+            ImportLocation::Env(name) => write!(f, "env:{}", name),
+            ImportLocation::Missing => write!(f, "missing"),
+        }
+    }
 }
 
-// TODO: error handling
-#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
-pub(crate) fn download_http_text(url: Url) -> Result<String, Error> {
-    Ok(reqwest::blocking::get(url).unwrap().text().unwrap())
-}
-#[cfg(all(not(target_arch = "wasm32"), not(feature = "reqwest")))]
-pub(crate) fn download_http_text(_url: Url) -> Result<String, Error> {
-    panic!("Remote imports are disabled in this build of dhall-rust")
+/// Check that a remote import from another origin is allowed by the `Access-Control-Allow-Origin`
+/// header of its response.
+fn cors_check(
+    parent: &Url,
+    url: &Url,
+    response: &RemoteResponse,
+) -> Result<(), Error> {
+    let parent_origin = parent.origin();
+    if parent_origin == url.origin() {
+        return Ok(());
+    }
+    match response.header("Access-Control-Allow-Origin") {
+        Some("*") => Ok(()),
+        Some(allowed) if allowed == parent_origin.ascii_serialization() => {
+            Ok(())
+        }
+        _ => Err(ImportError::CorsCheck.into()),
+    }
 }
-#[cfg(target_arch = "wasm32")]
-pub(crate) fn download_http_text(_url: Url) -> Result<String, Error> {
-    panic!("Remote imports are not supported on wasm yet")
+
+fn mkexpr(kind: UnspannedExpr) -> Expr {
+    Expr::new(kind, Span::Artificial)
 }
 
 fn make_aslocation_uniontype() -> Expr {
@@ -220,33 +277,247 @@ fn make_aslocation_uniontype() -> Expr {
     mkexpr(ExprKind::UnionType(union))
 }
 
-fn resolve_one_import(
-    env: &mut ImportEnv,
-    cache: &Cache,
+/// A request for a remote import: the url and the headers to send.
+type Request = (Url, Headers);
+
+type ImportFuture<'a> = LocalBoxFuture<'a, Result<TypedHir, Error>>;
+
+/// Resolves an import found in an expression, given the value of its `using` clause.
+type ResolveImportFn<'a> =
+    dyn Fn(Import, Option<Hir>, Span) -> ImportFuture<'a> + 'a;
+
+/// The state of the resolution of an expression.
+struct ResolveContext<'a> {
+    cache: Cache,
+    options: &'a ResolveOptions,
+    resolver: &'a dyn ImportResolver,
+    /// Loaded on the first remote import.
+    origin_headers: OnceCell<OriginHeaders>,
+    env: RefCell<ImportEnv>,
+    /// Whether to download the remote imports found in a file concurrently, with
+    /// `fetch_remote_async`, before resolving them.
+    concurrent: bool,
+    /// The responses downloaded ahead of resolution.
+    responses: RefCell<HashMap<Request, Result<RemoteResponse, Error>>>,
+}
+
+impl<'a> ResolveContext<'a> {
+    fn new(
+        resolver: &'a dyn ImportResolver,
+        options: &'a ResolveOptions,
+    ) -> Self {
+        ResolveContext {
+            cache: Cache::from_options(options),
+            options,
+            resolver,
+            origin_headers: OnceCell::new(),
+            env: RefCell::new(ImportEnv::new()),
+            concurrent: false,
+            responses: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn origin_headers(&self) -> Result<&OriginHeaders, Error> {
+        self.origin_headers
+            .get_or_try_init(|| load_origin_headers(self.resolver))
+    }
+
+    /// The request for a remote import: the headers from `using` or from the parent, merged with
+    /// the headers configured for its origin.
+    fn request(
+        &self,
+        url: &Url,
+        headers: &[(String, String)],
+    ) -> Result<Request, Error> {
+        let headers = merge_headers(self.origin_headers()?, url, headers);
+        Ok((url.clone(), headers))
+    }
+
+    async fn fetch_remote(
+        &self,
+        request: Request,
+    ) -> Result<RemoteResponse, Error> {
+        let prefetched = self.responses.borrow_mut().remove(&request);
+        if let Some(response) = prefetched {
+            return response;
+        }
+        let (url, headers) = &request;
+        let http = &self.options.http;
+        if self.concurrent {
+            self.resolver.fetch_remote_async(url, headers, http).await
+        } else {
+            self.resolver.fetch_remote(url, headers, http)
+        }
+    }
+
+    /// Download the remote imports found in a file concurrently, ahead of its resolution. Imports
+    /// with a `using` clause are downloaded during resolution, once their headers are known.
+    async fn prefetch(
+        &self,
+        expr: &Expr,
+        location: &ImportLocation,
+        headers: &[(String, String)],
+    ) {
+        let mut imports = Vec::new();
+        collect_imports(expr, &mut imports);
+        let mut requests: Vec<Request> = Vec::new();
+        for import in imports {
+            if let Some(request) =
+                self.prefetch_request(import, location, headers)
+            {
+                if !requests.contains(&request)
+                    && !self.responses.borrow().contains_key(&request)
+                {
+                    requests.push(request);
+                }
+            }
+        }
+        let responses = join_all(requests.iter().map(|(url, headers)| {
+            self.resolver
+                .fetch_remote_async(url, headers, &self.options.http)
+        }))
+        .await;
+        self.responses
+            .borrow_mut()
+            .extend(requests.into_iter().zip(responses));
+    }
+
+    /// The request that resolution will send for this import, if it is a remote import that
+    /// hasn't been resolved yet.
+    fn prefetch_request(
+        &self,
+        import: &syntax::Import<Expr>,
+        parent: &ImportLocation,
+        parent_headers: &[(String, String)],
+    ) -> Option<Request> {
+        match &import.location {
+            _ if import.mode == ImportMode::Location => return None,
+            ImportTarget::Remote(URL {
+                headers: Some(..), ..
+            }) => return None,
+            _ => {}
+        }
+        let import: Import =
+            import.traverse_ref(|_| Ok::<_, Error>(())).ok()?;
+        let (location, headers) =
+            locate_import(self, &import, parent, parent_headers, None).ok()?;
+        let url = match &location {
+            ImportLocation::Remote(url) => url,
+            _ => return None,
+        };
+        let cached =
+            import.mode == ImportMode::Code && self.cache.contains(&import);
+        if cached || self.env.borrow().is_resolved(&location) {
+            return None;
+        }
+        self.request(url, &headers).ok()
+    }
+}
+
+fn collect_imports<'a>(
+    expr: &'a Expr,
+    imports: &mut Vec<&'a syntax::Import<Expr>>,
+) {
+    if let ExprKind::Import(import) = expr.kind() {
+        imports.push(import);
+    }
+    let _ = expr.kind().traverse_ref(|e| {
+        collect_imports(e, imports);
+        Ok::<_, ()>(())
+    });
+}
+
+/// Run a resolution that doesn't download imports concurrently, and so never has to wait.
+fn now<T>(resolution: impl Future<Output = T>) -> T {
+    resolution
+        .now_or_never()
+        .expect("only concurrent downloads can wait")
+}
+
+/// Compute the location of an import found in `parent`, and the headers to fetch it with.
+/// `using_headers` is the value of its `using` clause, if any.
+fn locate_import(
+    ctx: &ResolveContext<'_>,
     import: &Import,
-    location: &ImportLocation,
+    parent: &ImportLocation,
+    parent_headers: &[(String, String)],
+    using_headers: Option<Headers>,
+) -> Result<(ImportLocation, Headers), Error> {
+    let do_sanity_check = import.mode != ImportMode::Location;
+    let location = parent.chain(&import.location, do_sanity_check)?;
+    // Relative imports from a remote location reuse the headers of that location.
+    let headers: Headers = match (using_headers, &import.location) {
+        (Some(headers), _) => headers,
+        (None, ImportTarget::Local(..)) => parent_headers.to_vec(),
+        (None, _) => Vec::new(),
+    };
+    if import.mode != ImportMode::Location {
+        location.check_allowed(ctx.options)?;
+    }
+    Ok((location, headers))
+}
+
+/// `parent_headers` are the headers used to fetch the current location, if it is remote.
+async fn resolve_one_import(
+    ctx: &ResolveContext<'_>,
+    import: Import,
+    using: Option<Hir>,
+    parent: &ImportLocation,
+    parent_headers: &[(String, String)],
     span: Span,
 ) -> Result<TypedHir, Error> {
-    let do_sanity_check = import.mode != ImportMode::Location;
-    let location = location.chain(&import.location, do_sanity_check)?;
-    env.handle_import(location.clone(), |env| match import.mode {
+    let using_headers = match using {
+        Some(headers) => Some(eval_headers(headers)?),
+        None => None,
+    };
+    let (location, headers) =
+        locate_import(ctx, &import, parent, parent_headers, using_headers)?;
+    if let Some(expr) = ctx.env.borrow_mut().start_import(&location)? {
+        return Ok(expr);
+    }
+    // WARNING: do not propagate errors here or the stack will get messed up.
+    let result =
+        load_import(ctx, &import, location, parent, &headers, span).await;
+    ctx.env.borrow_mut().finish_import(result)
+}
+
+/// Fetch and resolve an import that hasn't been resolved yet.
+async fn load_import(
+    ctx: &ResolveContext<'_>,
+    import: &Import,
+    location: ImportLocation,
+    parent: &ImportLocation,
+    headers: &[(String, String)],
+    span: Span,
+) -> Result<TypedHir, Error> {
+    match import.mode {
         ImportMode::Code => {
-            let (hir, ty) = cache.caching_import(
-                import,
-                || location.fetch_dhall(),
-                |parsed| {
-                    let typed =
-                        resolve_with_env(env, cache, parsed)?.typecheck()?;
-                    let hir = typed.normalize().to_hir();
-                    Ok((hir, typed.ty))
-                },
-            )?;
+            let (hir, ty) = ctx
+                .cache
+                .caching_import(
+                    import,
+                    || location.clone().fetch_dhall(ctx, parent, headers),
+                    |parsed| async move {
+                        let names = NameEnv::new();
+                        let typed =
+                            resolve_with_env(ctx, parsed, &names, headers)
+                                .await?
+                                .typecheck()?;
+                        let hir = typed.normalize().to_hir();
+                        Ok((hir, typed.ty))
+                    },
+                )
+                .await?;
             match &import.hash {
                 Some(Hash::SHA256(hash)) => {
                     let actual_hash = hir.to_expr_alpha().hash()?;
                     if hash[..] != actual_hash[..] {
-                        mkerr(
-                            ErrorBuilder::new("hash mismatch")
+                        let message = TypeMessage::HashMismatch {
+                            expected: Hash::SHA256(hash.clone()),
+                            found: Hash::SHA256(actual_hash.to_vec()),
+                        };
+                        mk_builder_err(
+                            ErrorBuilder::new(&message)
                                 .span_err(span, "hash mismatch")
                                 .note(format!(
                                     "Expected sha256:{}",
@@ -255,8 +526,8 @@ fn resolve_one_import(
                                 .note(format!(
                                     "Found    sha256:{}",
                                     hex::encode(actual_hash)
-                                ))
-                                .format(),
+                                )),
+                            message,
                         )?
                     }
                 }
@@ -265,7 +536,7 @@ fn resolve_one_import(
             Ok((hir, ty))
         }
         ImportMode::RawText => {
-            let text = location.fetch_text()?;
+            let text = location.fetch_text(ctx, parent, headers).await?;
             let hir = Hir::new(
                 HirKind::Expr(ExprKind::TextLit(text.into())),
                 Span::Artificial,
@@ -278,7 +549,7 @@ fn resolve_one_import(
             let ty = hir.typecheck_noenv()?.ty().clone();
             Ok((hir, ty))
         }
-    })
+    }
 }
 
 /// Desugar a `with` expression.
@@ -339,83 +610,306 @@ fn desugar(expr: &Expr) -> Cow<'_, Expr> {
 
 /// Traverse the expression, handling import alternatives and passing
 /// found imports to the provided function. Also resolving names.
-fn traverse_resolve_expr(
-    name_env: &mut NameEnv,
-    expr: &Expr,
-    f: &mut impl FnMut(Import, Span) -> Result<TypedHir, Error>,
-) -> Result<Hir, Error> {
-    let expr = desugar(expr);
-    Ok(match expr.kind() {
-        ExprKind::Var(var) => match name_env.unlabel_var(&var) {
-            Some(v) => Hir::new(HirKind::Var(v), expr.span()),
-            None => mkerr(
-                ErrorBuilder::new(format!("unbound variable `{}`", var))
-                    .span_err(expr.span(), "not found in this scope")
-                    .format(),
-            )?,
-        },
-        ExprKind::Op(OpKind::BinOp(BinOp::ImportAlt, l, r)) => {
-            match traverse_resolve_expr(name_env, l, f) {
-                Ok(l) => l,
-                Err(_) => {
-                    match traverse_resolve_expr(name_env, r, f) {
-                        Ok(r) => r,
-                        // TODO: keep track of the other error too
-                        Err(e) => return Err(e),
+fn traverse_resolve_expr<'a, 'f: 'a>(
+    name_env: &'a mut NameEnv,
+    expr: &'a Expr,
+    f: &'a ResolveImportFn<'f>,
+) -> LocalBoxFuture<'a, Result<Hir, Error>> {
+    async move {
+        let expr = desugar(expr);
+        Ok(match expr.kind() {
+            ExprKind::Var(var) => match name_env.unlabel_var(&var) {
+                Some(v) => Hir::new(HirKind::Var(v), expr.span()),
+                None => {
+                    let message = TypeMessage::UnboundVariable(var.clone());
+                    mk_builder_err(
+                        ErrorBuilder::new(&message)
+                            .span_err(expr.span(), "not found in this scope"),
+                        message,
+                    )?
+                }
+            },
+            ExprKind::Op(OpKind::BinOp(BinOp::ImportAlt, l, r)) => {
+                match traverse_resolve_expr(name_env, l, f).await {
+                    Ok(l) => l,
+                    Err(l_err) => {
+                        match traverse_resolve_expr(name_env, r, f).await {
+                            Ok(r) => r,
+                            Err(r_err) => {
+                                let mut errs =
+                                    l_err.into_alternatives(&l.span());
+                                errs.extend(r_err.into_alternatives(&r.span()));
+                                return Err(ImportError::AlternativesFailed(
+                                    errs,
+                                )
+                                .into());
+                            }
+                        }
                     }
                 }
             }
-        }
-        kind => {
-            let kind = kind.traverse_ref_maybe_binder(|l, e| {
-                if let Some(l) = l {
-                    name_env.insert_mut(l);
-                }
-                let hir = traverse_resolve_expr(name_env, e, f)?;
-                if l.is_some() {
-                    name_env.remove_mut();
-                }
-                Ok::<_, Error>(hir)
-            })?;
-            let kind = match kind {
-                ExprKind::Import(import) => {
-                    // TODO: evaluate import headers
-                    let import = import.traverse_ref(|_| Ok::<_, Error>(()))?;
-                    let imported = f(import, expr.span())?;
-                    HirKind::Import(imported.0, imported.1)
+            ExprKind::Import(import) => {
+                // Import headers can't refer to variables in scope. They are not needed to get
+                // the location of an import.
+                let using = match &import.location {
+                    ImportTarget::Remote(URL {
+                        headers: Some(headers),
+                        ..
+                    }) if import.mode != ImportMode::Location => Some(
+                        traverse_resolve_expr(&mut NameEnv::new(), headers, f)
+                            .await?,
+                    ),
+                    _ => None,
+                };
+                let import = import.traverse_ref(|_| Ok::<_, Error>(()))?;
+                let imported = f(import, using, expr.span())
+                    .await
+                    .map_err(|err| err.at_span(&expr.span()))?;
+                Hir::new(HirKind::Import(imported.0, imported.1), expr.span())
+            }
+            kind => {
+                let mut subexprs = Vec::new();
+                kind.map_ref_maybe_binder(|l, e| subexprs.push((l, e)));
+                let mut hirs = Vec::new();
+                for (l, e) in subexprs {
+                    if let Some(l) = l {
+                        name_env.insert_mut(l);
+                    }
+                    let hir = traverse_resolve_expr(name_env, e, f).await;
+                    if l.is_some() {
+                        name_env.remove_mut();
+                    }
+                    hirs.push(hir?);
                 }
-                kind => HirKind::Expr(kind),
-            };
-            Hir::new(kind, expr.span())
-        }
-    })
+                let mut hirs = hirs.into_iter();
+                let kind = kind.map_ref(|_| hirs.next().unwrap());
+                Hir::new(HirKind::Expr(kind), expr.span())
+            }
+        })
+    }
+    .boxed_local()
 }
 
-fn resolve_with_env(
-    env: &mut ImportEnv,
-    cache: &Cache,
+/// `headers` are the headers used to fetch the parsed expression, if it is remote.
+async fn resolve_with_env(
+    ctx: &ResolveContext<'_>,
     parsed: Parsed,
+    names: &NameEnv,
+    headers: &[(String, String)],
 ) -> Result<Resolved, Error> {
     let Parsed(expr, location) = parsed;
-    let resolved = traverse_resolve_expr(
-        &mut NameEnv::new(),
-        &expr,
-        &mut |import, span| {
-            resolve_one_import(env, cache, &import, &location, span)
-        },
-    )?;
+    if ctx.concurrent {
+        ctx.prefetch(&expr, &location, headers).await;
+    }
+    let resolve_import = |import, using, span| {
+        resolve_one_import(ctx, import, using, &location, headers, span)
+            .boxed_local()
+    };
+    let resolved =
+        traverse_resolve_expr(&mut names.clone(), &expr, &resolve_import)
+            .await?;
     Ok(Resolved(resolved))
 }
 
 pub fn resolve(parsed: Parsed) -> Result<Resolved, Error> {
-    let cache = Cache::new();
-    resolve_with_env(&mut ImportEnv::new(), &cache, parsed)
+    resolve_with_options(parsed, &ResolveOptions::default())
+}
+
+pub fn resolve_with_options(
+    parsed: Parsed,
+    options: &ResolveOptions,
+) -> Result<Resolved, Error> {
+    resolve_with(parsed, &DefaultResolver, options)
+}
+
+pub fn resolve_with(
+    parsed: Parsed,
+    resolver: &dyn ImportResolver,
+    options: &ResolveOptions,
+) -> Result<Resolved, Error> {
+    resolve_in(parsed, &NameEnv::new(), resolver, options)
+}
+
+/// Like `resolve_with`, for an expression that can refer to the variables in `names`.
+pub fn resolve_in(
+    parsed: Parsed,
+    names: &NameEnv,
+    resolver: &dyn ImportResolver,
+    options: &ResolveOptions,
+) -> Result<Resolved, Error> {
+    let ctx = ResolveContext::new(resolver, options);
+    now(resolve_with_env(&ctx, parsed, names, &[]))
+}
+
+/// Resolve imports like `resolve_with`, but download the remote imports found in each file
+/// concurrently, with `ImportResolver::fetch_remote_async`. This includes both sides of import
+/// alternatives.
+#[cfg(feature = "async")]
+pub async fn resolve_async_with(
+    parsed: Parsed,
+    resolver: &dyn ImportResolver,
+    options: &ResolveOptions,
+) -> Result<Resolved, Error> {
+    let mut ctx = ResolveContext::new(resolver, options);
+    ctx.concurrent = true;
+    resolve_with_env(&ctx, parsed, &NameEnv::new(), &[]).await
+}
+
+#[cfg(feature = "async")]
+pub async fn resolve_async(parsed: Parsed) -> Result<Resolved, Error> {
+    resolve_async_with(parsed, &DefaultResolver, &ResolveOptions::default())
+        .await
+}
+
+pub fn freeze_with(
+    parsed: &Parsed,
+    resolver: &dyn ImportResolver,
+    resolve_options: &ResolveOptions,
+    options: &FreezeOptions,
+) -> Result<Parsed, Error> {
+    let expr = freeze_parsed(
+        parsed,
+        resolver,
+        resolve_options,
+        options,
+        &mut |_, _, _| {},
+    )?;
+    Ok(Parsed(expr, parsed.1.clone()))
+}
+
+/// Like `freeze_with`, but write the hashes into the source text of `parsed`, so that the rest
+/// of the text, including comments, is kept as is. Expressions that were not parsed from text are
+/// printed instead.
+pub fn freeze_source_with(
+    parsed: &Parsed,
+    resolver: &dyn ImportResolver,
+    resolve_options: &ResolveOptions,
+    options: &FreezeOptions,
+) -> Result<String, Error> {
+    // The byte range to replace in the source, and its replacement.
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let expr = freeze_parsed(
+        parsed,
+        resolver,
+        resolve_options,
+        options,
+        &mut |import, span, hash| {
+            let span = match span {
+                Span::Parsed(span) => span,
+                _ => return,
+            };
+            // The span of an import of code ends with its hash, if it has one.
+            let (start, replacement) = match &import.hash {
+                Some(old) => {
+                    (span.end() - old.to_string().len(), hash.to_string())
+                }
+                None => (span.end(), format!(" {}", hash)),
+            };
+            edits.push((start, span.end(), replacement));
+        },
+    )?;
+    let source = match parsed.0.span() {
+        Span::Parsed(span) => span.to_input(),
+        _ => return Ok(expr.to_string()),
+    };
+    edits.sort_by_key(|(start, _, _)| *start);
+    let mut text = String::new();
+    let mut copied = 0;
+    for (start, end, replacement) in edits {
+        text.push_str(&source[copied..start]);
+        text.push_str(&replacement);
+        copied = end;
+    }
+    text.push_str(&source[copied..]);
+    Ok(text)
+}
+
+/// Freeze the imports of `parsed`, calling `on_freeze` with each frozen import, its span and its
+/// new hash.
+fn freeze_parsed(
+    parsed: &Parsed,
+    resolver: &dyn ImportResolver,
+    resolve_options: &ResolveOptions,
+    options: &FreezeOptions,
+    on_freeze: &mut dyn FnMut(&syntax::Import<Expr>, &Span, &Hash),
+) -> Result<Expr, Error> {
+    let ctx = ResolveContext::new(resolver, resolve_options);
+    let Parsed(expr, location) = parsed;
+    let resolve_import = |import, using, span| {
+        resolve_one_import(&ctx, import, using, location, &[], span)
+            .boxed_local()
+    };
+    let mut freeze_import = |import: &syntax::Import<Expr>, span: Span| {
+        // Resolve the import like `resolve_with` does, and hash the result.
+        let expr = Expr::new(ExprKind::Import(import.clone()), span);
+        let hir = now(traverse_resolve_expr(
+            &mut NameEnv::new(),
+            &expr,
+            &resolve_import,
+        ))?;
+        match hir.kind() {
+            HirKind::Import(hir, _) => {
+                Ok(Hash::SHA256(hir.to_expr_alpha().hash()?.into_vec()))
+            }
+            _ => unreachable!("an import resolves to an import"),
+        }
+    };
+    freeze_expr(expr, options, &mut freeze_import, on_freeze)
+}
+
+/// Add a hash to the imports selected by `options`, computed by `freeze_import`.
+fn freeze_expr(
+    expr: &Expr,
+    options: &FreezeOptions,
+    freeze_import: &mut impl FnMut(
+        &syntax::Import<Expr>,
+        Span,
+    ) -> Result<Hash, Error>,
+    on_freeze: &mut dyn FnMut(&syntax::Import<Expr>, &Span, &Hash),
+) -> Result<Expr, Error> {
+    let kind = match expr.kind() {
+        ExprKind::Import(import) => {
+            let selected = match import.location {
+                ImportTarget::Remote(..) => true,
+                ImportTarget::Local(..) | ImportTarget::Env(..) => options.all,
+                ImportTarget::Missing => false,
+            };
+            if !selected
+                || import.mode != ImportMode::Code
+                || (import.hash.is_some() && !options.refreeze)
+            {
+                return Ok(expr.clone());
+            }
+            let mut frozen = import.clone();
+            // Don't check the old hash when refreezing.
+            frozen.hash = None;
+            let hash = freeze_import(&frozen, expr.span())?;
+            on_freeze(import, &expr.span(), &hash);
+            frozen.hash = Some(hash);
+            ExprKind::Import(frozen)
+        }
+        ExprKind::Op(OpKind::BinOp(BinOp::ImportAlt, l, r))
+            if options.keep_fallbacks =>
+        {
+            let l = freeze_expr(l, options, freeze_import, on_freeze)?;
+            ExprKind::Op(OpKind::BinOp(BinOp::ImportAlt, l, r.clone()))
+        }
+        kind => kind.traverse_ref(|e| {
+            freeze_expr(e, options, freeze_import, on_freeze)
+        })?,
+    };
+    Ok(Expr::new(kind, expr.span()))
 }
 
 pub fn skip_resolve_expr(expr: &Expr) -> Result<Hir, Error> {
-    traverse_resolve_expr(&mut NameEnv::new(), expr, &mut |import, _span| {
-        Err(ImportError::UnexpectedImport(import).into())
-    })
+    let unexpected_import = |import, _, _| {
+        ready(Err(ImportError::UnexpectedImport(import).into())).boxed_local()
+    };
+    now(traverse_resolve_expr(
+        &mut NameEnv::new(),
+        expr,
+        &unexpected_import,
+    ))
 }
 
 pub fn skip_resolve(parsed: Parsed) -> Result<Resolved, Error> {
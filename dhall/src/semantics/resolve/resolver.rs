@@ -0,0 +1,1060 @@
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use std::env;
+use std::path::Path;
+use url::Url;
+
+use crate::error::{Error, ImportError};
+use crate::semantics::Headers;
+use crate::HttpOptions;
+
+/// Provides the contents of imports during import resolution.
+///
+/// Every method has a default implementation that reads from the filesystem, the network and the
+/// environment of the current process respectively. Override some of them to resolve imports
+/// from somewhere else, e.g. a database or an in-memory filesystem.
+pub trait ImportResolver {
+    /// Reads the contents of a local file.
+    fn read_local(&self, path: &Path) -> Result<String, Error> {
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    /// Downloads the contents of a remote file, sending the provided HTTP headers.
+    fn fetch_remote(
+        &self,
+        url: &Url,
+        headers: &[(String, String)],
+        options: &HttpOptions,
+    ) -> Result<RemoteResponse, Error> {
+        download_http_text(url.clone(), headers, options)
+    }
+
+    /// Like `fetch_remote`, for asynchronous resolution, where the remote imports found in a
+    /// file are downloaded concurrently. Calls `fetch_remote` by default.
+    fn fetch_remote_async<'a>(
+        &'a self,
+        url: &'a Url,
+        headers: &'a [(String, String)],
+        options: &'a HttpOptions,
+    ) -> LocalBoxFuture<'a, Result<RemoteResponse, Error>> {
+        async move { self.fetch_remote(url, headers, options) }.boxed_local()
+    }
+
+    /// Reads the value of an environment variable.
+    fn read_env(&self, name: &str) -> Result<String, Error> {
+        match env::var(name) {
+            Ok(val) => Ok(val),
+            Err(_) => Err(ImportError::MissingEnvVar.into()),
+        }
+    }
+}
+
+/// The response to a request for a remote import.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteResponse {
+    /// The body of the response
+    pub text: String,
+    /// The response headers. Only `Access-Control-Allow-Origin` is used, to check that an import
+    /// from another origin is allowed.
+    pub headers: Headers,
+}
+
+impl RemoteResponse {
+    /// Look up the value of a response header. Header names are case-insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Resolves imports from the filesystem, the network and the environment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultResolver;
+
+impl ImportResolver for DefaultResolver {
+    /// Downloads without blocking, with the `async` feature.
+    #[cfg(feature = "async")]
+    fn fetch_remote_async<'a>(
+        &'a self,
+        url: &'a Url,
+        headers: &'a [(String, String)],
+        options: &'a HttpOptions,
+    ) -> LocalBoxFuture<'a, Result<RemoteResponse, Error>> {
+        download_http_text_async(url.clone(), headers.to_vec(), options.clone())
+            .map(|response| Ok(response?))
+            .boxed_local()
+    }
+}
+
+impl std::fmt::Debug for dyn ImportResolver + '_ {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("ImportResolver")
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for ImportError {
+    fn from(err: reqwest::Error) -> ImportError {
+        if err.is_timeout() {
+            ImportError::Timeout
+        } else {
+            ImportError::Connection(std::sync::Arc::new(err))
+        }
+    }
+}
+
+/// Errors while reading the body of a response.
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+fn read_error(err: std::io::Error) -> ImportError {
+    match err.kind() {
+        std::io::ErrorKind::TimedOut => ImportError::Timeout,
+        _ => ImportError::Connection(std::sync::Arc::new(err)),
+    }
+}
+
+/// The timeout for requests made with `options`.
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "reqwest"),
+    all(not(target_arch = "wasm32"), feature = "async")
+))]
+fn http_timeout(options: &HttpOptions) -> std::time::Duration {
+    options
+        .timeout
+        .unwrap_or_else(|| std::time::Duration::from_secs(30))
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+fn make_http_client(
+    options: &HttpOptions,
+) -> Result<reqwest::blocking::Client, reqwest::Error> {
+    let mut builder =
+        reqwest::blocking::Client::builder().timeout(http_timeout(options));
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.clone())?);
+    }
+    for cert in &options.root_certificates {
+        let cert = reqwest::Certificate::from_pem(cert)?;
+        builder = builder.add_root_certificate(cert);
+    }
+    if let Some(user_agent) = &options.user_agent {
+        builder = builder.user_agent(user_agent.as_str());
+    }
+    builder.build()
+}
+
+/// The client for the given options. Building a client is expensive, so one is kept for each
+/// set of options and reused.
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+fn http_client(
+    options: &HttpOptions,
+) -> Result<reqwest::blocking::Client, reqwest::Error> {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    lazy_static::lazy_static! {
+        static ref CLIENTS: Mutex<HashMap<HttpOptions, reqwest::blocking::Client>> =
+            Mutex::new(HashMap::new());
+    }
+    let clients = || CLIENTS.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(client) = clients().get(options) {
+        return Ok(client.clone());
+    }
+    // Build the client without holding the lock, so that requests with other options don't
+    // wait for it.
+    let client = make_http_client(options)?;
+    Ok(clients().entry(options.clone()).or_insert(client).clone())
+}
+
+#[cfg(any(
+    all(not(target_arch = "wasm32"), feature = "reqwest"),
+    feature = "async"
+))]
+fn response_headers(headers: &reqwest::header::HeaderMap) -> Headers {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+pub(crate) fn download_http_text(
+    url: Url,
+    headers: &[(String, String)],
+    options: &HttpOptions,
+) -> Result<RemoteResponse, Error> {
+    use std::io::Read;
+
+    let client = http_client(options).map_err(ImportError::from)?;
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let mut response = request.send().map_err(ImportError::from)?;
+    if !response.status().is_success() {
+        return Err(ImportError::HttpStatus(response.status().as_u16()).into());
+    }
+    let headers = response_headers(response.headers());
+    let mut body = Vec::new();
+    match options.max_body_size {
+        Some(max) => {
+            if matches!(response.content_length(), Some(len) if len > max) {
+                return Err(ImportError::BodyTooLarge.into());
+            }
+            // Read one more byte than allowed to detect a body that is too large.
+            response
+                .take(max + 1)
+                .read_to_end(&mut body)
+                .map_err(read_error)?;
+            if body.len() as u64 > max {
+                return Err(ImportError::BodyTooLarge.into());
+            }
+        }
+        None => {
+            response.read_to_end(&mut body).map_err(read_error)?;
+        }
+    }
+    let text = String::from_utf8(body).map_err(|_| ImportError::InvalidUtf8)?;
+    Ok(RemoteResponse { text, headers })
+}
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "reqwest")))]
+pub(crate) fn download_http_text(
+    _url: Url,
+    _headers: &[(String, String)],
+    _options: &HttpOptions,
+) -> Result<RemoteResponse, Error> {
+    // Remote imports are disabled in this build of dhall-rust
+    Err(ImportError::RemoteImportsDisabled.into())
+}
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn download_http_text(
+    _url: Url,
+    _headers: &[(String, String)],
+    _options: &HttpOptions,
+) -> Result<RemoteResponse, Error> {
+    // Remote imports are not supported on wasm yet
+    Err(ImportError::RemoteImportsDisabled.into())
+}
+
+/// The options other than `max_body_size` are not supported on wasm, where the browser makes the
+/// requests.
+#[cfg(feature = "async")]
+fn make_async_http_client(
+    options: &HttpOptions,
+) -> Result<reqwest::Client, reqwest::Error> {
+    #[allow(unused_mut)]
+    let mut builder = reqwest::Client::builder();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        builder = builder.timeout(http_timeout(options));
+        if let Some(proxy) = &options.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy.clone())?);
+        }
+        for cert in &options.root_certificates {
+            let cert = reqwest::Certificate::from_pem(cert)?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some(user_agent) = &options.user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = options;
+    builder.build()
+}
+
+/// Like `download_http_text`, but without blocking. The arguments are owned so that several
+/// downloads can run concurrently.
+#[cfg(feature = "async")]
+pub(crate) async fn download_http_text_async(
+    url: Url,
+    headers: Headers,
+    options: HttpOptions,
+) -> Result<RemoteResponse, ImportError> {
+    let client = make_async_http_client(&options)?;
+    let mut request = client.get(url);
+    for (name, value) in &headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(ImportError::HttpStatus(response.status().as_u16()));
+    }
+    let headers = response_headers(response.headers());
+    let too_large =
+        |len: u64| matches!(options.max_body_size, Some(max) if len > max);
+    if matches!(response.content_length(), Some(len) if too_large(len)) {
+        return Err(ImportError::BodyTooLarge);
+    }
+    let body = response.bytes().await?;
+    if too_large(body.len() as u64) {
+        return Err(ImportError::BodyTooLarge);
+    }
+    let text = String::from_utf8(body.to_vec())
+        .map_err(|_| ImportError::InvalidUtf8)?;
+    Ok(RemoteResponse { text, headers })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::error::ErrorKind;
+    use crate::semantics::parse::parse_str;
+    use crate::semantics::ImportLocation;
+    use crate::{CacheDir, FreezeOptions, ResolveOptions};
+
+    #[derive(Default)]
+    struct MapResolver {
+        files: HashMap<PathBuf, String>,
+        vars: HashMap<String, String>,
+        /// Files from `example.com` are only served with an `Authorization: token secret` header.
+        remote: HashMap<String, RemoteResponse>,
+    }
+
+    impl ImportResolver for MapResolver {
+        fn read_local(&self, path: &Path) -> Result<String, Error> {
+            match self.files.get(path) {
+                Some(text) => Ok(text.clone()),
+                None => Err(ImportError::Missing.into()),
+            }
+        }
+        fn read_env(&self, name: &str) -> Result<String, Error> {
+            match self.vars.get(name) {
+                Some(text) => Ok(text.clone()),
+                None => Err(ImportError::MissingEnvVar.into()),
+            }
+        }
+        fn fetch_remote(
+            &self,
+            url: &Url,
+            headers: &[(String, String)],
+            _options: &HttpOptions,
+        ) -> Result<RemoteResponse, Error> {
+            let authorized = url.host_str() != Some("example.com")
+                || headers.iter().any(|(name, value)| {
+                    name == "Authorization" && value == "token secret"
+                });
+            match self.remote.get(url.as_str()) {
+                Some(response) if authorized => Ok(response.clone()),
+                _ => Err(ImportError::Missing.into()),
+            }
+        }
+    }
+
+    impl MapResolver {
+        fn serve(&mut self, url: &str, text: &str, allow_origin: Option<&str>) {
+            let headers = allow_origin
+                .map(|origin| {
+                    (
+                        "Access-Control-Allow-Origin".to_owned(),
+                        origin.to_owned(),
+                    )
+                })
+                .into_iter()
+                .collect();
+            let text = text.to_owned();
+            self.remote
+                .insert(url.to_owned(), RemoteResponse { text, headers });
+        }
+    }
+
+    fn remote_resolver() -> MapResolver {
+        let mut resolver = MapResolver::default();
+        resolver.serve("https://example.com/main.dhall", "./n.dhall", None);
+        resolver.serve("https://example.com/n.dhall", "42", None);
+        resolver.vars.insert("TOKEN".into(), "secret".into());
+        resolver
+    }
+
+    #[test]
+    fn resolve_with_should_use_provided_resolver() -> Result<(), Error> {
+        let mut resolver = MapResolver::default();
+        resolver
+            .files
+            .insert("/config/main.dhall".into(), "./other.dhall + 1".into());
+        resolver
+            .files
+            .insert("/config/other.dhall".into(), "env:NUMBER".into());
+        resolver.vars.insert("NUMBER".into(), "41".into());
+
+        let resolved = parse_str("/config/main.dhall")?
+            .resolve_with(&resolver, &ResolveOptions::default())?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "42");
+
+        assert!(parse_str("/config/missing.dhall")?
+            .resolve_with(&resolver, &ResolveOptions::default())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn errors_should_record_the_import_chain() -> Result<(), Error> {
+        let mut resolver = MapResolver::default();
+        resolver
+            .files
+            .insert("/config/main.dhall".into(), "./a.dhall".into());
+        resolver
+            .files
+            .insert("/config/a.dhall".into(), "{ b = ./b.dhall }".into());
+        resolver
+            .files
+            .insert("/config/b.dhall".into(), "../missing.dhall".into());
+
+        let err = parse_str("/config/main.dhall")?
+            .resolve_with(&resolver, &ResolveOptions::default())
+            .unwrap_err();
+        let stack: Vec<_> = [
+            "/config/main.dhall",
+            "/config/a.dhall",
+            "/config/b.dhall",
+            "/missing.dhall",
+        ]
+        .iter()
+        .map(|path| ImportLocation::Local(path.into()))
+        .collect();
+        assert_eq!(err.import_stack(), &stack[..]);
+        assert!(err.to_string().ends_with(
+            "while importing /config/main.dhall → /config/a.dhall → /config/b.dhall → /missing.dhall"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn import_alternatives_should_report_every_error() -> Result<(), Error> {
+        let mut resolver = MapResolver::default();
+        resolver
+            .files
+            .insert("/config/bad.dhall".into(), "1 + True".into());
+        let resolve = |s| {
+            parse_str(s)?.resolve_with(&resolver, &ResolveOptions::default())
+        };
+
+        assert!(resolve("/config/missing.dhall ? 1").is_ok());
+        let err = resolve(
+            "/config/missing.dhall\n? env:MISSING\n? /config/bad.dhall",
+        )
+        .unwrap_err();
+        let errs = match err.kind() {
+            ErrorKind::Resolve(ImportError::AlternativesFailed(errs)) => errs,
+            _ => panic!("unexpected error: {}", err),
+        };
+        let lines: Vec<_> = errs
+            .iter()
+            .map(|err| err.range().unwrap().start.line)
+            .collect();
+        assert_eq!(lines, vec![1, 2, 1]);
+        assert!(matches!(
+            errs[1].kind(),
+            ErrorKind::Resolve(ImportError::MissingEnvVar)
+        ));
+        assert!(matches!(errs[2].kind(), ErrorKind::Typecheck(_)));
+        assert!(err.to_string().contains("--> <current file>:2:3"));
+        Ok(())
+    }
+
+    #[test]
+    fn errors_should_point_to_the_imported_file() -> Result<(), Error> {
+        let mut resolver = MapResolver::default();
+        resolver
+            .files
+            .insert("/config/main.dhall".into(), "./other.dhall".into());
+        resolver.files.insert(
+            "/config/other.dhall".into(),
+            "let x = 1\nin  x + y".into(),
+        );
+        resolver
+            .files
+            .insert("/config/broken.dhall".into(), "{ x =\n".into());
+        let resolve = |s| {
+            parse_str(s)?.resolve_with(&resolver, &ResolveOptions::default())
+        };
+
+        let err = resolve("/config/main.dhall").unwrap_err();
+        let range = err.range().unwrap();
+        assert_eq!(range.file.as_deref(), Some("/config/other.dhall"));
+        assert_eq!((range.start.line, range.start.column), (2, 9));
+        assert_eq!((range.end.line, range.end.column), (2, 10));
+        assert!(err.to_string().contains("--> /config/other.dhall"));
+
+        let err = resolve("/config/broken.dhall").unwrap_err();
+        let range = err.range().unwrap();
+        assert_eq!(range.file.as_deref(), Some("/config/broken.dhall"));
+        assert_eq!(range.start.line, 2);
+        assert!(err.to_string().contains("--> /config/broken.dhall:2:1"));
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_should_send_import_headers() -> Result<(), Error> {
+        let resolver = remote_resolver();
+        let resolve = |s| {
+            parse_str(s)?.resolve_with(&resolver, &ResolveOptions::default())
+        };
+
+        assert!(resolve("https://example.com/main.dhall").is_err());
+        let resolved = resolve(
+            r#"https://example.com/main.dhall
+                using (toMap { Authorization = "token ${env:TOKEN as Text}" })"#,
+        )?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "42");
+
+        assert!(resolve(
+            r#"https://example.com/main.dhall using [{ mapKey = 1, mapValue = "" }]"#
+        )
+        .is_err());
+
+        // The headers are not needed for the location
+        let resolved = resolve(
+            r#"https://example.com/main.dhall using (env:MISSING) as Location"#,
+        )?;
+        assert_eq!(
+            resolved.typecheck()?.normalize().to_string(),
+            r#"< Environment: Text | Local: Text | Missing | Remote: Text >.Remote "https://example.com/main.dhall""#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_should_send_origin_headers() -> Result<(), Error> {
+        let mut resolver = remote_resolver();
+        resolver.vars.insert("HOME".into(), "/home/user".into());
+        resolver.files.insert(
+            "/home/user/.config/dhall/headers.dhall".into(),
+            r#"toMap {
+                `example.com:443` = toMap { Authorization = "token ${env:TOKEN as Text}" }
+            }"#
+            .into(),
+        );
+
+        let resolved = parse_str("https://example.com/main.dhall")?
+            .resolve_with(&resolver, &ResolveOptions::default())?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "42");
+
+        // Headers from the `using` clause take precedence
+        assert!(parse_str(
+            r#"https://example.com/n.dhall using (toMap { Authorization = "" })"#
+        )?
+        .resolve_with(&resolver, &ResolveOptions::default())
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_should_check_cors_for_remote_imports() -> Result<(), Error> {
+        let mut resolver = remote_resolver();
+        resolver.serve(
+            "https://example.com/cors.dhall",
+            "https://other.example.com/any.dhall + https://other.example.com/mine.dhall",
+            None,
+        );
+        resolver.serve(
+            "https://example.com/no-cors.dhall",
+            "https://other.example.com/none.dhall",
+            None,
+        );
+        resolver.serve("https://other.example.com/any.dhall", "1", Some("*"));
+        resolver.serve(
+            "https://other.example.com/mine.dhall",
+            "2",
+            Some("https://example.com"),
+        );
+        resolver.serve("https://other.example.com/none.dhall", "3", None);
+        let resolve = |url: &str| {
+            let using = r#"using (toMap { Authorization = "token secret" })"#;
+            parse_str(&format!("{} {}", url, using))?
+                .resolve_with(&resolver, &ResolveOptions::default())
+        };
+
+        let resolved = resolve("https://example.com/cors.dhall")?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "3");
+        assert!(resolve("https://example.com/no-cors.dhall").is_err());
+        // No check for imports from a local file
+        assert!(resolve("https://other.example.com/none.dhall").is_ok());
+        Ok(())
+    }
+
+    /// Serve HTTP responses on a local port, and return the base url to reach them. `handler`
+    /// gets the text of the request and returns the raw response. Each connection is handled on
+    /// its own thread.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    fn serve_http(
+        handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    ) -> String {
+        let port = serve(handler, Some);
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    /// Like `serve_http`, over HTTPS with the self-signed certificate in `tests/https/cert.pem`.
+    /// `tests/https/identity.p12` holds the certificate and its key, with the password `dhall`.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    fn serve_https(
+        handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    ) -> String {
+        let identity = include_bytes!("../../../tests/https/identity.p12");
+        let identity =
+            native_tls::Identity::from_pkcs12(identity, "dhall").unwrap();
+        let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+        let port = serve(handler, move |stream| acceptor.accept(stream).ok());
+        format!("https://127.0.0.1:{}", port)
+    }
+
+    /// Answer each request received on a local port with `handler`, over the connection made
+    /// by `accept`. Returns the port.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    fn serve<S: std::io::Read + std::io::Write>(
+        handler: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+        accept: impl Fn(std::net::TcpStream) -> Option<S> + Send + Sync + 'static,
+    ) -> u16 {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::sync::Arc;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handler = Arc::new(handler);
+        let accept = Arc::new(accept);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let handler = handler.clone();
+                let accept = accept.clone();
+                std::thread::spawn(move || {
+                    let mut stream = match accept(stream) {
+                        Some(stream) => stream,
+                        None => return,
+                    };
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let n = stream.read(&mut buf).unwrap();
+                        if n == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buf[..n]);
+                    }
+                    let response = handler(&String::from_utf8_lossy(&request));
+                    // The client may have given up already
+                    let _ = stream.write_all(&response);
+                });
+            }
+        });
+        port
+    }
+
+    /// Fetches remote imports over the network, but doesn't see the local files and environment
+    /// variables, e.g. a `headers.dhall` configuration, of the machine running the tests.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    struct RemoteOnlyResolver;
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    impl ImportResolver for RemoteOnlyResolver {
+        fn read_local(&self, _: &Path) -> Result<String, Error> {
+            Err(ImportError::Missing.into())
+        }
+        fn read_env(&self, _: &str) -> Result<String, Error> {
+            Err(ImportError::MissingEnvVar.into())
+        }
+        #[cfg(feature = "async")]
+        fn fetch_remote_async<'a>(
+            &'a self,
+            url: &'a Url,
+            headers: &'a [(String, String)],
+            options: &'a HttpOptions,
+        ) -> LocalBoxFuture<'a, Result<RemoteResponse, Error>> {
+            DefaultResolver.fetch_remote_async(url, headers, options)
+        }
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    #[test]
+    fn download_http_text_should_return_import_errors() -> Result<(), Error> {
+        use std::time::Duration;
+
+        let base = serve_http(|request| {
+            let path = request.split(' ').nth(1).unwrap();
+            match path {
+                "/ok" => http_response("200 OK", b"42"),
+                "/binary" => http_response("200 OK", b"\xff\xfe"),
+                "/slow" => {
+                    std::thread::sleep(Duration::from_secs(3600));
+                    Vec::new()
+                }
+                _ => http_response("404 Not Found", b""),
+            }
+        });
+        let options = ResolveOptions {
+            cache_dir: CacheDir::Disabled,
+            http: HttpOptions {
+                timeout: Some(Duration::from_millis(100)),
+                ..HttpOptions::default()
+            },
+            ..ResolveOptions::default()
+        };
+        let resolve =
+            |s: &str| parse_str(s)?.resolve_with(&RemoteOnlyResolver, &options);
+
+        let err = resolve(&format!("{}/missing", base)).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::HttpStatus(404))
+        ));
+        let err = resolve(&format!("{}/binary", base)).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::InvalidUtf8)
+        ));
+        let err = resolve("http://127.0.0.1:1/refused").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::Connection(_))
+        ));
+        // The error from the HTTP client is kept
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source.is::<reqwest::Error>());
+        let err = resolve(&format!("{}/slow", base)).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::Timeout)
+        ));
+
+        let resolved = resolve(&format!(
+            "http://127.0.0.1:1/refused ? {base}/missing ? {base}/slow ? {base}/binary ? {base}/ok",
+            base = base
+        ))?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "42");
+        Ok(())
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    #[test]
+    fn http_timeout_should_default_to_30_seconds() {
+        use std::time::Duration;
+
+        assert_eq!(
+            http_timeout(&HttpOptions::default()),
+            Duration::from_secs(30)
+        );
+        let options = HttpOptions {
+            timeout: Some(Duration::from_secs(5)),
+            ..HttpOptions::default()
+        };
+        assert_eq!(http_timeout(&options), Duration::from_secs(5));
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    fn http_response(status: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    #[test]
+    fn download_http_text_should_trust_provided_root_certificates(
+    ) -> Result<(), Error> {
+        let base = serve_https(|_| http_response("200 OK", b"1"));
+        let url = Url::parse(&format!("{}/one", base)).unwrap();
+
+        let err = DefaultResolver
+            .fetch_remote(&url, &[], &HttpOptions::default())
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::Connection(_))
+        ));
+
+        let cert = include_bytes!("../../../tests/https/cert.pem");
+        let options = HttpOptions {
+            root_certificates: vec![cert.to_vec()],
+            ..HttpOptions::default()
+        };
+        assert_eq!(
+            DefaultResolver.fetch_remote(&url, &[], &options)?.text,
+            "1"
+        );
+        Ok(())
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+    #[test]
+    fn download_http_text_should_follow_http_options() -> Result<(), Error> {
+        use std::time::Duration;
+
+        let base = serve_http(|request| {
+            let path = request.split(' ').nth(1).unwrap();
+            match path {
+                "/slow" => {
+                    std::thread::sleep(Duration::from_millis(500));
+                    http_response("200 OK", b"1")
+                }
+                "/big" => http_response("200 OK", b"123456789"),
+                "/agent" if request.contains("user-agent: dhall-test") => {
+                    http_response("200 OK", b"1")
+                }
+                // Requests through a proxy contain the full url
+                "http://example.invalid/proxied" => {
+                    http_response("200 OK", b"1")
+                }
+                _ => http_response("404 Not Found", b""),
+            }
+        });
+        let fetch = |url: &str, options: &HttpOptions| {
+            let url = Url::parse(url).unwrap();
+            DefaultResolver.fetch_remote(&url, &[], options)
+        };
+
+        let options = HttpOptions {
+            timeout: Some(Duration::from_millis(100)),
+            ..HttpOptions::default()
+        };
+        let err = fetch(&format!("{}/slow", base), &options).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::Timeout)
+        ));
+
+        let options = HttpOptions {
+            max_body_size: Some(8),
+            ..HttpOptions::default()
+        };
+        let err = fetch(&format!("{}/big", base), &options).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::BodyTooLarge)
+        ));
+        let options = HttpOptions {
+            max_body_size: Some(9),
+            ..HttpOptions::default()
+        };
+        assert_eq!(
+            fetch(&format!("{}/big", base), &options)?.text,
+            "123456789"
+        );
+
+        let options = HttpOptions {
+            user_agent: Some("dhall-test".to_owned()),
+            ..HttpOptions::default()
+        };
+        assert!(
+            fetch(&format!("{}/agent", base), &HttpOptions::default()).is_err()
+        );
+        assert_eq!(fetch(&format!("{}/agent", base), &options)?.text, "1");
+
+        let options = HttpOptions {
+            proxy: Some(Url::parse(&base).unwrap()),
+            ..HttpOptions::default()
+        };
+        assert_eq!(
+            fetch("http://example.invalid/proxied", &options)?.text,
+            "1"
+        );
+
+        let options = HttpOptions {
+            root_certificates: vec![b"not a certificate".to_vec()],
+            ..HttpOptions::default()
+        };
+        assert!(fetch(&format!("{}/big", base), &options).is_err());
+        Ok(())
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "async"))]
+    #[tokio::test]
+    async fn resolve_async_should_fetch_imports_concurrently(
+    ) -> Result<(), Error> {
+        use std::sync::{Arc, Condvar, Mutex};
+        use std::time::Duration;
+
+        // `/a` and `/b` only respond once both have been requested.
+        let pending = Arc::new((Mutex::new(0), Condvar::new()));
+        let base = serve_http(move |request| {
+            let path = request.split(' ').nth(1).unwrap();
+            let both_requested = || {
+                let (count, cvar) = &*pending;
+                let mut count = count.lock().unwrap();
+                *count += 1;
+                cvar.notify_all();
+                let timeout = Duration::from_secs(5);
+                let (count, _) = cvar
+                    .wait_timeout_while(count, timeout, |count| *count < 2)
+                    .unwrap();
+                *count >= 2
+            };
+            match path {
+                "/a" if both_requested() => http_response("200 OK", b"./c"),
+                "/b" if both_requested() => http_response("200 OK", b"2"),
+                "/c" => http_response("200 OK", b"1"),
+                "/headers" => http_response(
+                    "200 OK",
+                    br#"toMap { Authorization = "token secret" }"#,
+                ),
+                "/secret"
+                    if request.contains("authorization: token secret") =>
+                {
+                    http_response("200 OK", b"3")
+                }
+                _ => http_response("404 Not Found", b""),
+            }
+        });
+
+        let resolved = parse_str(&format!(
+            "{{ a = {base}/a, b = {base}/b, c = {base}/missing ? {base}/c }}",
+            base = base
+        ))?
+        .resolve_async()
+        .await?;
+        assert_eq!(
+            resolved.typecheck()?.normalize().to_string(),
+            "{ a = 1, b = 2, c = 1 }"
+        );
+
+        // The headers are themselves a remote import
+        let resolved = parse_str(&format!(
+            "{base}/secret using {base}/headers",
+            base = base
+        ))?
+        .resolve_async()
+        .await?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "3");
+
+        // Remote imports are fetched by the provided resolver
+        let mut resolver = MapResolver::default();
+        resolver.vars.insert("TOKEN".into(), "secret".into());
+        resolver.serve("https://example.com/secret", "3", None);
+        resolver.serve("https://example.org/four", "4", None);
+        let options = ResolveOptions {
+            cache_dir: CacheDir::Disabled,
+            ..ResolveOptions::default()
+        };
+        let resolved = parse_str(
+            r#"https://example.com/secret using (toMap { Authorization = "token ${env:TOKEN as Text}" }) + https://example.org/four"#,
+        )?
+        .resolve_async_with(&resolver, &options)
+        .await?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "7");
+
+        let err = parse_str(&format!("{}/missing", base))?
+            .resolve_async()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Resolve(ImportError::HttpStatus(404))
+        ));
+
+        // The cache entry is invalid, so the import is only downloaded during resolution
+        let dir = std::env::temp_dir()
+            .join(format!("dhall-prefetch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let hash =
+            "d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15";
+        std::fs::write(dir.join(format!("1220{}", hash)), b"not cbor")?;
+        let options = ResolveOptions {
+            cache_dir: CacheDir::Path(dir.clone()),
+            ..ResolveOptions::default()
+        };
+        let resolved = parse_str(&format!("{}/c sha256:{} ? 0", base, hash))?
+            .resolve_async_with(&RemoteOnlyResolver, &options)
+            .await?;
+        assert_eq!(resolved.typecheck()?.normalize().to_string(), "1");
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn freeze_should_add_hashes_to_selected_imports() -> Result<(), Error> {
+        let mut resolver = MapResolver::default();
+        resolver
+            .files
+            .insert("/config/one.dhall".into(), "1".into());
+        resolver.serve("https://example.org/n.dhall", "41 + 1", None);
+        resolver.serve("https://example.org/t.txt", "hi", None);
+        let resolve_options = ResolveOptions {
+            cache_dir: CacheDir::Disabled,
+            ..ResolveOptions::default()
+        };
+        let n = "https://example.org/n.dhall";
+        let one = "/config/\"one.dhall\"";
+        let zero = format!("{} sha256:{}", n, "0".repeat(64));
+        // The imports in `{ n = a + b + c + (d ? e), t = … }`, as printed
+        let expr = |a: &str, b: &str, c: &str, d: &str, e: &str| {
+            format!(
+                "{{ n = {} + {} + {} + ({} ? {}), t = https://example.org/t.txt as Text ? missing }}",
+                a, b, c, d, e
+            )
+        };
+        let freeze = |options: FreezeOptions| {
+            let parsed = parse_str(&expr(n, one, &zero, one, n))?;
+            parsed.freeze_with(&resolver, &resolve_options, &options)
+        };
+        let frozen_n = format!(
+            "{} sha256:c39cde2e11e3d5a57cccbc06f6599256ece67b3d16d1bc1df1d0cfa79d9be605",
+            n
+        );
+        let frozen_one = format!(
+            "{} sha256:d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15",
+            one
+        );
+
+        // Only remote imports without a hash are frozen by default.
+        assert_eq!(
+            freeze(FreezeOptions::default())?.to_string(),
+            expr(&frozen_n, one, &zero, one, &frozen_n)
+        );
+        let frozen = freeze(FreezeOptions {
+            all: true,
+            refreeze: true,
+            keep_fallbacks: false,
+        })?;
+        assert_eq!(
+            frozen.to_string(),
+            expr(&frozen_n, &frozen_one, &frozen_n, &frozen_one, &frozen_n)
+        );
+        let resolved = frozen.resolve_with(&resolver, &resolve_options)?;
+        assert_eq!(
+            resolved.typecheck()?.normalize().to_string(),
+            "{ n = 86, t = \"hi\" }"
+        );
+        assert_eq!(
+            freeze(FreezeOptions {
+                all: true,
+                refreeze: false,
+                keep_fallbacks: true,
+            })?
+            .to_string(),
+            expr(&frozen_n, &frozen_one, &zero, &frozen_one, n)
+        );
+
+        // Freezing the source keeps its layout and comments.
+        let source = format!(
+            "-- Numbers\n{{ a = {n} -- remote\n, b = {z} }}",
+            n = n,
+            z = zero
+        );
+        let frozen = parse_str(&source)?.freeze_source_with(
+            &resolver,
+            &resolve_options,
+            &FreezeOptions {
+                all: false,
+                refreeze: true,
+                keep_fallbacks: false,
+            },
+        )?;
+        assert_eq!(
+            frozen,
+            format!(
+                "-- Numbers\n{{ a = {n} -- remote\n, b = {n} }}",
+                n = frozen_n
+            )
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use crate::builtins::Builtin;
-use crate::error::{ErrorBuilder, TypeError};
-use crate::semantics::{mkerr, Hir, Nir, NirKind, NzEnv, TyEnv, VarEnv};
+use crate::error::{ErrorBuilder, TypeError, TypeMessage};
+use crate::semantics::{
+    mk_builder_err, Hir, Nir, NirKind, NzEnv, TyEnv, VarEnv,
+};
 use crate::syntax::{Const, Expr, Span};
 
 /// The type of a type. 0 is `Type`, 1 is `Kind`, etc...
@@ -134,23 +136,21 @@ impl<'hir> Tir<'hir> {
     }
     pub fn ensure_is_type(&self, env: &TyEnv) -> Result<(), TypeError> {
         if self.ty().as_const().is_none() {
-            return mkerr(
-                ErrorBuilder::new(format!(
-                    "Expected a type, found: `{}`",
-                    self.to_expr_tyenv(env),
-                ))
-                .span_err(
-                    self.span(),
-                    format!(
-                        "this has type: `{}`",
-                        self.ty().to_expr_tyenv(env)
-                    ),
-                )
-                .help(format!(
+            let message = TypeMessage::NotAType(self.to_expr_tyenv(env));
+            return mk_builder_err(
+                ErrorBuilder::new(&message)
+                    .span_err(
+                        self.span(),
+                        format!(
+                            "this has type: `{}`",
+                            self.ty().to_expr_tyenv(env)
+                        ),
+                    )
+                    .help(format!(
                     "An expression in type position must have type `Type`, \
                      `Kind` or `Sort`",
-                ))
-                .format(),
+                )),
+                message,
             );
         }
         Ok(())
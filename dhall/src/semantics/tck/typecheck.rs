@@ -14,15 +14,21 @@ fn function_check(a: Const, b: Const) -> Const {
     }
 }
 
-pub fn mkerr<T, S: ToString>(msg: S) -> Result<T, TypeError> {
-    Err(TypeError::new(TypeMessage::Custom(msg.to_string())))
+/// Report `message`, displayed with the output of `builder`.
+pub fn mk_builder_err<T>(
+    builder: &mut ErrorBuilder,
+    message: TypeMessage,
+) -> Result<T, TypeError> {
+    Err(TypeError::from_builder(message, builder))
 }
 
-pub fn mk_span_err<T, S: ToString>(span: Span, msg: S) -> Result<T, TypeError> {
-    mkerr(
-        ErrorBuilder::new(msg.to_string())
-            .span_err(span, msg.to_string())
-            .format(),
+pub fn mk_span_err<T>(
+    span: Span,
+    message: TypeMessage,
+) -> Result<T, TypeError> {
+    mk_builder_err(
+        ErrorBuilder::new(&message).span_err(span, &message),
+        message,
     )
 }
 
@@ -33,7 +39,7 @@ fn type_one_layer(
     ekind: ExprKind<Tir<'_>>,
     span: Span,
 ) -> Result<Type, TypeError> {
-    let span_err = |msg: &str| mk_span_err(span.clone(), msg);
+    let span_err = |msg: TypeMessage| mk_span_err(span.clone(), msg);
 
     Ok(match &ekind {
         ExprKind::Import(..) => {
@@ -66,7 +72,7 @@ fn type_one_layer(
                 use InterpolatedTextContents::Expr;
                 if let Expr(x) = contents {
                     if *x.ty() != text_type {
-                        return span_err("InvalidTextInterpolation");
+                        return span_err(TypeMessage::InvalidTextInterpolation);
                     }
                 }
             }
@@ -74,7 +80,7 @@ fn type_one_layer(
         }
         ExprKind::SomeLit(x) => {
             if x.ty().ty().as_const() != Some(Const::Type) {
-                return span_err("InvalidOptionalType");
+                return span_err(TypeMessage::InvalidOptionalType);
             }
 
             let t = x.ty().to_nir();
@@ -86,7 +92,7 @@ fn type_one_layer(
             let t = t.eval_to_type(env)?;
             match t.kind() {
                 NirKind::ListType(..) => {}
-                _ => return span_err("InvalidListType"),
+                _ => return span_err(TypeMessage::InvalidListType),
             };
             t
         }
@@ -95,11 +101,14 @@ fn type_one_layer(
             let x = iter.next().unwrap();
             for y in iter {
                 if x.ty() != y.ty() {
-                    return span_err("InvalidListElement");
+                    return span_err(TypeMessage::InvalidListElement {
+                        expected: x.ty().to_expr_tyenv(env),
+                        found: y.ty().to_expr_tyenv(env),
+                    });
                 }
             }
             if x.ty().ty().as_const() != Some(Const::Type) {
-                return span_err("InvalidListType");
+                return span_err(TypeMessage::InvalidListType);
             }
 
             let t = x.ty().to_nir();
@@ -108,11 +117,16 @@ fn type_one_layer(
         ExprKind::RecordLit(kvs) => {
             // An empty record type has type Type
             let mut k = Const::Type;
-            for v in kvs.values() {
+            for (x, v) in kvs {
                 // Check that the fields have a valid kind
                 match v.ty().ty().as_const() {
                     Some(c) => k = max(k, c),
-                    None => return mk_span_err(v.span(), "InvalidFieldType"),
+                    None => {
+                        return mk_span_err(
+                            v.span(),
+                            TypeMessage::InvalidFieldType(x.clone()),
+                        )
+                    }
                 }
             }
 
@@ -126,11 +140,16 @@ fn type_one_layer(
         ExprKind::RecordType(kts) => {
             // An empty record type has type Type
             let mut k = Const::Type;
-            for t in kts.values() {
+            for (x, t) in kts {
                 // Check the type is a Const and compute final type
                 match t.ty().as_const() {
                     Some(c) => k = max(k, c),
-                    None => return mk_span_err(t.span(), "InvalidFieldType"),
+                    None => {
+                        return mk_span_err(
+                            t.span(),
+                            TypeMessage::InvalidFieldType(x.clone()),
+                        )
+                    }
                 }
             }
 
@@ -140,12 +159,15 @@ fn type_one_layer(
             // An empty union type has type Type;
             // an union type with only unary variants also has type Type
             let mut k = Const::Type;
-            for t in kts.values() {
+            for (x, t) in kts {
                 if let Some(t) = t {
                     match t.ty().as_const() {
                         Some(c) => k = max(k, c),
                         None => {
-                            return mk_span_err(t.span(), "InvalidVariantType")
+                            return mk_span_err(
+                                t.span(),
+                                TypeMessage::InvalidVariantType(x.clone()),
+                            )
                         }
                     }
                 }
@@ -158,8 +180,13 @@ fn type_one_layer(
             let t = t.eval_to_type(env)?;
             match t.kind() {
                 NirKind::Equivalence(x, y) if x == y => {}
-                NirKind::Equivalence(..) => return span_err("AssertMismatch"),
-                _ => return span_err("AssertMustTakeEquivalence"),
+                NirKind::Equivalence(x, y) => {
+                    return span_err(TypeMessage::AssertMismatch {
+                        expected: x.to_expr_tyenv(env),
+                        found: y.to_expr_tyenv(env),
+                    })
+                }
+                _ => return span_err(TypeMessage::AssertMustTakeEquivalence),
             }
             t
         }
@@ -182,7 +209,7 @@ pub fn type_with<'hir>(
             unreachable!("Hir should contain no unresolved variables")
         }
         HirKind::Expr(ExprKind::Const(Const::Sort)) => {
-            return mk_span_err(hir.span(), "Sort does not have a type")
+            return mk_span_err(hir.span(), TypeMessage::Untyped)
         }
         HirKind::Expr(ExprKind::Annot(x, t)) => {
             let t = match t.kind() {
@@ -203,7 +230,12 @@ pub fn type_with<'hir>(
             let u_annot = annot.ty().as_const().unwrap();
             let u_body = match body.ty().ty().as_const() {
                 Some(k) => k,
-                _ => return mk_span_err(hir.span(), "Invalid output type"),
+                _ => {
+                    return mk_span_err(
+                        hir.span(),
+                        TypeMessage::InvalidOutputType,
+                    )
+                }
             };
             let u = function_check(u_annot, u_body).to_universe();
             let ty_hir = Hir::new(
@@ -253,11 +285,10 @@ pub fn type_with<'hir>(
         if *tir.ty() != annot {
             return mk_span_err(
                 hir.span(),
-                &format!(
-                    "annot mismatch: {} != {}",
-                    tir.ty().to_expr_tyenv(env),
-                    annot.to_expr_tyenv(env)
-                ),
+                TypeMessage::AnnotMismatch {
+                    expected: annot.to_expr_tyenv(env),
+                    found: tir.ty().to_expr_tyenv(env),
+                },
             );
         }
     }
@@ -279,3 +310,75 @@ pub fn typecheck_with<'hir>(
     let ty = typecheck(ty)?.eval_to_type(&TyEnv::new())?;
     type_with(&TyEnv::new(), hir, Some(ty))
 }
+
+#[cfg(test)]
+mod test {
+    use crate::error::{Error, TypeError, TypeMessage};
+    use crate::semantics::parse::parse_str;
+
+    fn type_error(source: &str) -> Result<TypeError, Error> {
+        Ok(parse_str(source)?.skip_resolve()?.typecheck().unwrap_err())
+    }
+
+    #[test]
+    fn type_errors_should_carry_structured_messages() -> Result<(), Error> {
+        let err = type_error("{ y = {=} }.x")?;
+        assert!(matches!(
+            err.message(),
+            TypeMessage::MissingRecordField(x) if x.as_ref() == "x"
+        ));
+        let expected = include_str!(
+            "../../../tests/type-inference/failure/unit/RecordSelectionNotPresent.txt"
+        );
+        assert_eq!(err.to_string(), expected.trim_end());
+
+        let err = type_error("(λ(_ : Natural) → _) True")?;
+        match err.message() {
+            TypeMessage::TypeMismatch { expected, found } => {
+                assert_eq!(expected.to_string(), "Natural");
+                assert_eq!(found.to_string(), "Bool");
+            }
+            message => panic!("unexpected error: {:?}", message),
+        }
+        let expected = include_str!(
+            "../../../tests/type-inference/failure/unit/FunctionApplicationArgumentNotMatch.txt"
+        );
+        assert_eq!(err.to_string(), expected.trim_end());
+
+        let err = type_error("True True")?;
+        assert!(matches!(
+            err.message(),
+            TypeMessage::NotAFunction(ty) if ty.to_string() == "Bool"
+        ));
+        let err = type_error("merge { x = True } (< x : Bool >.x True)")?;
+        assert!(matches!(
+            err.message(),
+            TypeMessage::MergeHandlerNotAFunction { variant, .. }
+                if variant.as_ref() == "x"
+        ));
+        let expected = include_str!(
+            "../../../tests/type-inference/failure/unit/MergeHandlerNotFunction.txt"
+        );
+        assert_eq!(err.to_string(), expected.trim_end());
+
+        let err = type_error("if True then 1 else False")?;
+        assert!(matches!(
+            err.message(),
+            TypeMessage::IfBranchMismatch { expected, found }
+                if expected.to_string() == "Natural"
+                    && found.to_string() == "Bool"
+        ));
+        assert_eq!(
+            err.message().to_string(),
+            "the branches of an `if` must have the same type: `Natural` and \
+             `Bool`"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn type_errors_should_be_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Error>();
+    }
+}
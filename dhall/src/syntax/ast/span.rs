@@ -1,9 +1,24 @@
-use std::rc::Rc;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::syntax::text::comments::Comments;
+use crate::syntax::text::parser::collect_comments;
+
+/// The text of a parsed file, with a description of where it comes from
+#[derive(Debug)]
+pub struct Source {
+    text: String,
+    /// The path, url or environment variable the text was read from, if any.
+    origin: Option<String>,
+    /// Only collected when needed, for formatting.
+    comments: OnceCell<Comments>,
+}
 
 /// A location in the source text
 #[derive(Debug, Clone)]
 pub struct ParsedSpan {
-    input: Rc<str>,
+    input: Arc<Source>,
     /// # Safety
     ///
     /// Must be a valid character boundary index into `input`.
@@ -14,6 +29,22 @@ pub struct ParsedSpan {
     end: usize,
 }
 
+/// A position in the source text. Lines and columns start at 1, and columns count characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A range of source text, with the file it belongs to. The end position is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceRange {
+    /// The path, url or environment variable the text was read from, if any.
+    pub file: Option<String>,
+    pub start: SourcePosition,
+    pub end: SourcePosition,
+}
+
 #[derive(Debug, Clone)]
 pub enum Span {
     /// A location in the source text
@@ -28,22 +59,100 @@ pub enum Span {
     Artificial,
 }
 
+impl std::fmt::Display for SourceRange {
+    /// Displays the start of the range, as `file:line:column`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}", self.start.line, self.start.column)
+    }
+}
+
+impl Source {
+    pub fn new(text: String, origin: Option<String>) -> Self {
+        Source {
+            text,
+            origin,
+            comments: OnceCell::new(),
+        }
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+    pub(crate) fn comments(&self) -> &Comments {
+        self.comments.get_or_init(|| collect_comments(&self.text))
+    }
+    /// The byte offset of a position in the text, if it exists.
+    pub(crate) fn offset(&self, position: SourcePosition) -> Option<usize> {
+        let line_start = match position.line.checked_sub(1)? {
+            0 => 0,
+            n => self.text.match_indices('\n').nth(n - 1)?.0 + 1,
+        };
+        let line = self.text[line_start..].split('\n').next()?;
+        let column = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(line.len()))
+            .nth(position.column.checked_sub(1)?)?;
+        Some(line_start + column)
+    }
+}
+
 impl ParsedSpan {
     pub fn to_input(&self) -> String {
-        self.input.to_string()
+        self.input.text.clone()
+    }
+    /// The path, url or environment variable the source text was read from, if any.
+    pub fn origin(&self) -> Option<&str> {
+        self.input.origin()
+    }
+    /// The byte offset of the start of the span.
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+    /// The byte offset of the end of the span.
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+    /// The span between the given byte offsets of the same source text.
+    pub(crate) fn with_offsets(&self, start: usize, end: usize) -> ParsedSpan {
+        ParsedSpan {
+            input: self.input.clone(),
+            start,
+            end,
+        }
+    }
+    pub(crate) fn source(&self) -> &Source {
+        &self.input
+    }
+    /// Whether the two spans point into the same source text.
+    pub fn same_source(&self, other: &ParsedSpan) -> bool {
+        Arc::ptr_eq(&self.input, &other.input)
+    }
+    /// The lines and columns covered by this span.
+    pub fn range(&self) -> SourceRange {
+        SourceRange {
+            file: self.input.origin.clone(),
+            start: position_from_byte_idx(&self.input.text, self.start),
+            end: position_from_byte_idx(&self.input.text, self.end),
+        }
     }
     /// Convert to a char range for consumption by annotate_snippets.
     /// This compensates for  https://github.com/rust-lang/annotate-snippets-rs/issues/24
     pub fn as_char_range(&self) -> (usize, usize) {
         (
-            char_idx_from_byte_idx(&self.input, self.start),
-            char_idx_from_byte_idx(&self.input, self.end),
+            char_idx_from_byte_idx(&self.input.text, self.start),
+            char_idx_from_byte_idx(&self.input.text, self.end),
         )
     }
 }
 
 impl Span {
-    pub fn make(input: Rc<str>, sp: pest::Span) -> Self {
+    pub fn make(input: Arc<Source>, sp: pest::Span) -> Self {
         Span::Parsed(ParsedSpan {
             input,
             start: sp.start(),
@@ -58,7 +167,7 @@ impl Span {
         use std::cmp::{max, min};
         use Span::*;
         match (self, other) {
-            (Parsed(x), Parsed(y)) if Rc::ptr_eq(&x.input, &y.input) => {
+            (Parsed(x), Parsed(y)) if Arc::ptr_eq(&x.input, &y.input) => {
                 Parsed(ParsedSpan {
                     input: x.input.clone(),
                     start: min(x.start, y.start),
@@ -77,6 +186,26 @@ impl Span {
             ),
         }
     }
+
+    /// The lines and columns covered by this span, if it points to a location in the source
+    /// text.
+    pub fn range(&self) -> Option<SourceRange> {
+        match self {
+            Span::Parsed(span) => Some(span.range()),
+            _ => None,
+        }
+    }
+}
+
+/// Compute the line and column of a byte idx into a string. The byte idx must be at a char
+/// boundary.
+fn position_from_byte_idx(input: &str, idx: usize) -> SourcePosition {
+    let before = &input[..idx];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    SourcePosition {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
 }
 
 /// Convert a byte idx into a string into a char idx for consumption by annotate_snippets.
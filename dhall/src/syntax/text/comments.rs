@@ -0,0 +1,218 @@
+//! Comments are not part of the syntax tree. Instead, they are collected from the parse tree into
+//! a table stored alongside the source text, keyed by the position of the syntax they precede, so
+//! that the formatter can print them back.
+use pest::iterators::Pair;
+use std::collections::{HashMap, HashSet};
+
+use crate::syntax::text::parser::Rule;
+use crate::syntax::Label;
+
+/// The comments of a source text that can be printed back: the header of the file, and the
+/// comments before `let` bindings and record fields. Other comments are dropped.
+#[derive(Debug, Default)]
+pub(crate) struct Comments {
+    /// Where the expression starts, after the header.
+    start: usize,
+    /// The comments and blank lines before the expression.
+    header: String,
+    /// Comments before `let` bindings, by the position of the `let` keyword.
+    lets: HashMap<usize, Vec<String>>,
+    /// Comments before record fields, by the position of the record and the field label.
+    fields: HashMap<(usize, String), Vec<String>>,
+}
+
+impl Comments {
+    /// Collect the comments of `text`, given its parse tree.
+    pub(crate) fn collect(text: &str, tree: Pair<'_, Rule>) -> Self {
+        let mut collector = Collector::default();
+        collector.walk(tree, 0);
+
+        let start = skip_comments(text);
+        let mut comments = Comments {
+            start,
+            ..Comments::default()
+        };
+        let mut gap_start = 0;
+        let end = (text.len(), text.len());
+        for (token_start, token_end) in
+            collector.tokens.into_iter().chain(Some(end))
+        {
+            for (comment, target) in
+                comments_in_gap(text, gap_start, token_start)
+            {
+                if target == start {
+                    // Part of the header
+                } else if collector.lets.contains(&target) {
+                    comments.lets.entry(target).or_default().push(comment);
+                } else if let Some(field) = collector.fields.get(&target) {
+                    let field = field.clone();
+                    comments.fields.entry(field).or_default().push(comment);
+                }
+            }
+            gap_start = gap_start.max(token_end);
+        }
+        comments.header = text[..start].trim_start().to_string();
+        comments
+    }
+
+    /// The comments before the whole expression, if it starts at `start`.
+    pub(crate) fn header(&self, start: usize) -> Option<&str> {
+        if start == self.start && !self.header.is_empty() {
+            Some(&self.header)
+        } else {
+            None
+        }
+    }
+
+    /// The comments before the `let` binding that starts at `start`.
+    pub(crate) fn before_let(&self, start: usize) -> &[String] {
+        self.lets.get(&start).map_or(&[], Vec::as_slice)
+    }
+
+    /// The comments before the field `label` of the record that starts at `start`.
+    pub(crate) fn before_field(
+        &self,
+        start: usize,
+        label: &Label,
+    ) -> &[String] {
+        self.fields
+            .get(&(start, String::from(label)))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Walks the parse tree to find where the tokens, `let` bindings and record fields start.
+#[derive(Default)]
+struct Collector {
+    /// The spans of the parse tree leaves. Comments can only be between them.
+    tokens: Vec<(usize, usize)>,
+    lets: HashSet<usize>,
+    /// The record and label of the fields, by the position of the field.
+    fields: HashMap<usize, (usize, String)>,
+}
+
+impl Collector {
+    /// `record` is the start of the innermost enclosing record, if any.
+    fn walk(&mut self, pair: Pair<'_, Rule>, record: usize) {
+        let span = pair.as_span();
+        let mut record = record;
+        match pair.as_rule() {
+            Rule::primitive_expression => record = span.start(),
+            Rule::let_binding => {
+                self.lets.insert(span.start());
+            }
+            Rule::record_literal_entry | Rule::record_type_entry => {
+                let mut label = pair.clone();
+                while let Some(child) = label.clone().into_inner().next() {
+                    label = child;
+                }
+                let field = (record, label.as_str().to_string());
+                self.fields.insert(span.start(), field);
+            }
+            _ => {}
+        }
+        // Comments inside of these are not collected.
+        let opaque = matches!(
+            pair.as_rule(),
+            Rule::double_quote_literal
+                | Rule::single_quote_literal
+                | Rule::import
+        );
+        let mut children = pair.into_inner().peekable();
+        if opaque || children.peek().is_none() {
+            self.tokens.push((span.start(), span.end()));
+        } else {
+            for child in children {
+                self.walk(child, record);
+            }
+        }
+    }
+}
+
+/// The comments between `start` and `end`, with the position of the syntax that follows them,
+/// skipping separators.
+fn comments_in_gap(
+    text: &str,
+    start: usize,
+    end: usize,
+) -> Vec<(String, usize)> {
+    let mut comments = Vec::new();
+    let mut pending = Vec::new();
+    let mut i = start;
+    while i < end {
+        let rest = &text[i..end];
+        if rest.starts_with("--") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            pending.push(rest[..len].trim_end().to_string());
+            i += len;
+        } else if rest.starts_with("{-") {
+            let len = block_comment_len(rest);
+            let column = i - text[..i].rfind('\n').map_or(0, |nl| nl + 1);
+            pending.push(dedent(&rest[..len], column));
+            i += len;
+        } else {
+            let c = rest.chars().next().unwrap();
+            if !c.is_whitespace() && c != ',' {
+                comments.extend(pending.drain(..).map(|comment| (comment, i)));
+            }
+            i += c.len_utf8();
+        }
+    }
+    comments.extend(pending.into_iter().map(|comment| (comment, end)));
+    comments
+}
+
+/// The position of the first token of `text`, after whitespace and comments.
+fn skip_comments(text: &str) -> usize {
+    let mut i = 0;
+    loop {
+        let rest = text[i..].trim_start();
+        i = text.len() - rest.len();
+        if rest.starts_with("--") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("{-") {
+            i += block_comment_len(rest);
+        } else {
+            return i;
+        }
+    }
+}
+
+/// The length of the block comment at the start of `s`. Block comments can be nested.
+fn block_comment_len(s: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with("{-") {
+            depth += 1;
+            i += 2;
+        } else if s[i..].starts_with("-}") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            i += s[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    i
+}
+
+/// Removes the indentation of the comment from its continuation lines, so that it can be printed
+/// at a different indentation.
+fn dedent(comment: &str, column: usize) -> String {
+    comment
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.trim_end();
+            if i == 0 {
+                return line;
+            }
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            &line[indent.min(column)..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
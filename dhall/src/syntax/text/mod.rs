@@ -1,2 +1,4 @@
+pub(crate) mod comments;
 pub mod parser;
+pub mod pretty;
 pub mod printer;
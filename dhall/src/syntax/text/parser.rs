@@ -3,17 +3,18 @@ use pest::prec_climber as pcl;
 use pest::prec_climber::PrecClimber;
 use std::collections::{BTreeMap, BTreeSet};
 use std::iter::once;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use pest_consume::{match_nodes, Parser};
 
 use crate::operations::OpKind::*;
+use crate::syntax::text::comments::Comments;
 use crate::syntax::ExprKind::*;
 use crate::syntax::NumKind::*;
 use crate::syntax::{
     Double, Expr, FilePath, FilePrefix, Hash, ImportMode, ImportTarget,
     Integer, InterpolatedText, InterpolatedTextContents, Label, NaiveDouble,
-    Natural, Scheme, Span, UnspannedExpr, URL, V,
+    Natural, Scheme, Source, Span, UnspannedExpr, URL, V,
 };
 
 // This file consumes the parse tree generated by pest and turns it into
@@ -23,7 +24,7 @@ use crate::syntax::{
 
 type ParsedText = InterpolatedText<Expr>;
 type ParsedTextContents = InterpolatedTextContents<Expr>;
-type ParseInput<'input> = pest_consume::Node<'input, Rule, Rc<str>>;
+type ParseInput<'input> = pest_consume::Node<'input, Rule, Arc<Source>>;
 
 pub type ParseError = pest::error::Error<Rule>;
 pub type ParseResult<T> = Result<T, ParseError>;
@@ -1023,15 +1024,46 @@ impl DhallParser {
 }
 
 pub fn parse_expr(input_str: &str) -> ParseResult<Expr> {
-    let rc_input_str = input_str.to_string().into();
-    let inputs = DhallParser::parse_with_userdata(
+    parse_expr_with_origin(input_str, None)
+}
+
+/// Parse an expression read from `origin`, i.e. a path, url or environment variable. The origin
+/// is recorded in the spans of the expression and in parse errors.
+pub fn parse_expr_with_origin(
+    input_str: &str,
+    origin: Option<&str>,
+) -> ParseResult<Expr> {
+    let source = Arc::new(Source::new(
+        input_str.to_string(),
+        origin.map(str::to_string),
+    ));
+    let parse = || {
+        let inputs = DhallParser::parse_with_userdata(
+            Rule::final_expression,
+            input_str,
+            source.clone(),
+        )?;
+        Ok(match_nodes!(<DhallParser>; inputs;
+            [expression(e)] => e,
+        ))
+    };
+    parse().map_err(|e: ParseError| match origin {
+        Some(origin) => e.with_path(origin),
+        None => e,
+    })
+}
+
+/// Collect the comments of a source text that was parsed successfully. The parse tree isn't kept
+/// after parsing, so the text is parsed again.
+pub(crate) fn collect_comments(input_str: &str) -> Comments {
+    let tree = <DhallParser as pest::Parser<Rule>>::parse(
         Rule::final_expression,
         input_str,
-        rc_input_str,
-    )?;
-    Ok(match_nodes!(<DhallParser>; inputs;
-        [expression(e)] => e,
-    ))
+    );
+    match tree.ok().and_then(|mut pairs| pairs.next()) {
+        Some(tree) => Comments::collect(input_str, tree),
+        None => Comments::default(),
+    }
 }
 
 #[test]
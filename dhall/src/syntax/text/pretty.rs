@@ -0,0 +1,748 @@
+//! A layout engine that breaks long expressions over several lines, following the conventions of
+//! `dhall format`: leading separators in records, lists and unions, aligned arrows in function
+//! types, `let` bindings separated by blank lines and multi-line strings.
+use itertools::Itertools;
+
+use crate::operations::OpKind;
+use crate::syntax::text::comments::Comments;
+use crate::syntax::text::printer::{quote_label, PhasedExpr, PrintPhase};
+use crate::syntax::{
+    Expr, ExprKind, Import, InterpolatedText, InterpolatedTextContents, Label,
+    Span,
+};
+
+/// A document to lay out, in the style of Wadler's "A prettier printer".
+#[derive(Debug, Clone)]
+enum Doc {
+    /// Text without newlines
+    Text(String),
+    /// A newline followed by the current indentation
+    HardLine,
+    /// Laid out as the first document if the enclosing group fits on one line, and as the second
+    /// one otherwise
+    FlatAlt(Box<Doc>, Box<Doc>),
+    /// Indents the lines of the document by this many more columns
+    Nest(usize, Box<Doc>),
+    /// Indents the lines of the document to the column where it starts
+    Align(Box<Doc>),
+    /// Laid out on one line if it fits in the remaining width
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+/// `flat` if the enclosing group fits on one line, and a newline otherwise.
+fn line(flat: &str) -> Doc {
+    Doc::FlatAlt(Box::new(text(flat)), Box::new(Doc::HardLine))
+}
+
+/// Text that only appears when the enclosing group is broken over several lines.
+fn broken_only(s: impl Into<String>) -> Doc {
+    Doc::FlatAlt(Box::new(text("")), Box::new(text(s)))
+}
+
+fn nest(indent: usize, doc: Doc) -> Doc {
+    Doc::Nest(indent, Box::new(doc))
+}
+
+fn align(doc: Doc) -> Doc {
+    Doc::Align(Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Whether `doc` laid out on one line, followed by the documents left to lay out, fits in
+/// `remaining` columns up to the next newline.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => remaining -= s.chars().count() as isize,
+            // A hard newline can't be laid out on one line.
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::FlatAlt(flat, broken) => match mode {
+                Mode::Flat => stack.push((mode, flat)),
+                Mode::Break => stack.push((mode, broken)),
+            },
+            Doc::Nest(_, doc) | Doc::Align(doc) | Doc::Group(doc) => {
+                stack.push((mode, doc))
+            }
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (mode, doc)))
+            }
+        }
+    }
+    false
+}
+
+fn render(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    // Where the indentation of the current line starts and ends.
+    let mut indent_start = 0;
+    let mut line_start = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                column += s.chars().count();
+            }
+            Doc::HardLine => {
+                // Don't indent empty lines.
+                if out.len() == line_start {
+                    out.truncate(indent_start);
+                }
+                out.push('\n');
+                indent_start = out.len();
+                out.push_str(&" ".repeat(indent));
+                column = indent;
+                line_start = out.len();
+            }
+            Doc::FlatAlt(flat, broken) => match mode {
+                Mode::Flat => stack.push((indent, mode, flat)),
+                Mode::Break => stack.push((indent, mode, broken)),
+            },
+            Doc::Nest(i, doc) => stack.push((indent + i, mode, doc)),
+            Doc::Align(doc) => stack.push((column, mode, doc)),
+            Doc::Group(doc) => {
+                let remaining = width as isize - column as isize;
+                let mode = if mode == Mode::Flat || fits(remaining, doc, &stack)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)))
+            }
+        }
+    }
+    out
+}
+
+/// Looks up comments in the source text `e` was parsed from, given where `e` starts.
+fn with_comments<T: Default>(
+    e: &Expr,
+    f: impl FnOnce(&Comments, usize) -> T,
+) -> T {
+    match e.span() {
+        Span::Parsed(span) => f(span.source().comments(), span.start()),
+        _ => T::default(),
+    }
+}
+
+/// Each comment on its own lines.
+fn comments_doc(comments: &[String]) -> Doc {
+    let lines = comments.iter().flat_map(|comment| comment.split('\n'));
+    Doc::Concat(lines.flat_map(|l| vec![text(l), Doc::HardLine]).collect())
+}
+
+fn phased(e: PhasedExpr<'_>) -> Doc {
+    expr_doc(e.0, e.1)
+}
+
+fn expr_doc(e: &Expr, phase: PrintPhase) -> Doc {
+    let kind = e.kind();
+    let doc = kind_doc(e, kind.annotate_with_phases());
+    // Paths are printed without quotes when possible, and then a selector would be read as part
+    // of the path.
+    let is_import = matches!(kind, ExprKind::Import(..));
+    if kind.needs_paren(phase) || (is_import && phase > PrintPhase::Import) {
+        Doc::Concat(vec![text("("), align(doc), text(")")])
+    } else {
+        doc
+    }
+}
+
+/// The annotated subexpressions of `e`, if it is of the same kind as its parent and can be
+/// printed without parentheses.
+fn continued<'a>(
+    e: PhasedExpr<'a>,
+    same_kind: impl Fn(&ExprKind<Expr>) -> bool,
+) -> Option<ExprKind<PhasedExpr<'a>>> {
+    let kind = e.0.kind();
+    if same_kind(kind) && !kind.needs_paren(e.1) {
+        Some(kind.annotate_with_phases())
+    } else {
+        None
+    }
+}
+
+/// `{ a, b }` on one line, or with the separators at the start of each line:
+/// ```text
+/// { a
+/// , b
+/// }
+/// ```
+fn list(open: &str, sep: &str, close: &str, items: Vec<Doc>) -> Doc {
+    let flat_sep = if sep == "," { ", " } else { " | " };
+    let mut docs = vec![text(format!("{} ", open))];
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            docs.push(Doc::FlatAlt(
+                Box::new(text(flat_sep)),
+                Box::new(Doc::Concat(vec![
+                    Doc::HardLine,
+                    text(format!("{} ", sep)),
+                ])),
+            ));
+        }
+        docs.push(align(item));
+    }
+    docs.push(line(" "));
+    docs.push(text(close));
+    group(align(Doc::Concat(docs)))
+}
+
+/// `label = value`, with the value on the next line if it doesn't fit.
+fn field(label: &Label, sep: &str, value: Doc) -> Doc {
+    group(Doc::Concat(vec![
+        text(quote_label(label)),
+        text(sep),
+        nest(2, Doc::Concat(vec![line(" "), value])),
+    ]))
+}
+
+/// A field of the record `record`, preceded by its comments.
+fn commented_field(record: &Expr, label: &Label, sep: &str, value: Doc) -> Doc {
+    let comments =
+        with_comments(record, |c, start| c.before_field(start, label).to_vec());
+    Doc::Concat(vec![comments_doc(&comments), field(label, sep, value)])
+}
+
+fn binder(symbol: &str, label: &Label, annot: PhasedExpr<'_>) -> Doc {
+    Doc::Concat(vec![
+        text(format!("{}({} : ", symbol, quote_label(label))),
+        align(phased(annot)),
+        text(")"),
+    ])
+}
+
+/// A chain of functions and function types, with aligned arrows when broken:
+/// ```text
+///   λ(x : Natural)
+/// → λ(y : Natural)
+/// → x + y
+/// ```
+fn arrows(mut kind: ExprKind<PhasedExpr<'_>>) -> Doc {
+    use ExprKind::{Lam, Pi};
+    let mut docs = vec![broken_only("  ")];
+    loop {
+        let body = match kind {
+            Lam(l, t, body) => {
+                docs.push(binder("λ", &l, t));
+                body
+            }
+            Pi(l, t, body) if l.as_ref() == "_" => {
+                docs.push(phased(t));
+                body
+            }
+            Pi(l, t, body) => {
+                docs.push(binder("∀", &l, t));
+                body
+            }
+            _ => unreachable!(),
+        };
+        docs.push(line(" "));
+        docs.push(text("→ "));
+        match continued(body, |k| matches!(k, Lam(..) | Pi(..))) {
+            Some(next) => kind = next,
+            None => {
+                docs.push(align(phased(body)));
+                break;
+            }
+        }
+    }
+    group(align(Doc::Concat(docs)))
+}
+
+/// A chain of `let` bindings, separated by blank lines when broken:
+/// ```text
+/// let x = 1
+///
+/// let y = 2
+///
+/// in  x + y
+/// ```
+fn lets<'a>(mut e: &'a Expr, mut kind: ExprKind<PhasedExpr<'a>>) -> Doc {
+    let mut docs = Vec::new();
+    loop {
+        let body = match kind {
+            ExprKind::Let(l, t, v, body) => {
+                docs.push(comments_doc(&with_comments(e, |c, start| {
+                    c.before_let(start).to_vec()
+                })));
+                let mut binding =
+                    vec![text(format!("let {}", quote_label(&l)))];
+                if let Some(t) = t {
+                    binding.push(text(" : "));
+                    binding.push(phased(t));
+                }
+                binding.push(text(" ="));
+                binding.push(nest(
+                    2,
+                    Doc::Concat(vec![line(" "), align(phased(v))]),
+                ));
+                docs.push(group(Doc::Concat(binding)));
+                body
+            }
+            _ => unreachable!(),
+        };
+        match continued(body, |k| matches!(k, ExprKind::Let(..))) {
+            Some(next) => {
+                docs.push(Doc::FlatAlt(
+                    Box::new(text(" in ")),
+                    Box::new(Doc::Concat(vec![Doc::HardLine, Doc::HardLine])),
+                ));
+                e = body.0;
+                kind = next;
+            }
+            None => {
+                docs.push(Doc::FlatAlt(
+                    Box::new(text(" in ")),
+                    Box::new(Doc::Concat(vec![
+                        Doc::HardLine,
+                        Doc::HardLine,
+                        text("in  "),
+                    ])),
+                ));
+                docs.push(align(phased(body)));
+                break;
+            }
+        }
+    }
+    group(align(Doc::Concat(docs)))
+}
+
+/// The lines of a text literal that can be written as a multi-line `''` literal.
+fn multiline_lines(t: &InterpolatedText<PhasedExpr<'_>>) -> Option<Vec<Doc>> {
+    use InterpolatedTextContents::{Expr, Text};
+    let texts = || {
+        t.iter().filter_map(|x| match x {
+            Text(s) => Some(s),
+            Expr(_) => None,
+        })
+    };
+    let has_newline = texts().any(|s| s.contains('\n'));
+    let has_control = texts()
+        .any(|s| s.chars().any(|c| c.is_control() && c != '\n' && c != '\t'));
+    if !has_newline || has_control {
+        return None;
+    }
+    let mut lines = vec![Vec::new()];
+    for x in t.iter() {
+        match x {
+            Text(s) => {
+                for (i, part) in s.split('\n').enumerate() {
+                    if i > 0 {
+                        lines.push(Vec::new());
+                    }
+                    let escaped =
+                        part.replace("''", "'''").replace("${", "''${");
+                    lines.last_mut().unwrap().push(text(escaped));
+                }
+            }
+            Expr(e) => {
+                let e = text(format!("${{ {} }}", e));
+                lines.last_mut().unwrap().push(e);
+            }
+        }
+    }
+    // The closing quotes follow the last line. Its indentation is stripped from every line, so
+    // it must not start with whitespace, and it must not end with a quote.
+    if let Some(Text(last)) = t.iter().last() {
+        let last_line = last.rsplit('\n').next().unwrap_or_default();
+        if last_line.starts_with(|c| matches!(c, ' ' | '\t'))
+            || last.ends_with('\'')
+        {
+            return None;
+        }
+    }
+    Some(lines.into_iter().map(Doc::Concat).collect())
+}
+
+fn text_doc(t: InterpolatedText<PhasedExpr<'_>>) -> Doc {
+    match multiline_lines(&t) {
+        Some(lines) => {
+            let mut docs = vec![text("''")];
+            for line in lines {
+                docs.push(Doc::HardLine);
+                docs.push(line);
+            }
+            docs.push(text("''"));
+            Doc::Concat(docs)
+        }
+        None => text(t.to_string()),
+    }
+}
+
+fn op_doc(op: OpKind<PhasedExpr<'_>>) -> Doc {
+    use OpKind::*;
+    let opt_annot = |t: Option<PhasedExpr<'_>>| match t {
+        Some(t) => Doc::Concat(vec![text(" : "), phased(t)]),
+        None => text(""),
+    };
+    match op {
+        App(f, a) => {
+            let mut args = vec![a];
+            let mut f = f;
+            while let Some(App(g, a)) = continued(f, |k| {
+                matches!(k, ExprKind::Op(App(..)))
+            })
+            .map(|k| match k {
+                ExprKind::Op(op) => op,
+                _ => unreachable!(),
+            }) {
+                args.push(a);
+                f = g;
+            }
+            let args = args
+                .into_iter()
+                .rev()
+                .map(|a| Doc::Concat(vec![line(" "), phased(a)]))
+                .collect();
+            group(align(Doc::Concat(vec![
+                phased(f),
+                nest(2, Doc::Concat(args)),
+            ])))
+        }
+        BinOp(op, a, b) => {
+            fn operands<'a>(
+                op: crate::operations::BinOp,
+                e: PhasedExpr<'a>,
+                out: &mut Vec<PhasedExpr<'a>>,
+            ) {
+                match continued(
+                    e,
+                    |k| matches!(k, ExprKind::Op(BinOp(o, _, _)) if *o == op),
+                ) {
+                    Some(ExprKind::Op(BinOp(_, a, b))) => {
+                        operands(op, a, out);
+                        operands(op, b, out);
+                    }
+                    _ => out.push(e),
+                }
+            }
+            let mut es = Vec::new();
+            operands(op, a, &mut es);
+            operands(op, b, &mut es);
+            let op = op.to_string();
+            let mut docs =
+                vec![broken_only(" ".repeat(op.chars().count() + 1))];
+            for (i, e) in es.into_iter().enumerate() {
+                if i > 0 {
+                    docs.push(line(" "));
+                    docs.push(text(format!("{} ", op)));
+                }
+                docs.push(align(phased(e)));
+            }
+            group(align(Doc::Concat(docs)))
+        }
+        BoolIf(a, b, c) => group(align(Doc::Concat(vec![
+            text("if "),
+            align(phased(a)),
+            line(" "),
+            text("then "),
+            align(phased(b)),
+            line(" "),
+            text("else "),
+            align(phased(c)),
+        ]))),
+        Merge(a, b, t) => group(align(Doc::Concat(vec![
+            text("merge"),
+            nest(
+                2,
+                Doc::Concat(vec![line(" "), phased(a), line(" "), phased(b)]),
+            ),
+            opt_annot(t),
+        ]))),
+        ToMap(a, t) => {
+            Doc::Concat(vec![text("toMap "), phased(a), opt_annot(t)])
+        }
+        Field(a, l) => {
+            Doc::Concat(vec![phased(a), text(format!(".{}", quote_label(&l)))])
+        }
+        Projection(e, ls) => Doc::Concat(vec![
+            phased(e),
+            text(format!(".{{ {} }}", ls.iter().map(quote_label).join(", "))),
+        ]),
+        ProjectionByExpr(a, b) => {
+            Doc::Concat(vec![phased(a), text(".("), phased(b), text(")")])
+        }
+        Completion(a, b) => Doc::Concat(vec![phased(a), text("::"), phased(b)]),
+        With(a, ls, b) => Doc::Concat(vec![
+            phased(a),
+            text(format!(" with {} = ", ls.iter().map(quote_label).join("."))),
+            phased(b),
+        ]),
+    }
+}
+
+fn kind_doc(e: &Expr, kind: ExprKind<PhasedExpr<'_>>) -> Doc {
+    use ExprKind::*;
+    match kind {
+        Lam(..) | Pi(..) => arrows(kind),
+        Let(..) => lets(e, kind),
+        TextLit(t) => text_doc(t),
+        SomeLit(e) => Doc::Concat(vec![text("Some "), phased(e)]),
+        EmptyListLit(t) => Doc::Concat(vec![text("[] : "), phased(t)]),
+        NEListLit(es) => {
+            list("[", ",", "]", es.into_iter().map(phased).collect())
+        }
+        RecordLit(kvs) if !kvs.is_empty() => list(
+            "{",
+            ",",
+            "}",
+            kvs.iter()
+                .map(|(k, v)| commented_field(e, k, " =", phased(*v)))
+                .collect(),
+        ),
+        RecordType(kts) if !kts.is_empty() => list(
+            "{",
+            ",",
+            "}",
+            kts.iter()
+                .map(|(k, t)| commented_field(e, k, " :", phased(*t)))
+                .collect(),
+        ),
+        UnionType(kts) if !kts.is_empty() => list(
+            "<",
+            "|",
+            ">",
+            kts.iter()
+                .map(|(k, t)| match t {
+                    Some(t) => field(k, " :", phased(*t)),
+                    None => text(quote_label(k)),
+                })
+                .collect(),
+        ),
+        Op(op) => op_doc(op),
+        Annot(a, b) => Doc::Concat(vec![phased(a), text(" : "), phased(b)]),
+        Assert(a) => Doc::Concat(vec![text("assert : "), phased(a)]),
+        Import(import) => import_doc(&import),
+        kind => text(kind.to_string()),
+    }
+}
+
+/// An import, with the components of a local path quoted only when the grammar requires it.
+fn import_doc(import: &Import<PhasedExpr<'_>>) -> Doc {
+    // The `path-character`s of the grammar.
+    let is_path_char = |c: char| {
+        matches!(c,
+            '\u{21}'
+            | '\u{24}'..='\u{27}'
+            | '\u{2A}'..='\u{2B}'
+            | '\u{2D}'..='\u{2E}'
+            | '\u{30}'..='\u{3B}'
+            | '\u{3D}'
+            | '\u{40}'..='\u{5A}'
+            | '\u{5E}'..='\u{7A}'
+            | '\u{7C}'
+            | '\u{7E}'
+        )
+    };
+    let quote_if_needed = |s: &str| -> String {
+        if !s.is_empty() && s.chars().all(is_path_char) {
+            s.to_string()
+        } else {
+            format!("\"{}\"", s)
+        }
+    };
+    let mut doc = String::new();
+    import.write_with(&mut doc, quote_if_needed).unwrap();
+    text(doc)
+}
+
+impl Expr {
+    /// Print the expression like `dhall format`, breaking it over several lines to fit in
+    /// `width` columns where possible.
+    ///
+    /// If the expression was parsed from text, the comments at the start of the text and before
+    /// `let` bindings and record fields are printed back.
+    pub fn pretty(&self, width: usize) -> String {
+        let header =
+            with_comments(self, |c, start| c.header(start).map(str::to_string));
+        let mut docs = Vec::new();
+        if let Some(header) = header {
+            let lines = header.trim_end().split('\n');
+            docs.extend(
+                lines.flat_map(|l| vec![text(l.trim_end()), Doc::HardLine]),
+            );
+            if header
+                .trim_end_matches(|c| matches!(c, ' ' | '\t' | '\r'))
+                .ends_with("\n\n")
+            {
+                docs.push(Doc::HardLine);
+            }
+        }
+        docs.push(expr_doc(self, PrintPhase::Base));
+        render(&Doc::Concat(docs), width)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::syntax::parse_expr;
+
+    fn pretty(s: &str, width: usize) -> String {
+        let expr = parse_expr(s).unwrap();
+        let printed = expr.pretty(width);
+        assert_eq!(parse_expr(&printed).unwrap(), expr, "{}", printed);
+        printed
+    }
+
+    #[test]
+    fn short_expressions_should_stay_on_one_line() {
+        assert_eq!(
+            pretty("{ a = 1, b = [1, 2], c = <A | B : Natural>.A }", 80),
+            "{ a = 1, b = [ 1, 2 ], c = < A | B : Natural >.A }"
+        );
+        assert_eq!(pretty("let x = 1 in x + 1", 80), "let x = 1 in x + 1");
+    }
+
+    #[test]
+    fn records_and_lists_should_use_leading_commas() {
+        assert_eq!(
+            pretty(
+                "{ name = \"server\", ports = [80, 443], debug = False }",
+                30
+            ),
+            "{ debug = False\n\
+             , name = \"server\"\n\
+             , ports = [ 80, 443 ]\n\
+             }"
+        );
+        assert_eq!(
+            pretty("{ ports = [80, 443, 8080, 8443] }", 20),
+            "{ ports =\n    [ 80\n    , 443\n    , 8080\n    , 8443\n    ]\n}"
+        );
+    }
+
+    #[test]
+    fn lets_should_be_separated_by_blank_lines() {
+        assert_eq!(
+            pretty("let x = 1 let y = 2 in x + y", 20),
+            "let x = 1\n\nlet y = 2\n\nin  x + y"
+        );
+    }
+
+    #[test]
+    fn arrows_should_be_aligned() {
+        assert_eq!(
+            pretty("∀(a : Type) → (a → Bool) → List a → List a", 30),
+            "  ∀(a : Type)\n→ (a → Bool)\n→ List a\n→ List a"
+        );
+        assert_eq!(
+            pretty(r"\(x : Natural) -> \(y : Natural) -> x + y", 20),
+            "  λ(x : Natural)\n→ λ(y : Natural)\n→ x + y"
+        );
+    }
+
+    #[test]
+    fn texts_with_newlines_should_be_multiline() {
+        assert_eq!(
+            pretty(r#"{ script = "echo ''\n\necho ${x}\n" }"#, 80),
+            "{ script =\n    ''\n    echo '''\n\n    echo ${ x }\n    ''\n}"
+        );
+        // The last line can't start with whitespace.
+        assert_eq!(pretty(r#""a\n b""#, 80), r#""a\n b""#);
+    }
+
+    #[test]
+    fn comments_should_be_kept() {
+        let input = "-- Header\n\n\
+                     let x = 1\n\
+                     {- About y,\n   \
+                        over two lines -}\n\
+                     let y = 2\n\
+                     in  { -- About a\n\
+                     a = x, b = \"-- not a comment\"\n\
+                     -- About c\n\
+                     , c = ./a--b.dhall }";
+        let expected = "-- Header\n\n\
+                        let x = 1\n\n\
+                        {- About y,\n\
+                        \x20  over two lines -}\n\
+                        let y = 2\n\n\
+                        in  { -- About a\n\
+                        \x20     a = x\n\
+                        \x20   , b = \"-- not a comment\"\n\
+                        \x20   , -- About c\n\
+                        \x20     c = ./a--b.dhall\n\
+                        \x20   }";
+        assert_eq!(pretty(input, 80), expected);
+        assert_eq!(pretty(expected, 80), expected);
+    }
+
+    #[test]
+    fn pretty_should_round_trip() {
+        let exprs = [
+            r#"\(x : { a : Natural, b : List Text }) -> merge { A = \(n : Natural) -> n + 1, B = 0 } (<A : Natural | B>.A 3) : Natural"#,
+            r#"if True then [1,2,3] # [4] else [] : List Natural"#,
+            r#"let f = \(x : Natural) -> x * 2 let g : Natural -> Natural = f in g (f 3) ++ "a""#,
+            r#"{ `if` = 1, `a b` = 2, `Type` = Text }"#,
+            r#"x.{ a, b }.(c).d"#,
+            r#"(./foo.dhall).x ? ./"a b"/c-d.dhall"#,
+            r#"T::{ a = 1 } with b.c = 2"#,
+            r#"toMap { a = 1 } : List { mapKey : Text, mapValue : Natural }"#,
+            r#"''
+            foo ${bar}
+              baz '''' ''${x}
+            ''"#,
+            r#""tab\there\nnext""#,
+            r#""ends with quote'\n""#,
+            r#"Some (Some 1) == None Natural && (a || b) === c"#,
+            r#"assert : 1 + 1 === 2"#,
+            r#"forall (a : Type) -> forall (b : Type) -> (a -> b) -> List a -> List b"#,
+            r#"(\(x : Natural) -> x) 1"#,
+            r#"f (g x) (h y z) [1] { a = 1 }"#,
+            r#"./foo.dhall ? env:HOME as Text ? https://example.com/x sha256:d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15"#,
+            r#"missing as Location"#,
+            r#"[] : List { a : Natural, b : < X | Y : Text > }"#,
+            r#"{=}"#,
+            r#"{}"#,
+            r#"<>"#,
+            r#"{ a.b.c = 1, a.b.d = 2 }"#,
+            r#"+1 + 2 * 3.5e10 + -Infinity"#,
+            r#"x@1 + _"#,
+            r#"let x = let y = 1 in y in let z = x in z"#,
+            r#"(let x = 1 in x) + 1"#,
+            r#"showConstructor (<A>.A)"#,
+            r#"[ "''\${", "a\nb", "\${x}" ]"#,
+            r#""\nfoo\n  bar""#,
+            r#""a\n""#,
+            r#""${x}\n${y}""#,
+            r#"Natural/fold 3 Natural (\(n : Natural) -> n + 1) 0"#,
+            r#"(if a then b else c) d"#,
+            r#"(x : T) : U"#,
+            r#"(a -> b) c"#,
+        ];
+        for s in &exprs {
+            for width in &[80, 30, 10, 1] {
+                pretty(s, *width);
+            }
+        }
+    }
+}
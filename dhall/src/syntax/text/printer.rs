@@ -10,7 +10,7 @@ use std::fmt::{self, Display};
 // of automatically getting all the parentheses and precedences right (in a manner dual do Pratt
 // parsing).
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-enum PrintPhase {
+pub(super) enum PrintPhase {
     // `expression`
     Base,
     // `operator-expression`
@@ -28,7 +28,7 @@ enum PrintPhase {
 // Wraps an Expr with a phase, so that phase selection can be done separate from the actual
 // printing.
 #[derive(Copy, Clone)]
-struct PhasedExpr<'a>(&'a Expr, PrintPhase);
+pub(super) struct PhasedExpr<'a>(pub(super) &'a Expr, pub(super) PrintPhase);
 
 impl<'a> PhasedExpr<'a> {
     fn phase(self, phase: PrintPhase) -> PhasedExpr<'a> {
@@ -38,7 +38,7 @@ impl<'a> PhasedExpr<'a> {
 
 impl UnspannedExpr {
     // Annotate subexpressions with the appropriate phase, defaulting to Base
-    fn annotate_with_phases(&self) -> ExprKind<PhasedExpr<'_>> {
+    pub(super) fn annotate_with_phases(&self) -> ExprKind<PhasedExpr<'_>> {
         use ExprKind::*;
         use OpKind::*;
         use PrintPhase::*;
@@ -86,15 +86,11 @@ impl UnspannedExpr {
         }
     }
 
-    fn fmt_phase(
-        &self,
-        f: &mut fmt::Formatter,
-        phase: PrintPhase,
-    ) -> Result<(), fmt::Error> {
+    // Whether the expression must be wrapped in parentheses when printed in this phase
+    pub(super) fn needs_paren(&self, phase: PrintPhase) -> bool {
         use ExprKind::*;
         use OpKind::*;
-
-        let needs_paren = match self {
+        match self {
             Lam(_, _, _)
             | Pi(_, _, _)
             | Let(_, _, _, _)
@@ -110,8 +106,15 @@ impl UnspannedExpr {
             Op(App(_, _)) => phase > PrintPhase::App,
             Op(Completion(_, _)) => phase > PrintPhase::Import,
             _ => false,
-        };
+        }
+    }
 
+    fn fmt_phase(
+        &self,
+        f: &mut fmt::Formatter,
+        phase: PrintPhase,
+    ) -> Result<(), fmt::Error> {
+        let needs_paren = self.needs_paren(phase);
         if needs_paren {
             f.write_str("(")?;
         }
@@ -147,6 +150,11 @@ where
 }
 
 fn fmt_label(label: &Label, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    f.write_str(&quote_label(label))
+}
+
+/// The label as written in source, quoted with backticks if needed.
+pub(super) fn quote_label(label: &Label) -> String {
     // TODO: distinguish between reserved and nonreserved locations for quoting builtins
     let s = String::from(label);
     let is_reserved = match s.as_str() {
@@ -154,12 +162,13 @@ fn fmt_label(label: &Label, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         | "True" | "False" | "Some" => true,
         _ => Builtin::parse(&s).is_some(),
     };
-    if s.is_empty() {
-        write!(f, "``")
-    } else if !is_reserved && s.chars().all(|c| c.is_ascii_alphanumeric()) {
-        write!(f, "{}", s)
+    if !s.is_empty()
+        && !is_reserved
+        && s.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        s
     } else {
-        write!(f, "`{}`", s)
+        format!("`{}`", s)
     }
 }
 
@@ -422,9 +431,6 @@ impl Display for Hash {
 
 impl<SubExpr: Display> Display for Import<SubExpr> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        use FilePrefix::*;
-        use ImportMode::*;
-        use ImportTarget::*;
         let quote_if_needed = |s: &str| -> String {
             if s.chars().all(|c| c.is_ascii_alphanumeric()) {
                 s.to_string()
@@ -432,6 +438,20 @@ impl<SubExpr: Display> Display for Import<SubExpr> {
                 format!("\"{}\"", s)
             }
         };
+        self.write_with(f, quote_if_needed)
+    }
+}
+
+impl<SubExpr: Display> Import<SubExpr> {
+    /// Print the import, quoting the components of a local path with `quote_path`.
+    pub(crate) fn write_with(
+        &self,
+        f: &mut impl fmt::Write,
+        quote_path: impl Fn(&str) -> String,
+    ) -> Result<(), fmt::Error> {
+        use FilePrefix::*;
+        use ImportMode::*;
+        use ImportTarget::*;
 
         match &self.location {
             Local(prefix, path) => {
@@ -442,11 +462,8 @@ impl<SubExpr: Display> Display for Import<SubExpr> {
                     Absolute => "",
                 };
                 write!(f, "{}/", prefix)?;
-                let path: String = path
-                    .file_path
-                    .iter()
-                    .map(|c| quote_if_needed(&*c))
-                    .join("/");
+                let path: String =
+                    path.file_path.iter().map(|c| quote_path(&*c)).join("/");
                 f.write_str(&path)?;
             }
             Remote(url) => {
@@ -488,8 +505,7 @@ impl<SubExpr: Display> Display for Import<SubExpr> {
             }
         }
         if let Some(hash) = &self.hash {
-            write!(f, " ")?;
-            hash.fmt(f)?;
+            write!(f, " {}", hash)?;
         }
         match self.mode {
             Code => {}
@@ -468,10 +468,6 @@ fn define_features() -> Vec<TestFeature> {
                 false
                     // TODO: the standard does not respect https://tools.ietf.org/html/rfc3986#section-5.2
                     || path == "unit/asLocation/RemoteCanonicalize4"
-                    // TODO: import headers
-                    || path == "customHeaders"
-                    || path == "headerForwarding"
-                    || path == "noHeaderForwarding"
                     // TODO: git changes newlines on windows
                     || (cfg!(windows) && path == "unit/AsText")
                     || (cfg!(windows) && path == "unit/QuotedPath")
@@ -485,12 +481,9 @@ fn define_features() -> Vec<TestFeature> {
             module_name: "import_failure",
             directory: "import/failure/",
             variant: SpecTestKind::ImportFailure,
-            exclude_path: Rc::new(|path: &str| {
-                false
-                    // TODO: paths on windows have backslashes; this breaks many things
-                    || cfg!(windows)
-                    // TODO: import headers
-                    || path == "customHeadersUsingBoundVariable"
+            exclude_path: Rc::new(|_path: &str| {
+                // TODO: paths on windows have backslashes; this breaks many things
+                cfg!(windows)
             }),
             output_type: FileType::UI,
             ..default_feature.clone()
@@ -649,6 +642,12 @@ fn run_test(test: &SpecTest) -> Result<()> {
             // Round-trip pretty-printer
             let reparsed = Parsed::parse_str(&parsed.to_string())?;
             assert_eq!(reparsed, parsed);
+            // Round-trip the formatter, both when it fits and when it breaks lines
+            for width in &[80, 10] {
+                let pretty = parsed.to_expr().pretty(*width);
+                let reparsed = Parsed::parse_str(&pretty)?;
+                assert_eq!(reparsed, parsed);
+            }
             expected.compare_ui(parsed)?;
         }
         ImportSuccess => {
@@ -0,0 +1,255 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::exit;
+use structopt::StructOpt;
+
+use dhall::error::{ConvertError, Error};
+use dhall::syntax::{binary, Hash};
+use dhall::{FreezeOptions, JsonOptions, Normalized, Parsed};
+
+mod repl;
+
+/// Exit status when the input is not a valid Dhall expression or could not be read.
+const EXIT_FAILURE: i32 = 1;
+/// Exit status when the command line is invalid.
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "dhall",
+    about = "Command-line tool for the Dhall configuration language",
+    after_help = "Exits with status 1 if the input is invalid, and 2 if the arguments are."
+)]
+struct Opts {
+    /// Read the input from this file instead of stdin
+    #[structopt(long, global = true, parse(from_os_str))]
+    file: Option<PathBuf>,
+    /// How to print errors: `human`, or `json` for one diagnostic object per error
+    #[structopt(
+        long,
+        global = true,
+        default_value = "human",
+        possible_values = &["human", "json"]
+    )]
+    error_format: String,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Resolve, typecheck and normalize an expression
+    Normalize,
+    /// Print the type of an expression
+    Type,
+    /// Resolve the imports of an expression
+    Resolve,
+    /// Print the semantic hash of an expression, for use as an import hash
+    Hash,
+    /// Encode an expression to the binary format, without resolving imports
+    Encode,
+    /// Decode an expression from the binary format
+    Decode,
+    /// Format an expression, without resolving imports
+    Format(FormatOpts),
+    /// Add integrity hashes to the remote imports of an expression, and format it
+    Freeze(FreezeOpts),
+    /// Convert an expression to JSON
+    ToJson(ConvertOpts),
+    /// Convert an expression to YAML
+    ToYaml(ConvertOpts),
+    /// Convert JSON to a Dhall expression
+    JsonToDhall(SchemaOpts),
+    /// Convert YAML to a Dhall expression
+    YamlToDhall(SchemaOpts),
+    /// Read expressions and commands interactively. Type `:help` for the list of commands.
+    Repl,
+}
+
+#[derive(Debug, StructOpt)]
+struct FormatOpts {
+    /// The line width to fit the output into where possible
+    #[structopt(long, default_value = "80")]
+    width: usize,
+}
+
+#[derive(Debug, StructOpt)]
+struct FreezeOpts {
+    /// Also freeze local and environment variable imports
+    #[structopt(long)]
+    all: bool,
+    /// Recompute the hashes of imports that already have one
+    #[structopt(long)]
+    refreeze: bool,
+    /// Leave the fallbacks of import alternatives, on the right of `?`, unchanged
+    #[structopt(long)]
+    keep_fallbacks: bool,
+}
+
+impl FreezeOpts {
+    fn to_options(&self) -> FreezeOptions {
+        FreezeOptions {
+            all: self.all,
+            refreeze: self.refreeze,
+            keep_fallbacks: self.keep_fallbacks,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct SchemaOpts {
+    /// The Dhall type of the result, e.g. `./schema.dhall`. Inferred from the input if omitted.
+    #[structopt(long = "type")]
+    ty: Option<String>,
+}
+
+impl SchemaOpts {
+    fn read_type(&self) -> Result<Option<Normalized>, Error> {
+        self.ty
+            .as_ref()
+            .map(|ty| {
+                Ok(Parsed::parse_str(ty)?.resolve()?.typecheck()?.normalize())
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct ConvertOpts {
+    /// Keep record fields that are `None` instead of omitting them
+    #[structopt(long)]
+    preserve_null: bool,
+}
+
+impl ConvertOpts {
+    fn to_options(&self) -> JsonOptions {
+        JsonOptions {
+            preserve_null: self.preserve_null,
+        }
+    }
+}
+
+fn read_input(opts: &Opts) -> Result<Parsed, Error> {
+    match &opts.file {
+        Some(path) => Parsed::parse_file(path),
+        None => Parsed::parse_str(&read_text(opts)?),
+    }
+}
+
+fn read_text(opts: &Opts) -> Result<String, Error> {
+    match &opts.file {
+        Some(path) => Ok(std::fs::read_to_string(path)?),
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+    }
+}
+
+fn read_binary_input(opts: &Opts) -> Result<Parsed, Error> {
+    match &opts.file {
+        Some(path) => Parsed::parse_binary_file(path),
+        None => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            Parsed::parse_binary(&data)
+        }
+    }
+}
+
+fn read_normalized(opts: &Opts) -> Result<Normalized, Error> {
+    Ok(read_input(opts)?.resolve()?.typecheck()?.normalize())
+}
+
+/// Run the command and return what to print on stdout.
+fn run(opts: &Opts) -> Result<Vec<u8>, Error> {
+    let text = match &opts.command {
+        Command::Normalize => read_normalized(opts)?.to_string(),
+        Command::Type => read_input(opts)?
+            .resolve()?
+            .typecheck()?
+            .get_type()?
+            .to_string(),
+        Command::Resolve => read_input(opts)?.resolve()?.to_string(),
+        Command::Hash => {
+            let hash = read_normalized(opts)?.to_expr_alpha().hash()?;
+            Hash::SHA256(hash.into_vec()).to_string()
+        }
+        Command::Encode => {
+            return Ok(binary::encode(&read_input(opts)?.to_expr())?);
+        }
+        Command::Decode => read_binary_input(opts)?.to_string(),
+        Command::Format(format) => {
+            read_input(opts)?.to_expr().pretty(format.width)
+        }
+        Command::Freeze(freeze) => {
+            let text = read_input(opts)?.freeze_source(&freeze.to_options())?;
+            return Ok(text.into_bytes());
+        }
+        Command::ToJson(convert) => {
+            let json = read_normalized(opts)?.to_json(&convert.to_options())?;
+            serde_json::to_string_pretty(&json)
+                .expect("JSON values can always be serialized")
+        }
+        Command::ToYaml(convert) => {
+            let yaml = read_normalized(opts)?.to_yaml(&convert.to_options())?;
+            return Ok(yaml.into_bytes());
+        }
+        Command::JsonToDhall(schema) => {
+            let ty = schema.read_type()?;
+            let json = serde_json::from_str(&read_text(opts)?)
+                .map_err(ConvertError::InvalidJson)?;
+            dhall::json::from_json(&json, ty.as_ref().map(Normalized::as_nir))?
+                .to_string()
+        }
+        Command::YamlToDhall(schema) => {
+            let ty = schema.read_type()?;
+            let yaml = read_text(opts)?;
+            dhall::json::from_yaml(&yaml, ty.as_ref().map(Normalized::as_nir))?
+                .to_string()
+        }
+        Command::Repl => {
+            repl::repl(|err| report(err, &opts.error_format))?;
+            return Ok(Vec::new());
+        }
+    };
+    Ok(format!("{}\n", text).into_bytes())
+}
+
+fn report(err: &Error, error_format: &str) {
+    if error_format == "json" {
+        let diagnostic = serde_json::to_string(&err.to_diagnostic())
+            .expect("diagnostics can always be serialized");
+        eprintln!("{}", diagnostic);
+    } else {
+        eprintln!("{}", err);
+    }
+}
+
+fn main() {
+    let opts = match Opts::from_iter_safe(std::env::args_os()) {
+        Ok(opts) => opts,
+        Err(err) if err.use_stderr() => {
+            eprintln!("{}", err.message);
+            exit(EXIT_USAGE)
+        }
+        // `--help` and `--version`
+        Err(err) => err.exit(),
+    };
+    match run(&opts) {
+        Ok(output) => {
+            let mut stdout = std::io::stdout();
+            if let Err(err) =
+                stdout.write_all(&output).and_then(|()| stdout.flush())
+            {
+                report(&err.into(), &opts.error_format);
+                exit(EXIT_FAILURE)
+            }
+        }
+        Err(err) => {
+            report(&err, &opts.error_format);
+            exit(EXIT_FAILURE)
+        }
+    }
+}
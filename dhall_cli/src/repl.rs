@@ -0,0 +1,182 @@
+//! An interactive loop that evaluates expressions in an environment of named values.
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use dhall::error::Error;
+use dhall::syntax::{Expr, ExprKind, Hash, Label, Span, V};
+use dhall::{Env, Parsed, Typed};
+
+/// The line width of saved files.
+const SAVE_WIDTH: usize = 80;
+
+const HELP: &str = "\
+<expr>                 Print the normal form of an expression
+:let <name> = <expr>   Bind a name to the value of an expression
+:type <expr>           Print the type of an expression
+:hash <expr>           Print the semantic hash of an expression
+:load <file>           Bind the fields of the record in a file
+:save [<file>]         Save the bindings to a file that `:load` can read back
+:help                  Print this message
+:quit                  Exit";
+
+struct Repl {
+    env: Env,
+    /// The bindings, in order, with their normalized values.
+    bindings: Vec<(Label, Expr)>,
+}
+
+impl Repl {
+    fn new() -> Self {
+        Repl {
+            env: Env::new(),
+            bindings: Vec::new(),
+        }
+    }
+
+    fn eval(&self, expr: &str) -> Result<Typed, Error> {
+        let resolved = Parsed::parse_str(expr)?.resolve_in(&self.env)?;
+        Ok(self.env.typecheck(&resolved)?)
+    }
+
+    /// Bind `name` for the following inputs, and return a line describing the binding.
+    fn bind(&mut self, name: Label, typed: &Typed) -> Result<String, Error> {
+        self.env = self.env.insert(&name, typed);
+        let line = format!("{} : {}", as_expr(&name), typed.get_type()?);
+        self.bindings.push((name, typed.normalize().to_expr()));
+        Ok(line)
+    }
+
+    /// Run one line of input, and return what to print.
+    fn run(&mut self, line: &str) -> Result<Option<String>, Error> {
+        if !line.starts_with(':') {
+            return Ok(Some(self.eval(line)?.normalize().to_string()));
+        }
+        let line = &line[1..];
+        let (command, arg) =
+            line.split_at(line.find(char::is_whitespace).unwrap_or(line.len()));
+        let arg = arg.trim();
+        let output = match command {
+            "let" => {
+                let (name, expr) = match arg.find('=') {
+                    Some(i) => (parse_name(&arg[..i])?, &arg[i + 1..]),
+                    None => {
+                        return Err(usage("`:let` expects `<name> = <expr>`"))
+                    }
+                };
+                let typed = self.eval(expr)?;
+                self.bind(name, &typed)?
+            }
+            "type" => self.eval(arg)?.get_type()?.to_string(),
+            "hash" => {
+                let hash =
+                    self.eval(arg)?.normalize().to_expr_alpha().hash()?;
+                Hash::SHA256(hash.into_vec()).to_string()
+            }
+            "load" => {
+                let typed = Parsed::parse_file(Path::new(arg))?
+                    .resolve()?
+                    .typecheck()?;
+                let fields = typed.fields().ok_or_else(|| {
+                    usage("`:load` expects a file that evaluates to a record")
+                })?;
+                let lines = fields
+                    .into_iter()
+                    .map(|(name, field)| self.bind(name, &field))
+                    .collect::<Result<Vec<_>, _>>()?;
+                lines.join("\n")
+            }
+            "save" => {
+                let path = match arg {
+                    "" => unused_path(),
+                    arg => PathBuf::from(arg),
+                };
+                std::fs::write(&path, self.save())?;
+                format!("Saved to {}", path.display())
+            }
+            "help" => HELP.to_string(),
+            "quit" => return Ok(None),
+            _ => {
+                return Err(usage(&format!(
+                    "unknown command `:{}`, see `:help`",
+                    command
+                )))
+            }
+        };
+        Ok(Some(output))
+    }
+
+    /// The bindings as `let`s that evaluate to a record of the names in scope.
+    fn save(&self) -> String {
+        let fields = self
+            .bindings
+            .iter()
+            .map(|(name, _)| (name.clone(), as_expr(name)))
+            .collect();
+        let record = Expr::new(ExprKind::RecordLit(fields), Span::Artificial);
+        let expr =
+            self.bindings
+                .iter()
+                .rev()
+                .fold(record, |body, (name, value)| {
+                    let kind =
+                        ExprKind::Let(name.clone(), None, value.clone(), body);
+                    Expr::new(kind, Span::Artificial)
+                });
+        format!("{}\n", expr.pretty(SAVE_WIDTH))
+    }
+}
+
+fn as_expr(name: &Label) -> Expr {
+    Expr::new(ExprKind::Var(V(name.clone(), 0)), Span::Artificial)
+}
+
+fn parse_name(name: &str) -> Result<Label, Error> {
+    let expr = Parsed::parse_str(name).map(|parsed| parsed.to_expr());
+    match expr.as_ref().map(Expr::kind) {
+        Ok(ExprKind::Var(V(name, 0))) => Ok(name.clone()),
+        _ => Err(usage("`:let` expects a name before `=`")),
+    }
+}
+
+fn usage(message: &str) -> Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message).into()
+}
+
+/// The first of `.dhall-repl-0`, `.dhall-repl-1`, ... that doesn't exist yet.
+fn unused_path() -> PathBuf {
+    (0..)
+        .map(|i| PathBuf::from(format!(".dhall-repl-{}", i)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Read lines from stdin until the end of the input or `:quit`. Errors are reported with
+/// `report` and don't stop the loop.
+pub fn repl(report: impl Fn(&Error)) -> std::io::Result<()> {
+    let stdin = std::io::stdin();
+    let interactive = atty::is(atty::Stream::Stdin);
+    let mut stdout = std::io::stdout();
+    let mut repl = Repl::new();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            write!(stdout, "⊢ ")?;
+            stdout.flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match repl.run(line.trim()) {
+            Ok(Some(output)) => writeln!(stdout, "{}", output)?,
+            Ok(None) => return Ok(()),
+            Err(err) => report(&err),
+        }
+        if interactive {
+            writeln!(stdout)?;
+        }
+    }
+}
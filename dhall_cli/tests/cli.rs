@@ -0,0 +1,204 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn dhall(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dhall"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(args: &[&str], stdin: &str) -> String {
+    let output = dhall(args, stdin.as_bytes());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_commands() {
+    let input = "let x = 1 in { x = x + 1 }";
+    assert_eq!(stdout(&["normalize"], input), "{ x = 2 }\n");
+    assert_eq!(stdout(&["type"], input), "{ x : Natural }\n");
+    assert_eq!(stdout(&["format"], input), "let x = 1 in { x = x + 1 }\n");
+    assert_eq!(
+        stdout(&["format", "--width", "16"], input),
+        "let x = 1\n\nin  { x = x + 1\n    }\n"
+    );
+    assert_eq!(
+        stdout(&["hash"], "1"),
+        "sha256:d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15\n"
+    );
+
+    let encoded = dhall(&["encode"], input.as_bytes()).stdout;
+    let decoded = dhall(&["decode"], &encoded).stdout;
+    assert_eq!(String::from_utf8(decoded).unwrap(), format!("{}\n", input));
+}
+
+#[test]
+fn test_conversions() {
+    let input = "{ a = [ 1, 2 ], b = None Text, c = toMap { k = \"v\" } }";
+    assert_eq!(
+        stdout(&["to-json"], input),
+        "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"c\": {\n    \"k\": \"v\"\n  }\n}\n"
+    );
+    let json = stdout(&["to-json", "--preserve-null"], input);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json["b"], serde_json::Value::Null);
+    assert_eq!(stdout(&["to-yaml"], input), "a:\n- 1\n- 2\nc:\n  k: v\n");
+
+    let output = dhall(&["to-json"], b"\\(x : Bool) -> x");
+    assert_eq!(output.status.code(), Some(1));
+
+    assert_eq!(
+        stdout(&["json-to-dhall"], r#"{ "a": [1, 2], "b": "x" }"#),
+        "{ a = [1, 2], b = \"x\" }\n"
+    );
+    assert_eq!(
+        stdout(
+            &[
+                "yaml-to-dhall",
+                "--type",
+                "{ a : Optional Integer, b : Text }"
+            ],
+            "b: x\n"
+        ),
+        "{ a = None Integer, b = \"x\" }\n"
+    );
+    let output = dhall(&["json-to-dhall"], b"[1, \"x\"]");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_file_input() {
+    let dir = std::env::temp_dir().join("dhall_cli_test_file_input");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.dhall"), "./other.dhall + 1").unwrap();
+    std::fs::write(dir.join("other.dhall"), "41").unwrap();
+    let file = dir.join("main.dhall");
+    let file = file.to_str().unwrap();
+
+    assert_eq!(stdout(&["resolve", "--file", file], ""), "41 + 1\n");
+    assert_eq!(stdout(&["normalize", "--file", file], ""), "42\n");
+}
+
+#[test]
+fn test_errors() {
+    let output = dhall(&["normalize"], b"1 + True");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Type error"));
+    assert!(output.stdout.is_empty());
+
+    let output = dhall(&["type", "--error-format", "json"], b"{ x = ");
+    assert_eq!(output.status.code(), Some(1));
+    let diagnostic: serde_json::Value =
+        serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(diagnostic["code"], "parse");
+    assert_eq!(diagnostic["severity"], "error");
+
+    let output = dhall(&["frobnicate"], b"");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_repl() {
+    let dir = std::env::temp_dir().join("dhall_cli_test_repl");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("defs.dhall"), "{ a = 1, b = \"hi\" }").unwrap();
+    let defs = dir.join("defs.dhall");
+    let saved = dir.join("saved.dhall");
+
+    let input = format!(
+        ":let x = 1\n\
+         :let f = \\(n : Natural) -> n + x\n\
+         f 2\n\
+         :type f\n\
+         :hash x\n\
+         :load {}\n\
+         a + x\n\
+         :let x = x + 10\n\
+         :save {}\n\
+         :quit\n\
+         x\n",
+        defs.display(),
+        saved.display(),
+    );
+    assert_eq!(
+        stdout(&["repl"], &input),
+        format!(
+            "x : Natural\n\
+             f : ∀(n : Natural) → Natural\n\
+             3\n\
+             ∀(n : Natural) → Natural\n\
+             sha256:d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15\n\
+             a : Natural\n\
+             b : Text\n\
+             2\n\
+             x : Natural\n\
+             Saved to {}\n",
+            saved.display()
+        )
+    );
+    let input = format!(":load {}\nx + a\n", saved.display());
+    assert_eq!(
+        stdout(&["repl"], &input),
+        "a : Natural\nb : Text\nf : ∀(n : Natural) → Natural\nx : Natural\n12\n"
+    );
+
+    // Errors are reported without leaving the loop.
+    let output = dhall(&["repl"], b"y\n:let = 1\n:frobnicate\n1 + 1\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unbound variable `y`"));
+    assert!(stderr.contains("`:let` expects a name before `=`"));
+    assert!(stderr.contains("unknown command `:frobnicate`"));
+}
+
+#[test]
+fn test_freeze() {
+    let dir = std::env::temp_dir().join("dhall_cli_test_freeze");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("one.dhall"), "1").unwrap();
+    std::fs::write(
+        dir.join("main.dhall"),
+        "-- Keep me\n./one.dhall ? {- and me -} ./missing.dhall\n",
+    )
+    .unwrap();
+    let file = dir.join("main.dhall");
+    let file = file.to_str().unwrap();
+    let hash =
+        "sha256:d60d8415e36e86dae7f42933d3b0c4fe3ca238f057fba206c7e9fbf5d784fe15";
+
+    // Local imports are only frozen with `--all`.
+    assert_eq!(
+        stdout(&["freeze", "--file", file], ""),
+        "-- Keep me\n./one.dhall ? {- and me -} ./missing.dhall\n"
+    );
+    assert_eq!(
+        stdout(&["freeze", "--all", "--keep-fallbacks", "--file", file], ""),
+        format!(
+            "-- Keep me\n./one.dhall {} ? {{- and me -}} ./missing.dhall\n",
+            hash
+        )
+    );
+    // Existing hashes are replaced.
+    let zero = format!("sha256:{}", "0".repeat(64));
+    assert_eq!(
+        stdout(
+            &["freeze", "--all", "--refreeze", "--keep-fallbacks"],
+            &format!("{}/one.dhall {} ? 2", dir.display(), zero)
+        ),
+        format!("{}/one.dhall {} ? 2", dir.display(), hash)
+    );
+    let output = dhall(&["freeze", "--all", "--file", file], b"");
+    assert_eq!(output.status.code(), Some(1));
+}
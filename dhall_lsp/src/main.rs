@@ -0,0 +1,77 @@
+//! A language server for Dhall, speaking JSON-RPC over stdio.
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::process::exit;
+
+mod server;
+
+use server::Server;
+
+/// Read the body of a message framed with a `Content-Length` header. Returns `None` at the end of
+/// the input.
+fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let prefix = "Content-Length:";
+        if header.starts_with(prefix) {
+            length = header[prefix.len()..].trim().parse().ok();
+        }
+    }
+    let length = length.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        )
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(
+    output: &mut impl Write,
+    message: &Value,
+) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut stdin = stdin.lock();
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    let mut server = Server::default();
+    loop {
+        let body = match read_message(&mut stdin) {
+            Ok(Some(body)) => body,
+            Ok(None) => exit(1),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1)
+            }
+        };
+        let responses = match serde_json::from_slice(&body) {
+            Ok(message) => server.handle(&message),
+            Err(err) => vec![server::parse_error(&err)],
+        };
+        for response in responses {
+            if let Err(err) = write_message(&mut stdout, &response) {
+                eprintln!("{}", err);
+                exit(1)
+            }
+        }
+        if let Some(code) = server.exit_code() {
+            exit(code)
+        }
+    }
+}
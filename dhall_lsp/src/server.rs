@@ -0,0 +1,410 @@
+//! The state of the server and the handling of each LSP method.
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use url::Url;
+
+use dhall::error::{Diagnostic, Error, ErrorKind};
+use dhall::semantics::{DefaultResolver, ImportResolver, RemoteResponse};
+use dhall::syntax::{SourcePosition, SourceRange};
+use dhall::{Definition, HttpOptions, Parsed, ResolveOptions, Resolved};
+
+/// JSON-RPC error code for messages that are not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for requests with missing or invalid parameters.
+const INVALID_PARAMS: i64 = -32602;
+/// The line width used for formatting.
+const FORMAT_WIDTH: usize = 80;
+
+/// An open document and what could be made of it.
+struct Document {
+    text: String,
+    /// The file the document is stored in, for relative imports.
+    path: Option<PathBuf>,
+    parsed: Option<Parsed>,
+    resolved: Option<Resolved>,
+    errors: Vec<Error>,
+}
+
+/// A request for a remote import: the url and the headers to send.
+type Request = (Url, Vec<(String, String)>);
+
+/// Downloads each remote import once, so that the imports of a document are not downloaded
+/// again on every change.
+#[derive(Default)]
+struct CachingResolver {
+    responses: RefCell<HashMap<Request, Result<RemoteResponse, Error>>>,
+}
+
+/// Failed downloads are reported again, as long as their error can be copied.
+fn copy_fetch_error(err: &Error) -> Option<Error> {
+    match err.kind() {
+        ErrorKind::Resolve(err) => Some(err.copy_download_error()?.into()),
+        _ => None,
+    }
+}
+
+impl CachingResolver {
+    /// Forget the failed downloads, so that they are tried again.
+    fn forget_failures(&self) {
+        self.responses
+            .borrow_mut()
+            .retain(|_, response| response.is_ok());
+    }
+}
+
+impl ImportResolver for CachingResolver {
+    fn fetch_remote(
+        &self,
+        url: &Url,
+        headers: &[(String, String)],
+        options: &HttpOptions,
+    ) -> Result<RemoteResponse, Error> {
+        let request = (url.clone(), headers.to_vec());
+        match self.responses.borrow().get(&request) {
+            Some(Ok(response)) => return Ok(response.clone()),
+            Some(Err(err)) => return Err(copy_fetch_error(err).unwrap()),
+            None => {}
+        }
+        let response = DefaultResolver.fetch_remote(url, headers, options);
+        match &response {
+            Ok(ok) => {
+                self.responses.borrow_mut().insert(request, Ok(ok.clone()));
+            }
+            Err(err) => {
+                if let Some(err) = copy_fetch_error(err) {
+                    self.responses.borrow_mut().insert(request, Err(err));
+                }
+            }
+        }
+        response
+    }
+}
+
+/// The response to a message that is not valid JSON.
+pub fn parse_error(err: &serde_json::Error) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": { "code": PARSE_ERROR, "message": err.to_string() },
+    })
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    resolver: CachingResolver,
+    shutdown: bool,
+    exit_code: Option<i32>,
+}
+
+impl Document {
+    fn new(uri: &str, text: String, resolver: &dyn ImportResolver) -> Self {
+        let path = Url::parse(uri).ok().and_then(|url| url.to_file_path().ok());
+        let mut document = Document {
+            text,
+            path,
+            parsed: None,
+            resolved: None,
+            errors: Vec::new(),
+        };
+        if let Err(err) = document.analyze(resolver) {
+            document.errors.push(err);
+        }
+        document
+    }
+
+    fn parse(&self) -> Result<Parsed, Error> {
+        match &self.path {
+            Some(path) => Parsed::parse_str_at(&self.text, path),
+            None => Parsed::parse_str(&self.text),
+        }
+    }
+
+    /// Parse, resolve and typecheck the document, keeping the intermediate results.
+    fn analyze(&mut self, resolver: &dyn ImportResolver) -> Result<(), Error> {
+        let parsed = self.parse()?;
+        self.parsed = Some(parsed.clone());
+        let resolved =
+            parsed.resolve_with(resolver, &ResolveOptions::default())?;
+        self.resolved = Some(resolved.clone());
+        resolved.typecheck()?;
+        Ok(())
+    }
+
+    /// Convert an LSP position, counted in UTF-16 code units from 0, to a source position.
+    fn source_position(&self, position: &Value) -> Option<SourcePosition> {
+        let line = position["line"].as_u64()? as usize;
+        let character = position["character"].as_u64()? as usize;
+        let text = self.text.split('\n').nth(line)?;
+        let mut units = 0;
+        let column = text
+            .chars()
+            .take_while(|c| {
+                units += c.len_utf16();
+                units <= character
+            })
+            .count();
+        Some(SourcePosition {
+            line: line + 1,
+            column: column + 1,
+        })
+    }
+
+    fn lsp_position(&self, position: &SourcePosition) -> Value {
+        let line = position.line.saturating_sub(1);
+        let character: usize = self
+            .text
+            .split('\n')
+            .nth(line)
+            .unwrap_or_default()
+            .chars()
+            .take(position.column.saturating_sub(1))
+            .map(char::len_utf16)
+            .sum();
+        json!({ "line": line, "character": character })
+    }
+
+    fn lsp_range(&self, range: &SourceRange) -> Value {
+        json!({
+            "start": self.lsp_position(&range.start),
+            "end": self.lsp_position(&range.end),
+        })
+    }
+
+    /// Whether `range` points into this document rather than into an import. Ranges have no
+    /// file only in documents that are not stored in a file.
+    fn contains(&self, range: &SourceRange) -> bool {
+        match (&range.file, &self.path) {
+            (Some(file), Some(path)) => *file == path.display().to_string(),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn diagnostic(&self, diagnostic: &Diagnostic) -> Value {
+        // Errors in imported files are shown at the start of the document.
+        let range = match diagnostic.spans.iter().find(|s| s.is_primary) {
+            Some(span) if self.contains(&span.range) => {
+                self.lsp_range(&span.range)
+            }
+            _ => json!({
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 0 },
+            }),
+        };
+        let message = Some(diagnostic.message.clone())
+            .into_iter()
+            .chain(diagnostic.notes.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        json!({
+            "range": range,
+            "severity": 1,
+            "code": diagnostic.code,
+            "source": "dhall",
+            "message": message,
+        })
+    }
+
+    fn diagnostics(&self) -> Value {
+        let diagnostics: Vec<_> = self
+            .errors
+            .iter()
+            .map(|err| self.diagnostic(&err.to_diagnostic()))
+            .collect();
+        json!(diagnostics)
+    }
+
+    fn hover(&self, position: SourcePosition) -> Value {
+        let resolved = match &self.resolved {
+            Some(resolved) => resolved,
+            None => return Value::Null,
+        };
+        match resolved.type_at(position) {
+            Some((range, ty)) => json!({
+                "contents": {
+                    "kind": "markdown",
+                    "value": format!("```dhall\n{}\n```", ty),
+                },
+                "range": self.lsp_range(&range),
+            }),
+            None => Value::Null,
+        }
+    }
+
+    fn definition(&self, uri: &str, position: SourcePosition) -> Value {
+        let parsed = match &self.parsed {
+            Some(parsed) => parsed,
+            None => return Value::Null,
+        };
+        match parsed.definition_at(position) {
+            Some(Definition::Binding(range)) => json!({
+                "uri": uri,
+                "range": self.lsp_range(&range),
+            }),
+            Some(Definition::File(path)) => match Url::from_file_path(&path) {
+                Ok(url) => json!({
+                    "uri": url.as_str(),
+                    "range": {
+                        "start": { "line": 0, "character": 0 },
+                        "end": { "line": 0, "character": 0 },
+                    },
+                }),
+                Err(()) => Value::Null,
+            },
+            None => Value::Null,
+        }
+    }
+
+    fn formatting(&self) -> Value {
+        let parsed = match self.parse() {
+            Ok(parsed) => parsed,
+            Err(_) => return Value::Null,
+        };
+        let mut formatted = parsed.to_expr().pretty(FORMAT_WIDTH);
+        formatted.push('\n');
+        // Replace the whole document.
+        let last_line = self.text.rsplit('\n').next().unwrap_or_default();
+        let end = json!({
+            "line": self.text.matches('\n').count(),
+            "character": last_line.encode_utf16().count(),
+        });
+        json!([{
+            "range": { "start": { "line": 0, "character": 0 }, "end": end },
+            "newText": formatted,
+        }])
+    }
+}
+
+impl Server {
+    /// The status to exit with, once the client asked to exit.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Handle a request or notification, and return the messages to send back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.notify(method, params),
+        };
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    // Full document synchronization
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": {
+                    "name": "dhall-lsp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self
+                .document_position(params)
+                .map(|(_, doc, pos)| doc.hover(pos)),
+            "textDocument/definition" => self
+                .document_position(params)
+                .map(|(uri, doc, pos)| doc.definition(uri, pos)),
+            "textDocument/formatting" => {
+                self.document(params).map(|(_, doc)| doc.formatting())
+            }
+            _ => {
+                Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method)))
+            }
+        };
+        let response = match result {
+            Ok(result) => {
+                json!({ "jsonrpc": "2.0", "id": id, "result": result })
+            }
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        vec![response]
+    }
+
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                self.resolver.forget_failures();
+                let text = params["textDocument"]["text"].as_str();
+                self.open(uri, text.unwrap_or_default())
+            }
+            "textDocument/didChange" => {
+                // With full synchronization, the last change is the whole text.
+                let changes = params["contentChanges"].as_array();
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match text {
+                    Some(text) => self.open(uri, text),
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, json!([]))]
+            }
+            "exit" => {
+                self.exit_code = Some(if self.shutdown { 0 } else { 1 });
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Vec<Value> {
+        let document = Document::new(uri, text.to_string(), &self.resolver);
+        let diagnostics = document.diagnostics();
+        self.documents.insert(uri.to_string(), document);
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    fn document<'a>(
+        &'a self,
+        params: &'a Value,
+    ) -> Result<(&'a str, &'a Document), (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match self.documents.get(uri) {
+            Some(document) => Ok((uri, document)),
+            None => {
+                Err((INVALID_PARAMS, format!("unknown document `{}`", uri)))
+            }
+        }
+    }
+
+    fn document_position<'a>(
+        &'a self,
+        params: &'a Value,
+    ) -> Result<(&'a str, &'a Document, SourcePosition), (i64, String)> {
+        let (uri, document) = self.document(params)?;
+        match document.source_position(&params["position"]) {
+            Some(position) => Ok((uri, document, position)),
+            None => Err((INVALID_PARAMS, "invalid position".to_string())),
+        }
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
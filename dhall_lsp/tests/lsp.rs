@@ -0,0 +1,238 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Run the server on a sequence of messages, and return its exit status and all the messages it
+/// sent back.
+fn session(messages: &[Value]) -> (Option<i32>, Vec<Value>) {
+    let bodies: Vec<_> = messages.iter().map(Value::to_string).collect();
+    session_with_bodies(&bodies)
+}
+
+/// Like `session`, with the raw body of each message.
+fn session_with_bodies(bodies: &[String]) -> (Option<i32>, Vec<Value>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dhall-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for body in bodies {
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+            .unwrap();
+    }
+    drop(stdin);
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut responses = Vec::new();
+    loop {
+        let mut header = String::new();
+        if stdout.read_line(&mut header).unwrap() == 0 {
+            break;
+        }
+        let prefix = "Content-Length: ";
+        assert!(header.starts_with(prefix));
+        let length: usize = header[prefix.len()..].trim_end().parse().unwrap();
+        stdout.read_line(&mut header).unwrap();
+        let mut body = vec![0; length];
+        stdout.read_exact(&mut body).unwrap();
+        responses.push(serde_json::from_slice(&body).unwrap());
+    }
+    (child.wait().unwrap().code(), responses)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn at(uri: &str, line: u64, character: u64) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character },
+    })
+}
+
+/// A new directory for the files of a test, unique to this run.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "dhall-lsp-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn result(responses: &[Value], id: u64) -> &Value {
+    let response = responses.iter().find(|r| r["id"] == id).unwrap();
+    &response["result"]
+}
+
+#[test]
+fn test_session() {
+    let dir = test_dir("session");
+    std::fs::write(dir.join("other.dhall"), "{ y = 2 }").unwrap();
+    let main = dir.join("main.dhall");
+    let uri = url::Url::from_file_path(&main).unwrap().to_string();
+    let other = url::Url::from_file_path(dir.join("other.dhall")).unwrap();
+    let text = "let x = 1\nin  { a = x + (./other.dhall).y,   b = x && True }";
+    let fixed = "let x = 1\nin  { a = x + (./other.dhall).y }";
+
+    let (status, responses) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": uri, "languageId": "dhall", "version": 1, "text": text,
+            }}),
+        ),
+        request(2, "textDocument/hover", at(&uri, 1, 10)),
+        request(3, "textDocument/definition", at(&uri, 1, 10)),
+        request(4, "textDocument/definition", at(&uri, 1, 16)),
+        request(
+            5,
+            "textDocument/formatting",
+            json!({
+                "textDocument": { "uri": uri },
+                "options": { "tabSize": 2, "insertSpaces": true },
+            }),
+        ),
+        request(6, "textDocument/hover", at("file:///unknown.dhall", 0, 0)),
+        request(7, "workspace/symbol", json!({ "query": "" })),
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": fixed }],
+            }),
+        ),
+        request(8, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+    assert_eq!(status, Some(0));
+
+    let capabilities = &result(&responses, 1)["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["definitionProvider"], true);
+    assert_eq!(capabilities["documentFormattingProvider"], true);
+
+    // The type error is reported on the offending operation.
+    let diagnostics: Vec<_> = responses
+        .iter()
+        .filter(|r| r["method"] == "textDocument/publishDiagnostics")
+        .map(|r| &r["params"]["diagnostics"])
+        .collect();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].as_array().unwrap().len(), 1);
+    assert_eq!(
+        diagnostics[0][0]["range"],
+        json!({
+            "start": { "line": 1, "character": 39 },
+            "end": { "line": 1, "character": 48 },
+        })
+    );
+    assert_eq!(diagnostics[1], &json!([]));
+
+    assert_eq!(
+        result(&responses, 2),
+        &json!({
+            "contents": { "kind": "markdown", "value": "```dhall\nNatural\n```" },
+            "range": {
+                "start": { "line": 1, "character": 10 },
+                "end": { "line": 1, "character": 11 },
+            },
+        })
+    );
+    assert_eq!(
+        result(&responses, 3),
+        &json!({
+            "uri": uri,
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 5 },
+            },
+        })
+    );
+    assert_eq!(result(&responses, 4)["uri"], other.as_str());
+
+    let edits = result(&responses, 5);
+    assert_eq!(
+        edits[0]["newText"],
+        "let x = 1 in { a = x + (./other.dhall).y, b = x && True }\n"
+    );
+    assert_eq!(
+        edits[0]["range"]["end"],
+        json!({ "line": 1, "character": 50 })
+    );
+
+    let error = |id: u64| {
+        let response = responses.iter().find(|r| r["id"] == id).unwrap();
+        response["error"]["code"].clone()
+    };
+    assert_eq!(error(6), -32602);
+    assert_eq!(error(7), -32601);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_invalid_json() {
+    let (status, responses) = session_with_bodies(&[
+        "{ not json".to_owned(),
+        request(1, "shutdown", Value::Null).to_string(),
+        notification("exit", Value::Null).to_string(),
+    ]);
+    assert_eq!(status, Some(0));
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(result(&responses, 1), &Value::Null);
+}
+
+#[test]
+fn test_error_in_import() {
+    let dir = test_dir("error-in-import");
+    std::fs::write(dir.join("broken.dhall"), "{ x = 1,\n\n  y =").unwrap();
+    let main = dir.join("main.dhall");
+    let uri = url::Url::from_file_path(&main).unwrap().to_string();
+
+    let (_, responses) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": uri, "languageId": "dhall", "version": 1,
+                "text": "./broken.dhall",
+            }}),
+        ),
+        notification("exit", Value::Null),
+    ]);
+
+    // The parse error is in the imported file, so it is shown at the start of the document.
+    let diagnostics = responses
+        .iter()
+        .find(|r| r["method"] == "textDocument/publishDiagnostics")
+        .map(|r| &r["params"]["diagnostics"])
+        .unwrap();
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 },
+        })
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_exit_without_shutdown() {
+    let (status, responses) = session(&[notification("exit", Value::Null)]);
+    assert_eq!(status, Some(1));
+    assert!(responses.is_empty());
+}
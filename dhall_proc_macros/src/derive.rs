@@ -176,3 +176,176 @@ pub fn derive_static_type_inner(
     };
     Ok(tokens)
 }
+
+pub fn derive_to_dhall(input: TokenStream) -> TokenStream {
+    TokenStream::from(match derive_to_dhall_inner(input) {
+        Ok(tokens) => tokens,
+        Err(err) => err.to_compile_error(),
+    })
+}
+
+fn to_dhall<T>(x: T, ty: proc_macro2::TokenStream) -> proc_macro2::TokenStream
+where
+    T: quote::ToTokens,
+{
+    quote!(
+        ::serde_dhall::ToDhall::to_dhall(#x, #ty)?
+    )
+}
+
+fn derive_to_dhall_for_struct(
+    data: &syn::DataStruct,
+    constraints: &mut Vec<syn::Type>,
+) -> Result<proc_macro2::TokenStream, Error> {
+    let fields: Vec<(String, syn::Member, &syn::Type)> = match &data.fields {
+        syn::Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| {
+                let ident = f.ident.clone().unwrap();
+                (ident.to_string(), syn::Member::Named(ident), &f.ty)
+            })
+            .collect(),
+        syn::Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let name = format!("_{}", i + 1);
+                (name, syn::Member::Unnamed(i.into()), &f.ty)
+            })
+            .collect(),
+        syn::Fields::Unit => vec![],
+    };
+    let entries = fields.into_iter().map(|(name, member, ty)| {
+        constraints.push(ty.clone());
+        let value = to_dhall(
+            quote!(&self.#member),
+            quote!(::serde_dhall::__derive::field_type(ty, #name)),
+        );
+        quote!( (#name, #value) )
+    });
+    Ok(quote! {
+        ::serde_dhall::__derive::make_record(ty, vec![ #(#entries),* ])
+    })
+}
+
+fn derive_to_dhall_for_enum(
+    ident: &syn::Ident,
+    data: &syn::DataEnum,
+    constraints: &mut Vec<syn::Type>,
+) -> Result<proc_macro2::TokenStream, Error> {
+    let arms: Vec<_> = data
+        .variants
+        .iter()
+        .map(|v| {
+            let variant = &v.ident;
+            let name = variant.to_string();
+            match &v.fields {
+                syn::Fields::Unit => Ok(quote! {
+                    #ident::#variant => ::serde_dhall::__derive::make_union(
+                        ty, #name, None
+                    ),
+                }),
+                syn::Fields::Unnamed(fields) if fields.unnamed.is_empty() => {
+                    Ok(quote! {
+                        #ident::#variant() => ::serde_dhall::__derive::make_union(
+                            ty, #name, None
+                        ),
+                    })
+                }
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    let ty = &fields.unnamed.iter().next().unwrap().ty;
+                    constraints.push(ty.clone());
+                    let value = to_dhall(
+                        quote!(x),
+                        quote!(::serde_dhall::__derive::variant_type(
+                            ty, #name
+                        )),
+                    );
+                    Ok(quote! {
+                        #ident::#variant(x) => ::serde_dhall::__derive::make_union(
+                            ty, #name, Some(#value)
+                        ),
+                    })
+                }
+                syn::Fields::Unnamed(_) => Err(Error::new(
+                    v.span(),
+                    "Variants with more than one field are not supported",
+                )),
+                syn::Fields::Named(_) => Err(Error::new(
+                    v.span(),
+                    "Named variants are not supported",
+                )),
+            }
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+pub fn derive_to_dhall_inner(
+    input: TokenStream,
+) -> Result<proc_macro2::TokenStream, Error> {
+    let input: DeriveInput = syn::parse_macro_input::parse(input)?;
+    let ident = &input.ident;
+
+    // List of types that must impl ToDhall
+    let mut constraints = vec![];
+
+    let body = match &input.data {
+        syn::Data::Struct(data) => {
+            derive_to_dhall_for_struct(data, &mut constraints)?
+        }
+        syn::Data::Enum(data) if data.variants.is_empty() => {
+            return Err(Error::new(
+                input.span(),
+                "Empty enums are not supported",
+            ))
+        }
+        syn::Data::Enum(data) => {
+            derive_to_dhall_for_enum(ident, data, &mut constraints)?
+        }
+        syn::Data::Union(x) => {
+            return Err(Error::new(
+                x.union_token.span(),
+                "Unions are not supported",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    generics.make_where_clause();
+    let (impl_generics, ty_generics, orig_where_clause) =
+        generics.split_for_impl();
+
+    // Ensure that all the fields have a ToDhall impl
+    let mut where_clause = orig_where_clause.unwrap().clone();
+    for ty in constraints.iter() {
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::serde_dhall::ToDhall));
+    }
+
+    let tokens = quote! {
+        impl #impl_generics ::serde_dhall::__derive::Sealed
+                for #ident #ty_generics
+                #where_clause {}
+
+        impl #impl_generics ::serde_dhall::ToDhall
+                for #ident #ty_generics
+                #where_clause {
+            fn to_dhall(
+                &self,
+                ty: Option<&::serde_dhall::SimpleType>,
+            ) -> ::serde_dhall::Result<::serde_dhall::Value> {
+                #body
+            }
+        }
+    };
+    Ok(tokens)
+}
@@ -12,3 +12,8 @@ use proc_macro::TokenStream;
 pub fn derive_static_type(input: TokenStream) -> TokenStream {
     derive::derive_static_type(input)
 }
+
+#[proc_macro_derive(ToDhall)]
+pub fn derive_to_dhall(input: TokenStream) -> TokenStream {
+    derive::derive_to_dhall(input)
+}
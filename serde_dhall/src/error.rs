@@ -3,7 +3,7 @@ use dhall::error::Error as DhallError;
 /// Alias for a `Result` with the error type `serde_dhall::Error`.
 pub type Result<T> = std::result::Result<T, Error>;
 
-/// Errors that can occur when deserializing Dhall data.
+/// Errors that can occur when deserializing or serializing Dhall data.
 #[derive(Debug)]
 pub struct Error(pub(crate) ErrorKind);
 
@@ -11,6 +11,7 @@ pub struct Error(pub(crate) ErrorKind);
 pub(crate) enum ErrorKind {
     Dhall(DhallError),
     Deserialize(String),
+    Serialize(String),
 }
 
 impl From<ErrorKind> for Error {
@@ -24,6 +25,7 @@ impl std::fmt::Display for Error {
         match &self.0 {
             ErrorKind::Dhall(err) => write!(f, "{}", err),
             ErrorKind::Deserialize(err) => write!(f, "{}", err),
+            ErrorKind::Serialize(err) => write!(f, "{}", err),
         }
     }
 }
@@ -38,3 +40,12 @@ impl serde::de::Error for Error {
         ErrorKind::Deserialize(msg.to_string()).into()
     }
 }
+
+impl serde::ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: std::fmt::Display,
+    {
+        ErrorKind::Serialize(msg.to_string()).into()
+    }
+}
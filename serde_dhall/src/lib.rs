@@ -12,8 +12,8 @@
 //! YAML. It uses the [Serde][serde] serialization library to provide drop-in support for Dhall
 //! for any datatype that supports serde (and that's a lot of them !).
 //!
-//! This library is limited to deserializing (reading) Dhall values; serializing (writing)
-//! values to Dhall is not supported.
+//! Dhall values can be deserialized (read) into Rust types, and Rust values can be serialized
+//! (written) back to Dhall text.
 //!
 //! # Basic usage
 //!
@@ -156,7 +156,43 @@
 //! If you need more control over the process of reading Dhall values, e.g. disabling
 //! imports, see the [`Deserializer`] methods.
 //!
+//! With the `async` feature, [`from_url_async`] and [`Deserializer::parse_async`] download remote
+//! imports without blocking, and download the imports of each file concurrently.
+//!
+//! # Serialization
+//!
+//! The [`serialize`] function goes the other way: it turns any serde-compatible value into Dhall
+//! text. Since Rust values don't always carry enough information to print a Dhall value (e.g. an
+//! empty list needs its type), you will often want to add a type annotation, as for
+//! deserialization.
+//!
+//! ```rust
+//! # fn main() -> serde_dhall::Result<()> {
+//! use serde::Serialize;
+//! use serde_dhall::StaticType;
+//!
+//! #[derive(Serialize, StaticType)]
+//! struct Point {
+//!     x: u64,
+//!     y: Option<u64>,
+//! }
+//!
+//! let point = Point { x: 1, y: None };
+//! let string = serde_dhall::serialize(&point)
+//!     .static_type_annotation()
+//!     .to_string()?;
+//! assert_eq!(string, "{ x = 1, y = None Natural }");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See the [`Serializer`] methods for more options.
+//!
 //! [`Deserializer`]: struct.Deserializer.html
+//! [`from_url_async`]: fn.from_url_async.html
+//! [`Deserializer::parse_async`]: struct.Deserializer.html#method.parse_async
+//! [`serialize`]: fn.serialize.html
+//! [`Serializer`]: struct.Serializer.html
 //! [dhall]: https://dhall-lang.org/
 //! [serde]: https://docs.serde.rs/serde/
 //! [serde::Deserialize]: https://docs.serde.rs/serde/trait.Deserialize.html
@@ -169,17 +205,30 @@ mod test_readme {
 mod deserialize;
 mod error;
 mod options;
+mod serialize;
 mod static_type;
 /// Dhall values
 mod value;
 
 #[doc(hidden)]
-pub use dhall_proc_macros::StaticType;
+pub use dhall_proc_macros::{StaticType, ToDhall};
+#[doc(hidden)]
+pub use serialize::derive as __derive;
+
+pub use dhall::error::{Error as DhallError, ImportError};
+pub use dhall::semantics::{ImportResolver, RemoteResponse};
+pub use dhall::HttpOptions;
 
 pub use deserialize::FromDhall;
 pub(crate) use deserialize::Sealed;
 pub(crate) use error::ErrorKind;
 pub use error::{Error, Result};
-pub use options::{from_file, from_str, Deserializer};
+#[cfg(feature = "async")]
+pub use options::de::from_url_async;
+pub use options::de::{
+    from_binary, from_file, from_reader, from_str, from_url, Deserializer,
+};
+pub use options::ser::{serialize, to_dhall, Serializer};
+pub use serialize::ToDhall;
 pub use static_type::StaticType;
-pub use value::{SimpleType, Value};
+pub use value::{SimpleType, Value, ValueKind};
@@ -0,0 +1,736 @@
+use std::cell::RefCell;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use dhall::semantics::{DefaultResolver, ImportResolver};
+use dhall::{CacheDir, HttpOptions, Parsed, ResolveOptions, Resolved};
+
+use crate::options::{HasAnnot, ManualAnnot, NoAnnot, StaticAnnot};
+use crate::SimpleType;
+use crate::{Error, ErrorKind, FromDhall, Result, Value};
+
+#[derive(Debug, Clone)]
+enum Source<'a> {
+    Str(&'a str),
+    File(PathBuf),
+    Url(&'a str),
+    Binary(&'a [u8]),
+    Reader(Rc<RefCell<ReaderSource<'a>>>),
+}
+
+/// A reader, read to the end the first time the value is parsed.
+enum ReaderSource<'a> {
+    Unread(Box<dyn Read + 'a>),
+    Read(String),
+    Failed,
+}
+
+impl<'a> std::fmt::Debug for ReaderSource<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReaderSource::Unread(_) => f.write_str("Unread"),
+            ReaderSource::Read(s) => f.debug_tuple("Read").field(s).finish(),
+            ReaderSource::Failed => f.write_str("Failed"),
+        }
+    }
+}
+
+impl<'a> ReaderSource<'a> {
+    /// Reads the text, if it hasn't been read yet.
+    fn read(&mut self) -> std::io::Result<&str> {
+        if let ReaderSource::Unread(reader) = self {
+            let mut text = String::new();
+            let result = reader.read_to_string(&mut text);
+            *self = match result {
+                Ok(_) => ReaderSource::Read(text),
+                Err(_) => ReaderSource::Failed,
+            };
+            result?;
+        }
+        match self {
+            ReaderSource::Read(text) => Ok(text),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "the reader failed in a previous parse",
+            )),
+        }
+    }
+}
+
+/// Controls how a Dhall value is read.
+///
+/// This builder exposes the ability to configure how a value is deserialized and what operations
+/// are permitted during evaluation.
+///
+/// Generally speaking, when using [`Deserializer`], you'll create it with [`from_str`], [`from_file`],
+/// [`from_url`], [`from_reader`] or [`from_binary`], then chain calls to methods to set each
+/// option, then call [`parse`]. This will give you a [`Result<T>`] where `T` is a deserializable
+/// type of your choice.
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`from_str`]: fn.from_str.html
+/// [`from_file`]: fn.from_file.html
+/// [`from_url`]: fn.from_url.html
+/// [`from_reader`]: fn.from_reader.html
+/// [`from_binary`]: fn.from_binary.html
+/// [`parse`]: struct.Deserializer.html#method.parse
+/// [`Result<T>`]: type.Result.html
+///
+/// # Examples
+///
+/// Reading from a file:
+///
+/// ```no_run
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::from_file;
+///
+/// let data = from_file("foo.dhall").parse::<u64>()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Reading from a file and checking the value against a provided type:
+///
+/// ```no_run
+/// # fn main() -> serde_dhall::Result<()> {
+/// use std::collections::HashMap;
+/// use serde_dhall::{from_file, from_str};
+///
+/// let ty = from_str("{ x: Natural, y: Natural }").parse()?;
+/// let data = from_file("foo.dhall")
+///             .type_annotation(&ty)
+///             .parse::<HashMap<String, usize>>()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Deserializer<'a, A> {
+    source: Source<'a>,
+    annot: A,
+    allow_imports: bool,
+    resolve_options: ResolveOptions,
+    resolver: &'a dyn ImportResolver,
+}
+
+impl<'a> Deserializer<'a, NoAnnot> {
+    fn default_with_source(source: Source<'a>) -> Self {
+        Deserializer {
+            source,
+            annot: NoAnnot,
+            allow_imports: true,
+            resolve_options: ResolveOptions::default(),
+            resolver: &DefaultResolver,
+        }
+    }
+    fn from_str(s: &'a str) -> Self {
+        Self::default_with_source(Source::Str(s))
+    }
+    fn from_file<P: AsRef<Path>>(path: P) -> Self {
+        Self::default_with_source(Source::File(path.as_ref().to_owned()))
+    }
+    fn from_url(url: &'a str) -> Self {
+        Self::default_with_source(Source::Url(url))
+    }
+    fn from_binary(data: &'a [u8]) -> Self {
+        Self::default_with_source(Source::Binary(data))
+    }
+    fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        let reader = ReaderSource::Unread(Box::new(reader));
+        Self::default_with_source(Source::Reader(Rc::new(RefCell::new(reader))))
+    }
+
+    /// Ensures that the parsed value matches the provided type.
+    ///
+    /// In many cases the Dhall type that corresponds to a Rust type can be inferred automatically.
+    /// See the [`StaticType`] trait and the [`static_type_annotation`] method for that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use std::collections::HashMap;
+    /// use serde::Deserialize;
+    /// use serde_dhall::{from_str, SimpleType};
+    ///
+    /// // Parse a Dhall type
+    /// let type_str = "{ x: Natural, y: Natural }";
+    /// let ty = from_str(type_str).parse::<SimpleType>()?;
+    ///
+    /// // Parse some Dhall data.
+    /// let data = "{ x = 1, y = 1 + 1 }";
+    /// let point = from_str(data)
+    ///     .type_annotation(&ty)
+    ///     .parse::<HashMap<String, usize>>()?;
+    /// assert_eq!(point.get("y"), Some(&2));
+    ///
+    /// // Invalid data fails the type validation; deserialization would have succeeded otherwise.
+    /// let invalid_data = "{ x = 1, z = 3 }";
+    /// assert!(
+    ///     from_str(invalid_data)
+    ///         .type_annotation(&ty)
+    ///         .parse::<HashMap<String, usize>>()
+    ///         .is_err()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`static_type_annotation`]: struct.Deserializer.html#method.static_type_annotation
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn type_annotation<'ty>(
+        self,
+        ty: &'ty SimpleType,
+    ) -> Deserializer<'a, ManualAnnot<'ty>> {
+        Deserializer {
+            annot: ManualAnnot(ty),
+            source: self.source,
+            allow_imports: self.allow_imports,
+            resolve_options: self.resolve_options,
+            resolver: self.resolver,
+        }
+    }
+
+    /// Ensures that the parsed value matches the type of `T`.
+    ///
+    /// `T` must implement the [`StaticType`] trait. If it doesn't, you can use [`type_annotation`]
+    /// to provide a type manually.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde::Deserialize;
+    /// use serde_dhall::StaticType;
+    ///
+    /// #[derive(Deserialize, StaticType)]
+    /// struct Point {
+    ///     x: u64,
+    ///     y: Option<u64>,
+    /// }
+    ///
+    /// // Some Dhall data
+    /// let data = "{ x = 1, y = Some (1 + 1) }";
+    ///
+    /// // Convert the Dhall string to a Point.
+    /// let point = serde_dhall::from_str(data)
+    ///     .static_type_annotation()
+    ///     .parse::<Point>()?;
+    /// assert_eq!(point.x, 1);
+    /// assert_eq!(point.y, Some(2));
+    ///
+    /// // Invalid data fails the type validation; deserialization would have succeeded otherwise.
+    /// let invalid_data = "{ x = 1 }";
+    /// assert!(
+    ///     serde_dhall::from_str(invalid_data)
+    ///         .static_type_annotation()
+    ///         .parse::<Point>()
+    ///         .is_err()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`type_annotation`]: struct.Deserializer.html#method.type_annotation
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn static_type_annotation(self) -> Deserializer<'a, StaticAnnot> {
+        Deserializer {
+            annot: StaticAnnot,
+            source: self.source,
+            allow_imports: self.allow_imports,
+            resolve_options: self.resolve_options,
+            resolver: self.resolver,
+        }
+    }
+}
+
+impl<'a, A> Deserializer<'a, A> {
+    /// Sets whether to enable imports.
+    ///
+    /// By default, imports are enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde::Deserialize;
+    /// use serde_dhall::SimpleType;
+    ///
+    /// let data = "12 + ./other_file.dhall : Natural";
+    /// assert!(
+    ///     serde_dhall::from_str(data)
+    ///         .imports(false)
+    ///         .parse::<u64>()
+    ///         .is_err()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`static_type_annotation`]: struct.Deserializer.html#method.static_type_annotation
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn imports(self, imports: bool) -> Self {
+        Deserializer {
+            allow_imports: imports,
+            ..self
+        }
+    }
+
+    /// Sets whether to enable remote imports.
+    ///
+    /// By default, remote imports are enabled. Disabling them allows loading untrusted data
+    /// without touching the network. A disabled import fails like a missing one, so it can still
+    /// be caught with the `?` operator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// let data = "https://example.com/answer.dhall ? 42";
+    /// let answer = serde_dhall::from_str(data)
+    ///     .remote_imports(false)
+    ///     .parse::<u64>()?;
+    /// assert_eq!(answer, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remote_imports(self, imports: bool) -> Self {
+        Deserializer {
+            resolve_options: ResolveOptions {
+                remote_imports: imports,
+                ..self.resolve_options
+            },
+            ..self
+        }
+    }
+
+    /// Sets whether to enable imports of environment variables.
+    ///
+    /// By default, environment variable imports are enabled. Disabling them prevents the loaded
+    /// data from reading the environment of the current process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// let data = r#"env:HOME as Text ? "nowhere""#;
+    /// let home = serde_dhall::from_str(data)
+    ///     .env_imports(false)
+    ///     .parse::<String>()?;
+    /// assert_eq!(home, "nowhere");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn env_imports(self, imports: bool) -> Self {
+        Deserializer {
+            resolve_options: ResolveOptions {
+                env_imports: imports,
+                ..self.resolve_options
+            },
+            ..self
+        }
+    }
+
+    /// Sets the directory in which to cache imports protected by a hash.
+    ///
+    /// By default, the cache lives in `$XDG_CACHE_HOME/dhall` (or the platform equivalent), and
+    /// is only used when remote imports are enabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// let data = serde_dhall::from_file("foo.dhall")
+    ///     .cache_dir("/var/cache/dhall")
+    ///     .parse::<u64>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cache_dir<P: AsRef<Path>>(self, path: P) -> Self {
+        Deserializer {
+            resolve_options: ResolveOptions {
+                cache_dir: CacheDir::Path(path.as_ref().to_owned()),
+                ..self.resolve_options
+            },
+            ..self
+        }
+    }
+
+    /// Disables the cache of imports protected by a hash.
+    ///
+    /// See [`cache_dir`] for the default behaviour.
+    ///
+    /// [`cache_dir`]: struct.Deserializer.html#method.cache_dir
+    pub fn no_cache(self) -> Self {
+        Deserializer {
+            resolve_options: ResolveOptions {
+                cache_dir: CacheDir::Disabled,
+                ..self.resolve_options
+            },
+            ..self
+        }
+    }
+
+    /// Sets how remote imports are fetched: timeouts, proxy, additional root certificates, etc.
+    ///
+    /// These options also apply to the value itself when reading from [`from_url`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use std::time::Duration;
+    /// use serde_dhall::HttpOptions;
+    ///
+    /// let options = HttpOptions {
+    ///     timeout: Some(Duration::from_secs(10)),
+    ///     root_certificates: vec![std::fs::read("ca.pem").unwrap()],
+    ///     ..HttpOptions::default()
+    /// };
+    /// let data = serde_dhall::from_url("https://example.com/config.dhall")
+    ///     .http_options(options)
+    ///     .parse::<u64>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`from_url`]: fn.from_url.html
+    pub fn http_options(self, options: HttpOptions) -> Self {
+        Deserializer {
+            resolve_options: ResolveOptions {
+                http: options,
+                ..self.resolve_options
+            },
+            ..self
+        }
+    }
+
+    /// Sets the resolver used to read the contents of imports, and of the file or url passed to
+    /// [`from_file`] or [`from_url`].
+    ///
+    /// By default, imports are read from the filesystem, the network and the environment. See
+    /// [`ImportResolver`] to read them from somewhere else.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use std::collections::HashMap;
+    /// use std::path::{Path, PathBuf};
+    /// use serde_dhall::{DhallError, ImportError, ImportResolver};
+    ///
+    /// struct InMemory(HashMap<PathBuf, String>);
+    ///
+    /// impl ImportResolver for InMemory {
+    ///     fn read_local(&self, path: &Path) -> Result<String, DhallError> {
+    ///         self.0.get(path).cloned().ok_or(ImportError::Missing.into())
+    ///     }
+    /// }
+    ///
+    /// let mut files = HashMap::new();
+    /// files.insert("/etc/answer.dhall".into(), "6 * 7".to_owned());
+    /// let files = InMemory(files);
+    ///
+    /// let answer = serde_dhall::from_str("/etc/answer.dhall")
+    ///     .resolver(&files)
+    ///     .parse::<u64>()?;
+    /// assert_eq!(answer, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ImportResolver`]: trait.ImportResolver.html
+    /// [`from_file`]: fn.from_file.html
+    /// [`from_url`]: fn.from_url.html
+    pub fn resolver(self, resolver: &'a dyn ImportResolver) -> Self {
+        Deserializer { resolver, ..self }
+    }
+
+    fn parse_source(&self) -> dhall::error::Result<Parsed> {
+        Ok(match &self.source {
+            Source::Str(s) => Parsed::parse_str(s)?,
+            Source::File(p) => {
+                Parsed::parse_file_with(p.as_ref(), self.resolver)?
+            }
+            Source::Url(u) => Parsed::parse_remote_with(
+                url::Url::parse(u)?,
+                self.resolver,
+                &self.resolve_options.http,
+            )?,
+            Source::Binary(data) => Parsed::parse_binary(data)?,
+            Source::Reader(reader) => {
+                Parsed::parse_str(reader.borrow_mut().read()?)?
+            }
+        })
+    }
+
+    fn typecheck<T>(&self, resolved: Resolved) -> dhall::error::Result<Value>
+    where
+        T: HasAnnot<A>,
+    {
+        let typed = match &T::get_annot(&self.annot) {
+            None => resolved.typecheck()?,
+            Some(ty) => resolved.typecheck_with(&ty.to_value().to_hir())?,
+        };
+        Ok(Value::from_nir(typed.normalize().as_nir()))
+    }
+
+    fn _parse<T>(&self) -> dhall::error::Result<Value>
+    where
+        T: HasAnnot<A>,
+    {
+        let parsed = self.parse_source()?;
+        let resolved = if self.allow_imports {
+            parsed.resolve_with(self.resolver, &self.resolve_options)?
+        } else {
+            parsed.skip_resolve()?
+        };
+        self.typecheck::<T>(resolved)
+    }
+
+    #[cfg(feature = "async")]
+    async fn _parse_async<T>(&self) -> dhall::error::Result<Value>
+    where
+        T: HasAnnot<A>,
+    {
+        let parsed = match &self.source {
+            Source::Url(u) => {
+                Parsed::parse_remote_async_with(
+                    url::Url::parse(u)?,
+                    self.resolver,
+                    &self.resolve_options.http,
+                )
+                .await?
+            }
+            _ => self.parse_source()?,
+        };
+        let resolved = if self.allow_imports {
+            parsed
+                .resolve_async_with(self.resolver, &self.resolve_options)
+                .await?
+        } else {
+            parsed.skip_resolve()?
+        };
+        self.typecheck::<T>(resolved)
+    }
+
+    /// Parses the chosen dhall value with the options provided.
+    ///
+    /// If you enabled static annotations, `T` is required to implement [`StaticType`].
+    ///
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// let data = serde_dhall::from_str("6 * 7").parse::<u64>()?;
+    /// assert_eq!(data, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: FromDhall + HasAnnot<A>,
+    {
+        let val = self
+            ._parse::<T>()
+            .map_err(ErrorKind::Dhall)
+            .map_err(Error)?;
+        T::from_dhall(&val)
+    }
+
+    /// Like [`parse`], but downloads remote imports without blocking. All the remote imports
+    /// found in a file are downloaded concurrently. This requires the `async` feature.
+    ///
+    /// Local files and environment variables are still read synchronously, using the resolver
+    /// set with [`resolver`] if any. The returned future is not `Send`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> serde_dhall::Result<()> {
+    /// let url = "https://prelude.dhall-lang.org/Bool/not";
+    /// let not: serde_dhall::Value = serde_dhall::from_url(url).parse_async().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`parse`]: struct.Deserializer.html#method.parse
+    /// [`resolver`]: struct.Deserializer.html#method.resolver
+    #[cfg(feature = "async")]
+    pub async fn parse_async<T>(&self) -> Result<T>
+    where
+        T: FromDhall + HasAnnot<A>,
+    {
+        let val = self
+            ._parse_async::<T>()
+            .await
+            .map_err(ErrorKind::Dhall)
+            .map_err(Error)?;
+        T::from_dhall(&val)
+    }
+}
+
+/// Deserialize a value from a string of Dhall text.
+///
+/// This returns a [`Deserializer`] object. Call the [`parse`] method to get the deserialized
+/// value, or use other [`Deserializer`] methods to control the deserialization process.
+///
+/// Imports will be resolved relative to the current directory.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde::Deserialize;
+///
+/// // We use serde's derive feature
+/// #[derive(Deserialize)]
+/// struct Point {
+///     x: u64,
+///     y: u64,
+/// }
+///
+/// // Some Dhall data
+/// let data = "{ x = 1, y = 1 + 1 } : { x: Natural, y: Natural }";
+///
+/// // Parse the Dhall string as a Point.
+/// let point: Point = serde_dhall::from_str(data).parse()?;
+///
+/// assert_eq!(point.x, 1);
+/// assert_eq!(point.y, 2);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`parse`]: struct.Deserializer.html#method.parse
+pub fn from_str(s: &str) -> Deserializer<'_, NoAnnot> {
+    Deserializer::from_str(s)
+}
+
+/// Deserialize a value from a Dhall file.
+///
+/// This returns a [`Deserializer`] object. Call the [`parse`] method to get the deserialized
+/// value, or use other [`Deserializer`] methods to control the deserialization process.
+///
+/// Imports will be resolved relative to the provided file's path.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde::Deserialize;
+///
+/// // We use serde's derive feature
+/// #[derive(Deserialize)]
+/// struct Point {
+///     x: u64,
+///     y: u64,
+/// }
+///
+/// // Parse the Dhall file as a Point.
+/// let point: Point = serde_dhall::from_file("foo.dhall").parse()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`parse`]: struct.Deserializer.html#method.parse
+pub fn from_file<'a, P: AsRef<Path>>(path: P) -> Deserializer<'a, NoAnnot> {
+    Deserializer::from_file(path)
+}
+
+/// Deserialize a value from a remote Dhall file.
+///
+/// This returns a [`Deserializer`] object. Call the [`parse`] method to get the deserialized
+/// value, or use other [`Deserializer`] methods to control the deserialization process.
+///
+/// Relative imports will be resolved relative to the provided URL. This requires the `reqwest`
+/// feature, which is enabled by default.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> serde_dhall::Result<()> {
+/// let url = "https://prelude.dhall-lang.org/Bool/not";
+/// let not: serde_dhall::Value = serde_dhall::from_url(url).parse()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`parse`]: struct.Deserializer.html#method.parse
+pub fn from_url(url: &str) -> Deserializer<'_, NoAnnot> {
+    Deserializer::from_url(url)
+}
+
+/// Deserialize a value from a remote Dhall file, without blocking.
+///
+/// This is a shorthand for `from_url(url).parse_async()`. Use [`from_url`] and
+/// [`parse_async`] to set other options. This requires the `async` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> serde_dhall::Result<()> {
+/// let url = "https://prelude.dhall-lang.org/Bool/not";
+/// let not: serde_dhall::Value = serde_dhall::from_url_async(url).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`from_url`]: fn.from_url.html
+/// [`parse_async`]: struct.Deserializer.html#method.parse_async
+#[cfg(feature = "async")]
+pub async fn from_url_async<T>(url: &str) -> Result<T>
+where
+    T: FromDhall,
+{
+    from_url(url).parse_async().await
+}
+
+/// Deserialize a value from a reader of Dhall text, e.g. stdin.
+///
+/// The reader is read to the end by the first call to [`parse`], which reports any read error.
+/// Imports will be resolved relative to the current directory.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// let reader = std::io::Cursor::new("[1, 2] # [3]");
+/// let data: Vec<u64> = serde_dhall::from_reader(reader).parse()?;
+/// assert_eq!(data, vec![1, 2, 3]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`parse`]: struct.Deserializer.html#method.parse
+pub fn from_reader<'a, R: Read + 'a>(reader: R) -> Deserializer<'a, NoAnnot> {
+    Deserializer::from_reader(reader)
+}
+
+/// Deserialize a value from a Dhall expression in the binary (CBOR) format.
+///
+/// This returns a [`Deserializer`] object. Call the [`parse`] method to get the deserialized
+/// value, or use other [`Deserializer`] methods to control the deserialization process.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// // The encoding of `[1, 2]`
+/// let data = [0x84, 0x04, 0xf6, 0x82, 0x0f, 0x01, 0x82, 0x0f, 0x02];
+/// let list: Vec<u64> = serde_dhall::from_binary(&data).parse()?;
+/// assert_eq!(list, vec![1, 2]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`parse`]: struct.Deserializer.html#method.parse
+pub fn from_binary(data: &[u8]) -> Deserializer<'_, NoAnnot> {
+    Deserializer::from_binary(data)
+}
@@ -0,0 +1,30 @@
+use crate::{SimpleType, StaticType};
+
+pub(crate) mod de;
+pub(crate) mod ser;
+
+#[derive(Debug, Clone, Copy)]
+pub struct NoAnnot;
+#[derive(Debug, Clone, Copy)]
+pub struct ManualAnnot<'ty>(&'ty SimpleType);
+#[derive(Debug, Clone, Copy)]
+pub struct StaticAnnot;
+
+pub trait HasAnnot<A> {
+    fn get_annot(a: &A) -> Option<SimpleType>;
+}
+impl<T> HasAnnot<NoAnnot> for T {
+    fn get_annot(_: &NoAnnot) -> Option<SimpleType> {
+        None
+    }
+}
+impl<'ty, T> HasAnnot<ManualAnnot<'ty>> for T {
+    fn get_annot(a: &ManualAnnot<'ty>) -> Option<SimpleType> {
+        Some(a.0.clone())
+    }
+}
+impl<T: StaticType> HasAnnot<StaticAnnot> for T {
+    fn get_annot(_: &StaticAnnot) -> Option<SimpleType> {
+        Some(T::static_type())
+    }
+}
@@ -0,0 +1,294 @@
+use crate::options::{HasAnnot, ManualAnnot, NoAnnot, StaticAnnot};
+use crate::serialize::serialize_to_simple_value;
+use crate::{Result, SimpleType, ToDhall, Value};
+
+/// Controls how a Dhall value is written.
+///
+/// This builder exposes the ability to configure how a value is serialized, and to set the type
+/// annotation used when serializing.
+///
+/// Generally speaking, when using [`Serializer`], you'll create it with [`serialize`] (or
+/// [`to_dhall`] for [`ToDhall`] types), then chain calls to methods to set each option, then call
+/// [`to_string`]. This will give you a [`Result<String>`] containing the Dhall text.
+///
+/// [`Serializer`]: struct.Serializer.html
+/// [`serialize`]: fn.serialize.html
+/// [`to_dhall`]: fn.to_dhall.html
+/// [`ToDhall`]: trait.ToDhall.html
+/// [`to_string`]: struct.Serializer.html#method.to_string
+/// [`Result<String>`]: type.Result.html
+///
+/// # Examples
+///
+/// Serializing without a type annotation:
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::serialize;
+///
+/// let string = serialize(&1i64).to_string()?;
+/// assert_eq!(string, "+1".to_string());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Serializing with an automatic type annotation:
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::serialize;
+///
+/// let data: Option<u64> = None;
+/// let string = serialize(&data).static_type_annotation().to_string()?;
+/// assert_eq!(string, "None Natural".to_string());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Serializer<'a, T, A> {
+    data: &'a T,
+    annot: A,
+    /// How `data` becomes a `Value`: through serde or through `ToDhall`.
+    convert: fn(&T, Option<&SimpleType>) -> Result<Value>,
+}
+
+impl<'a, T: std::fmt::Debug, A: std::fmt::Debug> std::fmt::Debug
+    for Serializer<'a, T, A>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Serializer")
+            .field("data", &self.data)
+            .field("annot", &self.annot)
+            .finish()
+    }
+}
+
+impl<'a, T> Serializer<'a, T, NoAnnot> {
+    /// Provides a type to the serialization process. The provided value will be checked against
+    /// that type, and the type will be used when Dhall needs it, like for empty lists or for
+    /// unions.
+    ///
+    /// In many cases the Dhall type that corresponds to a Rust type can be inferred automatically.
+    /// See the [`StaticType`] trait and the [`static_type_annotation`] method for that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde::Serialize;
+    /// use serde_dhall::{serialize, from_str, SimpleType};
+    ///
+    /// #[derive(Serialize)]
+    /// enum Choice {
+    ///     A,
+    ///     B(u64),
+    /// }
+    ///
+    /// let ty = from_str("< A | B: Natural >").parse::<SimpleType>()?;
+    ///
+    /// let data = vec![Choice::A, Choice::B(42)];
+    /// let list_ty = SimpleType::List(Box::new(ty));
+    /// let string = serialize(&data)
+    ///     .type_annotation(&list_ty)
+    ///     .to_string()?;
+    /// assert_eq!(
+    ///     string,
+    ///     "[< A | B: Natural >.A, < A | B: Natural >.B 42]".to_string()
+    /// );
+    ///
+    /// // Invalid data fails the type validation; serialization would have succeeded otherwise.
+    /// let list_ty = SimpleType::List(Box::new(SimpleType::Natural));
+    /// assert!(
+    ///     serialize(&data)
+    ///         .type_annotation(&list_ty)
+    ///         .to_string()
+    ///         .is_err()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`static_type_annotation`]: struct.Serializer.html#method.static_type_annotation
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn type_annotation<'ty>(
+        self,
+        ty: &'ty SimpleType,
+    ) -> Serializer<'a, T, ManualAnnot<'ty>> {
+        Serializer {
+            annot: ManualAnnot(ty),
+            data: self.data,
+            convert: self.convert,
+        }
+    }
+
+    /// Uses the type of `T` in the serialization process. This will be used when Dhall needs it,
+    /// like for empty lists or for unions.
+    ///
+    /// `T` must implement the [`StaticType`] trait. If it doesn't, you can use
+    /// [`type_annotation`] to provide a type manually.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde::Serialize;
+    /// use serde_dhall::{serialize, StaticType};
+    ///
+    /// #[derive(Serialize, StaticType)]
+    /// enum Choice {
+    ///     A,
+    ///     B(u64),
+    /// }
+    ///
+    /// let data = Choice::B(42);
+    /// let string = serialize(&data)
+    ///     .static_type_annotation()
+    ///     .to_string()?;
+    /// assert_eq!(string, "< A | B: Natural >.B 42".to_string());
+    ///
+    /// let data: Vec<u64> = Vec::new();
+    /// let string = serialize(&data)
+    ///     .static_type_annotation()
+    ///     .to_string()?;
+    /// assert_eq!(string, "[] : List Natural".to_string());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`type_annotation`]: struct.Serializer.html#method.type_annotation
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn static_type_annotation(self) -> Serializer<'a, T, StaticAnnot> {
+        Serializer {
+            annot: StaticAnnot,
+            data: self.data,
+            convert: self.convert,
+        }
+    }
+}
+
+impl<'a, T, A> Serializer<'a, T, A> {
+    /// Prints the chosen value with the options provided.
+    ///
+    /// If you enabled static annotations, `T` is required to implement [`StaticType`].
+    ///
+    /// Note that if no type annotation was provided, this will fail on values that require a type
+    /// to be printed, like empty lists, `None` or enums.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde_dhall::serialize;
+    ///
+    /// let string = serialize(&vec![1, 2]).to_string()?;
+    /// assert_eq!(string, "[+1, +2]".to_string());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`StaticType`]: trait.StaticType.html
+    pub fn to_string(&self) -> Result<String>
+    where
+        T: HasAnnot<A>,
+    {
+        let val =
+            (self.convert)(self.data, T::get_annot(&self.annot).as_ref())?;
+        Ok(val.to_string())
+    }
+}
+
+/// Serialize a value to a string of Dhall text.
+///
+/// This returns a [`Serializer`] object. Call the [`to_string`] method to get the serialized
+/// value, or use other [`Serializer`] methods to control the serialization process.
+///
+/// In order to process certain values (like unions or empty lists) correctly, it is necessary to
+/// add a type annotation (with [`static_type_annotation`] or [`type_annotation`]).
+///
+/// # Examples
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x: u64,
+///     y: u64,
+/// }
+///
+/// let data = Point { x: 0, y: 0 };
+/// let string = serde_dhall::serialize(&data).to_string()?;
+/// assert_eq!(string, "{ x = 0, y = 0 }".to_string());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde::Serialize;
+/// use serde_dhall::{serialize, StaticType};
+///
+/// #[derive(Serialize, StaticType)]
+/// struct Foo {
+///     x: bool,
+///     y: Vec<u64>,
+/// }
+///
+/// let data = Foo { x: true, y: vec![] };
+/// let string = serialize(&data)
+///     .static_type_annotation()
+///     .to_string()?;
+/// assert_eq!(string, "{ x = True, y = [] : List Natural }".to_string());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Serializer`]: struct.Serializer.html
+/// [`type_annotation`]: struct.Serializer.html#method.type_annotation
+/// [`static_type_annotation`]: struct.Serializer.html#method.static_type_annotation
+/// [`to_string`]: struct.Serializer.html#method.to_string
+pub fn serialize<T>(data: &T) -> Serializer<'_, T, NoAnnot>
+where
+    T: serde::Serialize,
+{
+    Serializer {
+        data,
+        annot: NoAnnot,
+        convert: |data, ty| serialize_to_simple_value(data)?.into_value(ty),
+    }
+}
+
+/// Convert a [`ToDhall`] value to a string of Dhall text.
+///
+/// This is the counterpart of [`serialize`] for types that derive [`ToDhall`] instead of serde's
+/// `Serialize`, and for [`Value`] and [`SimpleType`]. It returns the same [`Serializer`] object.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::{from_str, to_dhall, Value};
+///
+/// let value: Value = from_str("{ x = 1 + 1 }").parse()?;
+/// let string = to_dhall(&value).to_string()?;
+/// assert_eq!(string, "{ x = 2 }".to_string());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ToDhall`]: trait.ToDhall.html
+/// [`serialize`]: fn.serialize.html
+/// [`Serializer`]: struct.Serializer.html
+/// [`Value`]: struct.Value.html
+/// [`SimpleType`]: enum.SimpleType.html
+pub fn to_dhall<T>(data: &T) -> Serializer<'_, T, NoAnnot>
+where
+    T: ToDhall,
+{
+    Serializer {
+        data,
+        annot: NoAnnot,
+        convert: T::to_dhall,
+    }
+}
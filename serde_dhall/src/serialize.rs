@@ -0,0 +1,589 @@
+use serde::ser;
+use std::collections::BTreeMap;
+
+use dhall::syntax::NumKind;
+
+use crate::value::SimpleValue;
+use crate::{Error, ErrorKind, Result, SimpleType, Value};
+use SimpleValue::*;
+
+/// Only the types listed on [`ToDhall`] and those deriving it are meant to implement it.
+///
+/// [`ToDhall`]: trait.ToDhall.html
+pub trait Sealed {}
+
+/// A data structure that can be converted into a Dhall value directly, without going through
+/// serde.
+///
+/// This is implemented for the basic Rust types, for `Option`, `Vec`, slices, tuples, `Result`,
+/// and maps with `String` keys whose contents implement it, and for [`Value`] and
+/// [`SimpleType`]. For your own types, use `#[derive(ToDhall)]`; implementing it by hand is not
+/// supported. Print such values with [`to_dhall`]; types that serde can serialize go through
+/// [`serialize`] instead.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::{to_dhall, StaticType, ToDhall};
+///
+/// // No serde involved here: the value is built directly.
+/// #[derive(ToDhall, StaticType)]
+/// enum Shape {
+///     Point,
+///     Circle(u64),
+/// }
+///
+/// #[derive(ToDhall, StaticType)]
+/// struct Drawing {
+///     shapes: Vec<Shape>,
+///     background: Option<Shape>,
+/// }
+///
+/// let drawing = Drawing {
+///     shapes: vec![Shape::Point],
+///     background: None,
+/// };
+/// let string = to_dhall(&drawing).static_type_annotation().to_string()?;
+/// assert_eq!(
+///     string,
+///     "{ background = None < Circle: Natural | Point >, \
+///      shapes = [< Circle: Natural | Point >.Point] }"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Value`]: struct.Value.html
+/// [`SimpleType`]: enum.SimpleType.html
+/// [`to_dhall`]: fn.to_dhall.html
+/// [`serialize`]: fn.serialize.html
+pub trait ToDhall: Sealed {
+    #[doc(hidden)]
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value>;
+}
+
+macro_rules! to_dhall_builtin {
+    ($($rust_ty:ty),*) => {$(
+        impl Sealed for $rust_ty {}
+        impl ToDhall for $rust_ty {
+            fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+                serialize_to_simple_value(self)?.into_value(ty)
+            }
+        }
+    )*};
+}
+
+to_dhall_builtin!(
+    bool, usize, u64, u32, u16, u8, isize, i64, i32, i16, i8, f64, f32, char,
+    String, str
+);
+
+impl<T: ?Sized + ToDhall> Sealed for &T {}
+impl<T: ?Sized + ToDhall> ToDhall for &T {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        (**self).to_dhall(ty)
+    }
+}
+
+impl<T: ?Sized + ToDhall> Sealed for Box<T> {}
+impl<T: ?Sized + ToDhall> ToDhall for Box<T> {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        (**self).to_dhall(ty)
+    }
+}
+
+impl<T: ToDhall> Sealed for Option<T> {}
+impl<T: ToDhall> ToDhall for Option<T> {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        let payload_ty = match ty {
+            Some(SimpleType::Optional(t)) => Some(&**t),
+            _ => None,
+        };
+        let payload = match self {
+            Some(x) => Some(x.to_dhall(payload_ty)?),
+            None => None,
+        };
+        Value::make_optional(payload, ty)
+    }
+}
+
+impl<T: ToDhall> Sealed for [T] {}
+impl<T: ToDhall> ToDhall for [T] {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        let element_ty = match ty {
+            Some(SimpleType::List(t)) => Some(&**t),
+            _ => None,
+        };
+        let elements = self
+            .iter()
+            .map(|x| x.to_dhall(element_ty))
+            .collect::<Result<_>>()?;
+        Value::make_list(elements, ty)
+    }
+}
+
+impl<T: ToDhall> Sealed for Vec<T> {}
+impl<T: ToDhall> ToDhall for Vec<T> {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        self.as_slice().to_dhall(ty)
+    }
+}
+
+impl<T: ToDhall, E: ToDhall> Sealed for std::result::Result<T, E> {}
+impl<T: ToDhall, E: ToDhall> ToDhall for std::result::Result<T, E> {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        let (variant, payload) = match self {
+            Ok(x) => ("Ok", x.to_dhall(derive::variant_type(ty, "Ok"))?),
+            Err(x) => ("Err", x.to_dhall(derive::variant_type(ty, "Err"))?),
+        };
+        Value::make_union(variant, Some(payload), ty)
+    }
+}
+
+// Maps with string keys become records, like they do with serde.
+macro_rules! to_dhall_map {
+    ($($map:ident),*) => {$(
+        impl<T: ToDhall> Sealed for std::collections::$map<String, T> {}
+        impl<T: ToDhall> ToDhall for std::collections::$map<String, T> {
+            fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+                let fields = self
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok((k.as_str(), v.to_dhall(derive::field_type(ty, k))?))
+                    })
+                    .collect::<Result<_>>()?;
+                Value::make_record(fields, ty)
+            }
+        }
+    )*};
+}
+
+to_dhall_map!(BTreeMap, HashMap);
+
+// Tuples are records with fields `_1`, `_2`, etc., in accordance with `StaticType`.
+macro_rules! to_dhall_tuple {
+    ($(($($ty:ident $index:tt $name:literal),*)),*) => {$(
+        impl<$($ty: ToDhall),*> Sealed for ($($ty,)*) {}
+        impl<$($ty: ToDhall),*> ToDhall for ($($ty,)*) {
+            fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+                let fields = vec![$(
+                    ($name, self.$index.to_dhall(derive::field_type(ty, $name))?)
+                ),*];
+                Value::make_record(fields, ty)
+            }
+        }
+    )*};
+}
+
+to_dhall_tuple!(
+    (A 0 "_1", B 1 "_2"),
+    (A 0 "_1", B 1 "_2", C 2 "_3"),
+    (A 0 "_1", B 1 "_2", C 2 "_3", D 3 "_4")
+);
+
+/// Support functions for the code generated by `#[derive(ToDhall)]`.
+#[doc(hidden)]
+pub mod derive {
+    pub use super::Sealed;
+    use crate::{Result, SimpleType, Value};
+
+    /// The expected type of the field `name` of a record, if known.
+    pub fn field_type<'a>(
+        ty: Option<&'a SimpleType>,
+        name: &str,
+    ) -> Option<&'a SimpleType> {
+        match ty {
+            Some(SimpleType::Record(kts)) => kts.get(name),
+            _ => None,
+        }
+    }
+
+    /// The expected type of the payload of the union variant `name`, if known.
+    pub fn variant_type<'a>(
+        ty: Option<&'a SimpleType>,
+        name: &str,
+    ) -> Option<&'a SimpleType> {
+        match ty {
+            Some(SimpleType::Union(kts)) => kts.get(name)?.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn make_record(
+        ty: Option<&SimpleType>,
+        fields: Vec<(&str, Value)>,
+    ) -> Result<Value> {
+        Value::make_record(fields, ty)
+    }
+
+    pub fn make_union(
+        ty: Option<&SimpleType>,
+        variant: &str,
+        payload: Option<Value>,
+    ) -> Result<Value> {
+        Value::make_union(variant, payload, ty)
+    }
+}
+
+/// Converts any serde-serializable value into a `SimpleValue`.
+pub(crate) fn serialize_to_simple_value<T>(x: &T) -> Result<SimpleValue>
+where
+    T: ser::Serialize + ?Sized,
+{
+    x.serialize(Serializer)
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = TupleSerializer;
+    type SerializeTupleStruct = TupleSerializer;
+    type SerializeTupleVariant = VariantSerializer<TupleSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = VariantSerializer<StructSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Num(NumKind::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        use std::convert::TryInto;
+        match v.try_into() {
+            Ok(v) => Ok(Num(NumKind::Integer(v))),
+            Err(_) => {
+                Err(ser::Error::custom(format!("integer out of range: {}", v)))
+            }
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_u64(u64::from(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_u64(u64::from(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_u64(u64::from(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        use std::convert::TryInto;
+        match v.try_into() {
+            Ok(v) => Ok(Num(NumKind::Natural(v))),
+            Err(_) => {
+                Err(ser::Error::custom(format!("natural out of range: {}", v)))
+            }
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        self.serialize_f64(f64::from(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Num(NumKind::Double(v.into())))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(Text(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Text(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        Err(ErrorKind::Serialize(
+            "unsupported type: byte array; Dhall has no bytes type".to_owned(),
+        )
+        .into())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(Optional(None))
+    }
+    fn serialize_some<T>(self, v: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Ok(Optional(Some(Box::new(v.serialize(self)?))))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Record(Default::default()))
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Union(variant.to_owned(), None))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let value = value.serialize(self)?;
+        Ok(Union(variant.to_owned(), Some(Box::new(value))))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    // Tuples are represented as records with fields `_1`, `_2`, etc., in accordance with
+    // `StaticType`.
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(TupleSerializer::default())
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_tuple(len)?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(MapSerializer::default())
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Ok(StructSerializer::default())
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: StructSerializer::default(),
+        })
+    }
+}
+
+struct SeqSerializer(Vec<SimpleValue>);
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.0.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(List(self.0))
+    }
+}
+
+#[derive(Default)]
+struct TupleSerializer(Vec<SimpleValue>);
+
+impl TupleSerializer {
+    fn into_record(self) -> SimpleValue {
+        Record(
+            self.0
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| (format!("_{}", i + 1), x))
+                .collect(),
+        )
+    }
+}
+
+impl ser::SerializeTuple for TupleSerializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.0.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.into_record())
+    }
+}
+
+impl ser::SerializeTupleStruct for TupleSerializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+#[derive(Default)]
+struct MapSerializer {
+    map: BTreeMap<String, SimpleValue>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        match key.serialize(Serializer)? {
+            Text(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            key => Err(ErrorKind::Serialize(format!(
+                "map keys must be strings, found: {:?}",
+                key
+            ))
+            .into()),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.map.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Record(self.map))
+    }
+}
+
+#[derive(Default)]
+struct StructSerializer(BTreeMap<String, SimpleValue>);
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.0.insert(key.to_owned(), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Record(self.0))
+    }
+}
+
+/// Wraps the payload of a tuple or struct enum variant.
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn wrap(variant: &str, payload: SimpleValue) -> SimpleValue {
+        Union(variant.to_owned(), Some(Box::new(payload)))
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<TupleSerializer> {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        ser::SerializeTuple::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Self::wrap(self.variant, self.inner.into_record()))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<StructSerializer> {
+    type Ok = SimpleValue;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Self::wrap(self.variant, Record(self.inner.0)))
+    }
+}
@@ -2,21 +2,72 @@ use std::collections::{BTreeMap, HashMap};
 
 use dhall::builtins::Builtin;
 use dhall::operations::OpKind;
-use dhall::semantics::{Hir, HirKind, Nir, NirKind};
+use dhall::semantics::{typecheck_with, Hir, HirKind, Nir, NirKind};
 use dhall::syntax::{Expr, ExprKind, NumKind, Span};
 
-use crate::{Error, ErrorKind, FromDhall, Result, Sealed};
+use crate::{Error, ErrorKind, FromDhall, Result, Sealed, ToDhall};
 
-#[doc(hidden)]
 /// An arbitrary Dhall value.
+///
+/// A `Value` can be deserialized from any Dhall expression, which makes it useful for
+/// configurations whose shape isn't known at compile time. It can then be inspected with
+/// [`kind`] or with the various accessors.
+///
+/// [`kind`]: struct.Value.html#method.kind
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> serde_dhall::Result<()> {
+/// use serde_dhall::Value;
+///
+/// let data = "{ servers = [ { name = \"a\", port = 80 } ], debug = True }";
+/// let value: Value = serde_dhall::from_str(data).parse()?;
+///
+/// assert_eq!(value.get("debug").and_then(|v| v.as_bool()), Some(true));
+/// assert_eq!(
+///     value.pointer("servers.0.port").and_then(|v| v.as_natural()),
+///     Some(80)
+/// );
+/// assert!(value.pointer("servers.1").is_none());
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone)]
 pub struct Value {
     /// Invariant: in normal form
-    hir: Hir,
-    /// Cached conversions because they are annoying to construct from Hir.
-    /// At most one of them will be `Some`.
-    as_simple_val: Option<SimpleValue>,
-    as_simple_ty: Option<SimpleType>,
+    nir: Nir,
+}
+
+/// The shape of a Dhall [`Value`], as returned by [`Value::kind`].
+///
+/// Association lists (`List { mapKey : Text, mapValue : T }`) are presented as lists of records.
+///
+/// [`Value`]: struct.Value.html
+/// [`Value::kind`]: struct.Value.html#method.kind
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueKind {
+    /// A `Bool` value
+    Bool(bool),
+    /// A `Natural` value
+    Natural(u64),
+    /// An `Integer` value
+    Integer(i64),
+    /// A `Double` value
+    Double(f64),
+    /// A `Text` value
+    Text(String),
+    /// An `Optional` value, i.e. `Some x` or `None T`
+    Optional(Option<Value>),
+    /// A `List` value
+    List(Vec<Value>),
+    /// A record value, e.g. `{ x = 1, y = True }`
+    Record(BTreeMap<String, Value>),
+    /// A union value: the name of the alternative and its payload if any, e.g. `< A | B : Natural >.B 42`
+    Union(String, Option<Value>),
+    /// A function, a type, or any other value that can't be inspected further. Use its
+    /// `Display` impl to print it.
+    Opaque,
 }
 
 /// A simple value of the kind that can be decoded with serde
@@ -105,30 +156,325 @@ pub enum SimpleType {
 
 impl Value {
     pub(crate) fn from_nir(x: &Nir) -> Self {
-        Value {
-            hir: x.to_hir_noenv(),
-            as_simple_val: SimpleValue::from_nir(x),
-            as_simple_ty: SimpleType::from_nir(x),
-        }
+        Value { nir: x.clone() }
     }
 
-    pub(crate) fn as_hir(&self) -> &Hir {
-        &self.hir
+    pub(crate) fn to_hir(&self) -> Hir {
+        self.nir.to_hir_noenv()
     }
 
     /// Converts a Value into a SimpleValue.
     pub(crate) fn to_simple_value(&self) -> Option<SimpleValue> {
-        self.as_simple_val.clone()
+        SimpleValue::from_nir(&self.nir)
     }
 
     /// Converts a Value into a SimpleType.
     pub(crate) fn to_simple_type(&self) -> Option<SimpleType> {
-        self.as_simple_ty.clone()
+        SimpleType::from_nir(&self.nir)
     }
 
     /// Converts a value back to the corresponding AST expression.
     pub(crate) fn to_expr(&self) -> Expr {
-        self.hir.to_expr(Default::default())
+        self.nir.to_expr(Default::default())
+    }
+
+    /// Returns the shape of this value, giving access to its immediate contents.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde_dhall::{Value, ValueKind};
+    ///
+    /// let value: Value = serde_dhall::from_str("Some [1, 2]").parse()?;
+    /// match value.kind() {
+    ///     ValueKind::Optional(Some(list)) => {
+    ///         assert_eq!(list.as_list().map(|l| l.len()), Some(2))
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    ///
+    /// let value: Value = serde_dhall::from_str("\\(x: Bool) -> x").parse()?;
+    /// assert_eq!(value.kind(), ValueKind::Opaque);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn kind(&self) -> ValueKind {
+        use std::convert::TryInto;
+        let val = |x: &Nir| Value::from_nir(x);
+        match self.nir.kind() {
+            NirKind::Num(NumKind::Bool(b)) => ValueKind::Bool(*b),
+            NirKind::Num(NumKind::Natural(n)) => match (*n).try_into() {
+                Ok(n) => ValueKind::Natural(n),
+                Err(_) => ValueKind::Opaque,
+            },
+            NirKind::Num(NumKind::Integer(n)) => match (*n).try_into() {
+                Ok(n) => ValueKind::Integer(n),
+                Err(_) => ValueKind::Opaque,
+            },
+            NirKind::Num(NumKind::Double(d)) => ValueKind::Double((*d).into()),
+            NirKind::TextLit(t) => match t.as_text() {
+                Some(t) => ValueKind::Text(t),
+                None => ValueKind::Opaque,
+            },
+            NirKind::EmptyOptionalLit(_) => ValueKind::Optional(None),
+            NirKind::NEOptionalLit(x) => ValueKind::Optional(Some(val(x))),
+            NirKind::EmptyListLit(_) => ValueKind::List(vec![]),
+            NirKind::NEListLit(xs) => {
+                ValueKind::List(xs.iter().map(val).collect())
+            }
+            NirKind::RecordLit(kvs) => ValueKind::Record(
+                kvs.iter().map(|(k, v)| (k.to_string(), val(v))).collect(),
+            ),
+            NirKind::UnionConstructor(field, _) => {
+                ValueKind::Union(field.to_string(), None)
+            }
+            NirKind::UnionLit(field, x, _) => {
+                ValueKind::Union(field.to_string(), Some(val(x)))
+            }
+            _ => ValueKind::Opaque,
+        }
+    }
+
+    /// Returns the field `name` of a record value, or `None` if this isn't a record or the
+    /// field is missing.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.nir.kind() {
+            NirKind::RecordLit(kvs) => kvs.get(name).map(Value::from_nir),
+            _ => None,
+        }
+    }
+
+    /// Looks up a nested value by following a path of dot-separated record fields and list
+    /// indices, e.g. `"servers.0.port"`. The empty path returns the value itself.
+    pub fn pointer(&self, path: &str) -> Option<Value> {
+        if path.is_empty() {
+            return Some(self.clone());
+        }
+        path.split('.').try_fold(self.clone(), |val, segment| {
+            match val.nir.kind() {
+                NirKind::RecordLit(_) => val.get(segment),
+                NirKind::NEListLit(xs) => {
+                    let i: usize = segment.parse().ok()?;
+                    xs.get(i).map(Value::from_nir)
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns the value if it is a `Bool`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.kind() {
+            ValueKind::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Natural`.
+    pub fn as_natural(&self) -> Option<u64> {
+        match self.kind() {
+            ValueKind::Natural(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is an `Integer`.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self.kind() {
+            ValueKind::Integer(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Double`.
+    pub fn as_double(&self) -> Option<f64> {
+        match self.kind() {
+            ValueKind::Double(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Text`.
+    pub fn as_text(&self) -> Option<String> {
+        match self.kind() {
+            ValueKind::Text(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of an `Optional` value. The outer `Option` is `None` if this isn't an
+    /// `Optional`.
+    pub fn as_optional(&self) -> Option<Option<Value>> {
+        match self.kind() {
+            ValueKind::Optional(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `List` value.
+    pub fn as_list(&self) -> Option<Vec<Value>> {
+        match self.kind() {
+            ValueKind::List(xs) => Some(xs),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a record value.
+    pub fn as_record(&self) -> Option<BTreeMap<String, Value>> {
+        match self.kind() {
+            ValueKind::Record(kvs) => Some(kvs),
+            _ => None,
+        }
+    }
+
+    /// Returns the alternative and payload of a union value.
+    pub fn as_union(&self) -> Option<(String, Option<Value>)> {
+        match self.kind() {
+            ValueKind::Union(field, x) => Some((field, x)),
+            _ => None,
+        }
+    }
+
+    /// Builds a record out of its fields, checking it against the provided type if any.
+    pub(crate) fn make_record(
+        fields: Vec<(&str, Value)>,
+        ty: Option<&SimpleType>,
+    ) -> Result<Value> {
+        match ty {
+            None => {}
+            Some(SimpleType::Record(kts))
+                if kts.len() == fields.len()
+                    && fields.iter().all(|(k, _)| kts.contains_key(*k)) => {}
+            Some(ty) => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "this record does not match the type annotation: \
+                     expected `{}`",
+                    ty
+                ))))
+            }
+        }
+        let nir = Nir::from_kind(NirKind::RecordLit(
+            fields.into_iter().map(|(k, v)| (k.into(), v.nir)).collect(),
+        ));
+        Ok(Value { nir })
+    }
+
+    /// Builds a union value. The type is required to know the other alternatives of the union.
+    pub(crate) fn make_union(
+        variant: &str,
+        payload: Option<Value>,
+        ty: Option<&SimpleType>,
+    ) -> Result<Value> {
+        let kts = match ty {
+            Some(SimpleType::Union(kts)) => kts,
+            Some(ty) => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "the `{}` variant does not match the type annotation: \
+                     expected `{}`",
+                    variant, ty
+                ))))
+            }
+            None => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "the `{}` variant cannot be serialized without a type \
+                     annotation",
+                    variant
+                ))))
+            }
+        };
+        let union_ty =
+            SimpleType::Union(kts.clone()).to_hir().eval_closed_expr();
+        let alternatives = match union_ty.kind() {
+            NirKind::UnionType(alternatives) => alternatives.clone(),
+            _ => unreachable!("A union type should evaluate to a union type"),
+        };
+        let kind = match (payload, kts.get(variant)) {
+            (None, Some(None)) => {
+                NirKind::UnionConstructor(variant.into(), alternatives)
+            }
+            (Some(x), Some(Some(_))) => {
+                NirKind::UnionLit(variant.into(), x.nir, alternatives)
+            }
+            _ => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "the `{}` variant does not match the type annotation: \
+                     expected `{}`",
+                    variant,
+                    ty.unwrap()
+                ))))
+            }
+        };
+        Ok(Value {
+            nir: Nir::from_kind(kind),
+        })
+    }
+
+    /// Builds an optional value, checking it against the provided type if any. The type is
+    /// required for `None`.
+    pub(crate) fn make_optional(
+        payload: Option<Value>,
+        ty: Option<&SimpleType>,
+    ) -> Result<Value> {
+        let kind = match (payload, ty) {
+            (Some(x), None) | (Some(x), Some(SimpleType::Optional(_))) => {
+                NirKind::NEOptionalLit(x.nir)
+            }
+            (None, Some(SimpleType::Optional(t))) => {
+                NirKind::EmptyOptionalLit(t.to_hir().eval_closed_expr())
+            }
+            (None, None) => {
+                return Err(Error(ErrorKind::Serialize(
+                    "`None` cannot be serialized without a type annotation"
+                        .to_owned(),
+                )))
+            }
+            (_, Some(ty)) => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "this optional value does not match the type annotation: \
+                     expected `{}`",
+                    ty
+                ))))
+            }
+        };
+        Ok(Value {
+            nir: Nir::from_kind(kind),
+        })
+    }
+
+    /// Builds a list out of its elements, checking it against the provided type if any. The type
+    /// is required for empty lists.
+    pub(crate) fn make_list(
+        elements: Vec<Value>,
+        ty: Option<&SimpleType>,
+    ) -> Result<Value> {
+        let kind = match ty {
+            None | Some(SimpleType::List(_)) if !elements.is_empty() => {
+                NirKind::NEListLit(
+                    elements.into_iter().map(|x| x.nir).collect(),
+                )
+            }
+            Some(SimpleType::List(t)) => {
+                NirKind::EmptyListLit(t.to_hir().eval_closed_expr())
+            }
+            None => {
+                return Err(Error(ErrorKind::Serialize(
+                    "an empty list cannot be serialized without a type \
+                     annotation"
+                        .to_owned(),
+                )))
+            }
+            Some(ty) => {
+                return Err(Error(ErrorKind::Serialize(format!(
+                    "this list does not match the type annotation: \
+                     expected `{}`",
+                    ty
+                ))))
+            }
+        };
+        Ok(Value {
+            nir: Nir::from_kind(kind),
+        })
     }
 }
 
@@ -213,6 +559,155 @@ impl SimpleValue {
             _ => return None,
         })
     }
+
+    /// Converts a SimpleValue into a Value, checking it against the provided type if any.
+    pub(crate) fn into_value(self, ty: Option<&SimpleType>) -> Result<Value> {
+        Ok(Value {
+            nir: self.to_hir(ty)?.eval_closed_expr(),
+        })
+    }
+
+    /// Converts to a Hir, using the type to annotate the values that need it, like empty lists
+    /// or union constructors.
+    fn to_hir(&self, ty: Option<&SimpleType>) -> Result<Hir> {
+        use std::convert::TryInto;
+        use SimpleType as T;
+        use SimpleValue as V;
+        let hir = |k| Hir::new(HirKind::Expr(k), Span::Artificial);
+        let type_error = || {
+            Error(ErrorKind::Serialize(match ty {
+                Some(ty) => format!(
+                    "this value does not match the type annotation: \
+                     expected `{}`, found {:?}",
+                    ty, self
+                ),
+                None => format!(
+                    "this value cannot be serialized without a type \
+                     annotation: {:?}",
+                    self
+                ),
+            }))
+        };
+        let kind = match (self, ty) {
+            (V::Num(num), _) => ExprKind::Num(match (num, ty) {
+                (num, None)
+                | (num @ NumKind::Bool(_), Some(T::Bool))
+                | (num @ NumKind::Natural(_), Some(T::Natural))
+                | (num @ NumKind::Integer(_), Some(T::Integer))
+                | (num @ NumKind::Double(_), Some(T::Double)) => num.clone(),
+                // Allow numbers to be serialized to the other numeric type when it makes sense.
+                (NumKind::Natural(n), Some(T::Integer)) => {
+                    NumKind::Integer((*n).try_into().map_err(|_| type_error())?)
+                }
+                (NumKind::Integer(n), Some(T::Natural)) => {
+                    NumKind::Natural((*n).try_into().map_err(|_| type_error())?)
+                }
+                _ => return Err(type_error()),
+            }),
+            (V::Text(t), None) | (V::Text(t), Some(T::Text)) => {
+                ExprKind::TextLit(t.clone().into())
+            }
+            (V::Optional(None), Some(T::Optional(t))) => {
+                ExprKind::Op(OpKind::App(
+                    hir(ExprKind::Builtin(Builtin::OptionalNone)),
+                    t.to_hir(),
+                ))
+            }
+            (V::Optional(Some(x)), None) => ExprKind::SomeLit(x.to_hir(None)?),
+            (V::Optional(Some(x)), Some(T::Optional(t))) => {
+                ExprKind::SomeLit(x.to_hir(Some(t))?)
+            }
+            (V::List(xs), Some(T::List(t))) if xs.is_empty() => {
+                ExprKind::EmptyListLit(hir(ExprKind::Op(OpKind::App(
+                    hir(ExprKind::Builtin(Builtin::List)),
+                    t.to_hir(),
+                ))))
+            }
+            (V::List(xs), None) if !xs.is_empty() => ExprKind::NEListLit(
+                xs.iter().map(|x| x.to_hir(None)).collect::<Result<_>>()?,
+            ),
+            (V::List(xs), Some(T::List(t))) => ExprKind::NEListLit(
+                xs.iter()
+                    .map(|x| x.to_hir(Some(t)))
+                    .collect::<Result<_>>()?,
+            ),
+            (V::Record(kvs), None) => ExprKind::RecordLit(
+                kvs.iter()
+                    .map(|(k, v)| Ok((k.as_str().into(), v.to_hir(None)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            (V::Record(kvs), Some(T::Record(kts))) => {
+                if kvs.keys().any(|k| !kts.contains_key(k)) {
+                    return Err(type_error());
+                }
+                ExprKind::RecordLit(
+                    kts.iter()
+                        .map(|(k, t)| {
+                            let v = match (kvs.get(k), t) {
+                                (Some(v), _) => v.to_hir(Some(t))?,
+                                // Missing optional fields default to `None`.
+                                (None, T::Optional(_)) => {
+                                    V::Optional(None).to_hir(Some(t))?
+                                }
+                                (None, _) => return Err(type_error()),
+                            };
+                            Ok((k.as_str().into(), v))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            // Records with a map type are serialized to association lists.
+            (V::Record(kvs), Some(T::List(t))) => {
+                let entry_ty = match &**t {
+                    T::Record(kts)
+                        if kts.len() == 2
+                            && kts.get("mapKey") == Some(&T::Text) =>
+                    {
+                        match kts.get("mapValue") {
+                            Some(t) => t,
+                            None => return Err(type_error()),
+                        }
+                    }
+                    _ => return Err(type_error()),
+                };
+                if kvs.is_empty() {
+                    return V::List(vec![]).to_hir(ty);
+                }
+                ExprKind::NEListLit(
+                    kvs.iter()
+                        .map(|(k, v)| {
+                            let mut entry = BTreeMap::new();
+                            entry.insert(
+                                "mapKey".into(),
+                                hir(ExprKind::TextLit(k.clone().into())),
+                            );
+                            entry.insert(
+                                "mapValue".into(),
+                                v.to_hir(Some(entry_ty))?,
+                            );
+                            Ok(hir(ExprKind::RecordLit(entry)))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            (V::Union(variant, x), Some(T::Union(kts))) => {
+                let constructor = hir(ExprKind::Op(OpKind::Field(
+                    T::Union(kts.clone()).to_hir(),
+                    variant.as_str().into(),
+                )));
+                match (x, kts.get(variant)) {
+                    (None, Some(None)) => return Ok(constructor),
+                    (Some(x), Some(Some(t))) => ExprKind::Op(OpKind::App(
+                        constructor,
+                        x.to_hir(Some(t))?,
+                    )),
+                    _ => return Err(type_error()),
+                }
+            }
+            _ => return Err(type_error()),
+        };
+        Ok(hir(kind))
+    }
 }
 
 impl SimpleType {
@@ -242,9 +737,10 @@ impl SimpleType {
                     .map(|(k, v)| {
                         Some((
                             k.into(),
-                            v.as_ref()
-                                .map(|v| Ok(Self::from_nir(v)?))
-                                .transpose()?,
+                            match v {
+                                Some(v) => Some(Self::from_nir(v)?),
+                                None => None,
+                            },
                         ))
                     })
                     .collect::<Option<_>>()?,
@@ -255,9 +751,7 @@ impl SimpleType {
 
     pub(crate) fn to_value(&self) -> Value {
         Value {
-            hir: self.to_hir(),
-            as_simple_val: None,
-            as_simple_ty: Some(self.clone()),
+            nir: self.to_hir().eval_closed_expr(),
         }
     }
     pub(crate) fn to_hir(&self) -> Hir {
@@ -295,6 +789,43 @@ impl SimpleType {
     pub(crate) fn to_expr(&self) -> Expr {
         self.to_hir().to_expr(Default::default())
     }
+
+    /// Converts a JSON value to a Dhall expression of this type, following the conventions of
+    /// [`dhall::json::from_json`].
+    ///
+    /// [`dhall::json::from_json`]: ../dhall/json/fn.from_json.html
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> serde_dhall::Result<()> {
+    /// use serde_dhall::SimpleType;
+    ///
+    /// let ty: SimpleType =
+    ///     serde_dhall::from_str("{ name : Text, port : Optional Natural }").parse()?;
+    /// let json = serde_json::json!({ "name": "localhost" });
+    ///
+    /// let expr = ty.json_to_expr(&json)?;
+    /// assert_eq!(expr.to_string(), r#"{ name = "localhost", port = None Natural }"#);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json_to_expr(&self, json: &serde_json::Value) -> Result<Expr> {
+        let ty = self.to_hir().eval_closed_expr();
+        dhall::json::from_json(json, Some(&ty))
+            .map_err(|e| Error(ErrorKind::Dhall(e)))
+    }
+
+    /// Converts a YAML document to a Dhall expression of this type, like [`json_to_expr`].
+    ///
+    /// [`json_to_expr`]: enum.SimpleType.html#method.json_to_expr
+    #[cfg(feature = "yaml")]
+    pub fn yaml_to_expr(&self, yaml: &str) -> Result<Expr> {
+        let ty = self.to_hir().eval_closed_expr();
+        dhall::json::from_yaml(yaml, Some(&ty))
+            .map_err(|e| Error(ErrorKind::Dhall(e)))
+    }
 }
 
 impl Sealed for Value {}
@@ -327,10 +858,34 @@ impl FromDhall for SimpleType {
     }
 }
 
+impl crate::serialize::Sealed for Value {}
+impl crate::serialize::Sealed for SimpleType {}
+
+impl ToDhall for Value {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        if let Some(ty) = ty {
+            typecheck_with(&self.to_hir(), &ty.to_hir())
+                .map_err(|e| Error(ErrorKind::Dhall(e.into())))?;
+        }
+        Ok(self.clone())
+    }
+}
+impl ToDhall for SimpleType {
+    fn to_dhall(&self, ty: Option<&SimpleType>) -> Result<Value> {
+        match ty {
+            None => Ok(self.to_value()),
+            Some(ty) => Err(Error(ErrorKind::Serialize(format!(
+                "a type cannot match the type annotation `{}`",
+                ty
+            )))),
+        }
+    }
+}
+
 impl Eq for Value {}
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        self.hir == other.hir
+        self.nir == other.nir
     }
 }
 impl std::fmt::Display for Value {
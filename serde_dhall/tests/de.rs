@@ -116,5 +116,198 @@ fn test_de_untyped() {
     assert!(from_str("List/length [True, 42]").parse::<bool>().is_err());
 }
 
+#[test]
+fn test_de_sources() {
+    use serde_dhall::{from_binary, from_reader};
+
+    struct FailingReader;
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "oops"))
+        }
+    }
+
+    let reader = std::io::Cursor::new("{ x = 1 }.x");
+    let deserializer = from_reader(reader);
+    assert_eq!(deserializer.parse::<u64>().unwrap(), 1);
+    // The text is kept for later calls
+    assert_eq!(deserializer.parse::<u64>().unwrap(), 1);
+    let err = from_reader(FailingReader).parse::<u64>().unwrap_err();
+    assert_eq!(err.to_string(), "oops");
+
+    // The encoding of `True`
+    assert!(from_binary(&[0xf5]).parse::<bool>().unwrap());
+    assert!(from_binary(&[0xff, 0x00]).parse::<bool>().is_err());
+}
+
+#[test]
+fn test_de_import_options() {
+    let parse = |s| from_str(s).remote_imports(false).env_imports(false);
+
+    assert!(parse("https://example.com/foo.dhall")
+        .parse::<u64>()
+        .is_err());
+    assert!(parse("env:HOME as Text").parse::<String>().is_err());
+    assert!(parse("env:HOME as Location")
+        .parse::<serde_dhall::Value>()
+        .is_ok());
+    assert_eq!(
+        parse("https://example.com/foo.dhall ? env:FOO ? 1")
+            .parse::<u64>()
+            .unwrap(),
+        1
+    );
+    assert_eq!(from_str("1").no_cache().parse::<u64>().unwrap(), 1);
+}
+
+#[test]
+fn test_de_resolver() {
+    use serde_dhall::{DhallError, ImportError, ImportResolver};
+
+    struct Env;
+    impl ImportResolver for Env {
+        fn read_env(&self, name: &str) -> Result<String, DhallError> {
+            match name {
+                "FOO" => Ok("1".to_owned()),
+                _ => Err(ImportError::MissingEnvVar.into()),
+            }
+        }
+    }
+
+    let parse = |s| from_str(s).resolver(&Env).parse::<u64>();
+    assert_eq!(parse("env:FOO + 1").unwrap(), 2);
+    assert!(parse("env:BAR").is_err());
+    assert!(from_str("env:FOO")
+        .resolver(&Env)
+        .env_imports(false)
+        .parse::<u64>()
+        .is_err());
+}
+
+#[test]
+fn test_de_resolver_reads_root() {
+    use serde_dhall::{
+        from_file, from_url, DhallError, HttpOptions, ImportError,
+        ImportResolver, RemoteResponse,
+    };
+    use std::path::Path;
+    use url::Url;
+
+    struct InMemory;
+    impl ImportResolver for InMemory {
+        fn read_local(&self, path: &Path) -> Result<String, DhallError> {
+            match path.to_str() {
+                Some("/etc/main.dhall") => Ok("./answer.dhall + 1".to_owned()),
+                Some("/etc/answer.dhall") => Ok("41".to_owned()),
+                _ => Err(ImportError::Missing.into()),
+            }
+        }
+        fn fetch_remote(
+            &self,
+            url: &Url,
+            _: &[(String, String)],
+            _: &HttpOptions,
+        ) -> Result<RemoteResponse, DhallError> {
+            match url.as_str() {
+                "https://example.com/main.dhall" => Ok(RemoteResponse {
+                    text: "2 + 2".to_owned(),
+                    ..RemoteResponse::default()
+                }),
+                _ => Err(ImportError::Missing.into()),
+            }
+        }
+    }
+
+    let parsed = from_file("/etc/main.dhall")
+        .resolver(&InMemory)
+        .parse::<u64>();
+    assert_eq!(parsed.unwrap(), 42);
+    let parsed = from_url("https://example.com/main.dhall")
+        .resolver(&InMemory)
+        .parse::<u64>();
+    assert_eq!(parsed.unwrap(), 4);
+}
+
+/// Serve the files in `files` over HTTP on a local port, and return the base url to reach them.
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    any(feature = "reqwest", feature = "async")
+))]
+fn serve_http(files: &'static [(&'static str, &'static str)]) -> String {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split(' ').nth(1).unwrap_or("");
+            let response = match files.iter().find(|(p, _)| *p == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n"
+                    .to_owned(),
+            };
+            // The client may have given up already
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+#[test]
+fn test_de_url() {
+    use serde_dhall::from_url;
+
+    let base = serve_http(&[
+        ("/main.dhall", "./answer.dhall + 1"),
+        ("/answer.dhall", "41"),
+    ]);
+    let parsed = from_url(&format!("{}/main.dhall", base)).parse::<u64>();
+    assert_eq!(parsed.unwrap(), 42);
+    assert!(from_url(&format!("{}/missing.dhall", base))
+        .parse::<u64>()
+        .is_err());
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "async"))]
+#[tokio::test]
+async fn test_de_async() {
+    use serde_dhall::from_url_async;
+
+    let parsed = from_str("let x = 1 in x + 1").parse_async::<u64>().await;
+    assert_eq!(parsed.unwrap(), 2);
+    assert!(from_str("http://127.0.0.1:1/missing ? 3")
+        .remote_imports(false)
+        .parse_async::<u64>()
+        .await
+        .is_ok());
+    assert!(from_url_async::<u64>("http://127.0.0.1:1/missing")
+        .await
+        .is_err());
+
+    let base = serve_http(&[
+        ("/main.dhall", "./answer.dhall + 1"),
+        ("/answer.dhall", "41"),
+    ]);
+    let parsed = from_url_async::<u64>(&format!("{}/main.dhall", base)).await;
+    assert_eq!(parsed.unwrap(), 42);
+}
+
 // TODO: test various builder configurations
 // In particular test cloning and reusing builder
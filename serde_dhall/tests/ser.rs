@@ -0,0 +1,210 @@
+use serde::Serialize;
+use serde_dhall::{from_str, serialize, to_dhall, SimpleType, StaticType};
+
+#[test]
+fn test_ser_typed() {
+    fn print<T: Serialize + StaticType>(x: &T) -> String {
+        serialize(x).static_type_annotation().to_string().unwrap()
+    }
+
+    assert_eq!(print(&true), "True");
+
+    assert_eq!(print(&1u64), "1");
+    assert_eq!(print(&1u32), "1");
+    assert_eq!(print(&1usize), "1");
+
+    assert_eq!(print(&1i64), "+1");
+    assert_eq!(print(&-1i32), "-1");
+    assert_eq!(print(&1isize), "+1");
+
+    assert_eq!(print(&1.0f64), "1.0");
+    assert_eq!(print(&1.0f32), "1.0");
+
+    assert_eq!(print(&"foo".to_owned()), r#""foo""#);
+    assert_eq!(print(&<Vec<u64>>::new()), "[] : List Natural");
+    assert_eq!(print(&vec![1u64, 2]), "[1, 2]");
+    assert_eq!(print(&None::<u64>), "None Natural");
+    assert_eq!(print(&Some(1u64)), "Some 1");
+
+    assert_eq!(
+        print(&(1u64, "foo".to_owned())),
+        r#"{ `_1` = 1, `_2` = "foo" }"#
+    );
+
+    #[derive(Serialize, StaticType)]
+    struct Foo {
+        x: u64,
+        y: i64,
+    }
+    assert_eq!(print(&Foo { x: 1, y: -2 }), "{ x = 1, y = -2 }");
+
+    #[derive(Serialize, StaticType)]
+    enum Bar {
+        X(u64),
+        Y(i64),
+    }
+    assert_eq!(print(&Bar::X(1)), "< X: Natural | Y: Integer >.X 1");
+    assert_eq!(print(&Bar::Y(-1)), "< X: Natural | Y: Integer >.Y -1");
+
+    #[derive(Serialize, StaticType)]
+    enum Baz {
+        X,
+        Y(i64),
+    }
+    assert_eq!(print(&Baz::X), "< X | Y: Integer >.X");
+    assert_eq!(print(&Baz::Y(1)), "< X | Y: Integer >.Y +1");
+}
+
+#[test]
+fn test_ser_untyped() {
+    use std::collections::BTreeMap;
+
+    fn print<T: Serialize>(x: &T) -> String {
+        serialize(x).to_string().unwrap()
+    }
+
+    assert_eq!(print(&1u64), "1");
+    assert_eq!(print(&1i64), "+1");
+    assert_eq!(print(&vec![1u64, 2]), "[1, 2]");
+    assert_eq!(print(&Some(1u64)), "Some 1");
+
+    let mut map = BTreeMap::new();
+    map.insert("x".to_string(), 1u64);
+    map.insert("y".to_string(), 2u64);
+    assert_eq!(print(&map), "{ x = 1, y = 2 }");
+
+    // Values that need a type to be printed can't be serialized without an annotation.
+    assert!(serialize(&<Vec<u64>>::new()).to_string().is_err());
+    assert!(serialize(&None::<u64>).to_string().is_err());
+}
+
+#[test]
+fn test_ser_manual_annot() {
+    use std::collections::HashMap;
+
+    let ty: SimpleType = from_str("List { mapKey: Text, mapValue: Natural }")
+        .parse()
+        .unwrap();
+    let mut map = HashMap::new();
+    map.insert("x".to_string(), 1u64);
+    assert_eq!(
+        serialize(&map).type_annotation(&ty).to_string().unwrap(),
+        r#"[{ mapKey = "x", mapValue = 1 }]"#
+    );
+
+    // Naturals can be printed as Integers and vice-versa.
+    assert_eq!(
+        serialize(&1u64)
+            .type_annotation(&SimpleType::Integer)
+            .to_string()
+            .unwrap(),
+        "+1"
+    );
+    assert!(serialize(&-1i64)
+        .type_annotation(&SimpleType::Natural)
+        .to_string()
+        .is_err());
+
+    // Type mismatches are reported.
+    assert!(serialize(&true)
+        .type_annotation(&SimpleType::Natural)
+        .to_string()
+        .is_err());
+}
+
+#[test]
+fn test_ser_to_dhall_derive() {
+    use serde_dhall::ToDhall;
+
+    fn print<T: ToDhall + StaticType>(x: &T) -> String {
+        to_dhall(x).static_type_annotation().to_string().unwrap()
+    }
+
+    #[derive(ToDhall, StaticType)]
+    struct Foo {
+        x: u64,
+        y: Vec<i64>,
+    }
+    assert_eq!(
+        print(&Foo { x: 1, y: vec![] }),
+        "{ x = 1, y = [] : List Integer }"
+    );
+
+    #[derive(ToDhall, StaticType)]
+    struct Pair(bool, String);
+    assert_eq!(
+        print(&Pair(true, "foo".to_owned())),
+        r#"{ `_1` = True, `_2` = "foo" }"#
+    );
+
+    #[derive(ToDhall, StaticType)]
+    enum Bar {
+        X,
+        Y(Foo),
+    }
+    assert_eq!(
+        print(&Bar::X),
+        "< X | Y: { x : Natural, y : List Integer } >.X"
+    );
+    assert_eq!(
+        print(&Bar::Y(Foo { x: 1, y: vec![2] })),
+        "< X | Y: { x : Natural, y : List Integer } >.Y { x = 1, y = [+2] }"
+    );
+    assert!(to_dhall(&Bar::X).to_string().is_err());
+
+    // Containers of derived types are supported too.
+    #[derive(ToDhall, StaticType)]
+    struct Baz {
+        bars: Vec<Bar>,
+        bar: Option<Bar>,
+        pair: (Bar, u64),
+    }
+    assert_eq!(
+        print(&Baz {
+            bars: vec![],
+            bar: Some(Bar::X),
+            pair: (Bar::X, 1),
+        }),
+        "{ bar = Some < X | Y: { x : Natural, y : List Integer } >.X, \
+         bars = [] : List < X | Y: { x : Natural, y : List Integer } >, \
+         pair = { `_1` = < X | Y: { x : Natural, y : List Integer } >.X, \
+         `_2` = 1 } }"
+    );
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("a".to_owned(), vec![Foo { x: 1, y: vec![2] }]);
+    assert_eq!(
+        to_dhall(&map).to_string().unwrap(),
+        "{ a = [{ x = 1, y = [+2] }] }"
+    );
+
+    // A type can derive both `Serialize` and `ToDhall`.
+    #[derive(Serialize, ToDhall, StaticType)]
+    enum Both {
+        A,
+        B(u64),
+    }
+    assert_eq!(
+        serialize(&Both::B(1))
+            .static_type_annotation()
+            .to_string()
+            .unwrap(),
+        "< A | B: Natural >.B 1"
+    );
+    assert_eq!(print(&Both::A), "< A | B: Natural >.A");
+}
+
+#[test]
+fn test_ser_builtin_to_dhall() {
+    use serde_dhall::Value;
+
+    let ty = SimpleType::List(Box::new(SimpleType::Bool));
+    assert_eq!(to_dhall(&ty).to_string().unwrap(), "List Bool");
+
+    let val: Value = from_str("{ x = 1 + 1 }").parse().unwrap();
+    assert_eq!(to_dhall(&val).to_string().unwrap(), "{ x = 2 }");
+    assert!(to_dhall(&val)
+        .type_annotation(&SimpleType::Natural)
+        .to_string()
+        .is_err());
+}
@@ -0,0 +1,73 @@
+use serde_dhall::{from_str, Value, ValueKind};
+
+fn parse(s: &str) -> Value {
+    from_str(s).parse().unwrap()
+}
+
+#[test]
+fn test_value_kind() {
+    assert_eq!(parse("True").kind(), ValueKind::Bool(true));
+    assert_eq!(parse("1 + 1").kind(), ValueKind::Natural(2));
+    assert_eq!(parse("-2").kind(), ValueKind::Integer(-2));
+    assert_eq!(parse("1.5").kind(), ValueKind::Double(1.5));
+    assert_eq!(parse(r#""foo""#).kind(), ValueKind::Text("foo".to_owned()));
+    assert_eq!(parse("None Natural").kind(), ValueKind::Optional(None));
+    assert_eq!(parse("[] : List Bool").kind(), ValueKind::List(vec![]));
+    assert_eq!(
+        parse("< A | B: Natural >.A").kind(),
+        ValueKind::Union("A".to_owned(), None)
+    );
+    assert_eq!(
+        parse("< A | B: Natural >.B 1").kind(),
+        ValueKind::Union("B".to_owned(), Some(parse("1")))
+    );
+    assert_eq!(parse("Natural").kind(), ValueKind::Opaque);
+    assert_eq!(parse("Natural/even").kind(), ValueKind::Opaque);
+}
+
+#[test]
+fn test_value_accessors() {
+    let value = parse(
+        r#"{ a = { b = [ 1, 2 ] }, c = Some "x", m = toMap { k = True } }"#,
+    );
+
+    assert_eq!(value.pointer("a.b.1").and_then(|v| v.as_natural()), Some(2));
+    assert_eq!(value.pointer(""), Some(value.clone()));
+    assert!(value.pointer("a.b.2").is_none());
+    assert!(value.pointer("a.c").is_none());
+    assert!(value.pointer("a.b.x").is_none());
+
+    assert_eq!(
+        value.get("c").and_then(|v| v.as_optional()),
+        Some(Some(parse(r#""x""#)))
+    );
+    assert_eq!(value.get("a").and_then(|v| v.as_natural()), None);
+    assert_eq!(
+        value.pointer("m.0.mapKey").and_then(|v| v.as_text()),
+        Some("k".to_owned())
+    );
+    assert_eq!(value.as_record().map(|kvs| kvs.len()), Some(3));
+    assert_eq!(value.as_list(), None);
+}
+
+#[test]
+#[cfg(feature = "yaml")]
+fn test_json_to_expr() {
+    let ty: serde_dhall::SimpleType = from_str(
+        "{ x : List { mapKey : Text, mapValue : Natural }, y : < A | B : Bool > }",
+    )
+    .parse()
+    .unwrap();
+    let expr = ty
+        .yaml_to_expr("x:\n  a: 1\ny: true\n")
+        .unwrap()
+        .to_string();
+    let value = parse(&expr);
+    assert_eq!(value.pointer("x.0.mapValue").unwrap().as_natural(), Some(1));
+    assert_eq!(
+        value.get("y").unwrap().as_union().unwrap().0,
+        "B".to_string()
+    );
+
+    assert!(ty.json_to_expr(&serde_json::json!({ "x": 1 })).is_err());
+}